@@ -0,0 +1,419 @@
+//! Files stored in zip and tar archives
+//!
+//! Archives are read in place: their members are listed from the zip central
+//! directory or by scanning the tar entries, and read one at a time without
+//! being extracted to disk. A [`Location`] designates a file either on disk or
+//! inside an archive, so that readers do not need to tell them apart.
+use crate::croissant::compression::Compression;
+use crate::croissant::errors::{Error, Result};
+use crate::croissant::utils::sha256_reader;
+use std::collections::HashMap;
+use std::fs::File;
+use std::io::{BufReader, Cursor, Read};
+use std::path::{Path, PathBuf};
+
+/// Archive formats that files can be read from
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum ArchiveFormat {
+    Zip,
+    Tar,
+    /// Gzip-compressed tar archive (`.tar.gz` or `.tgz`)
+    TarGz,
+}
+
+impl ArchiveFormat {
+    /// Format of an archive, from its file name
+    pub fn from_path(path: &Path) -> Option<Self> {
+        Self::from_name(&path.file_name()?.to_string_lossy())
+    }
+
+    /// Format of an archive, from a file name or URL
+    pub fn from_name(name: &str) -> Option<Self> {
+        let name = name.to_lowercase();
+        if name.ends_with(".zip") {
+            Some(Self::Zip)
+        } else if name.ends_with(".tar") {
+            Some(Self::Tar)
+        } else if name.ends_with(".tar.gz") || name.ends_with(".tgz") {
+            Some(Self::TarGz)
+        } else {
+            None
+        }
+    }
+
+    /// Encoding format (MIME type) of archives of this format
+    pub fn encoding_format(self) -> &'static str {
+        match self {
+            Self::Zip => "application/zip",
+            Self::Tar => "application/x-tar",
+            Self::TarGz => "application/gzip",
+        }
+    }
+
+    /// File name of an archive without its extension
+    pub fn stem(self, name: &str) -> &str {
+        let extension = match self {
+            Self::Zip | Self::Tar => 4,
+            Self::TarGz if name.to_lowercase().ends_with(".tgz") => 4,
+            Self::TarGz => 7,
+        };
+        &name[..name.len().saturating_sub(extension)]
+    }
+}
+
+/// A file stored in an archive
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ArchiveMember {
+    /// Path in the archive, with `/` separators
+    pub path: String,
+    /// Uncompressed size in bytes
+    pub size: u64,
+}
+
+/// List the files of an archive, in archive order
+///
+/// Directories, links and members whose path escapes the archive (absolute
+/// or with `..` components) are skipped.
+pub fn list_members(archive: &Path, format: ArchiveFormat) -> Result<Vec<ArchiveMember>> {
+    let mut members = Vec::new();
+    match format {
+        ArchiveFormat::Zip => {
+            let mut zip = open_zip(archive)?;
+            for i in 0..zip.len() {
+                let file = zip.by_index_raw(i)?;
+                if let Some(path) = file.is_file().then(|| member_path(file.name())).flatten() {
+                    members.push(ArchiveMember {
+                        path,
+                        size: file.size(),
+                    });
+                }
+            }
+        }
+        ArchiveFormat::Tar | ArchiveFormat::TarGz => {
+            let mut tar = open_tar(archive, format)?;
+            for entry in tar.entries()? {
+                let entry = entry?;
+                if !entry.header().entry_type().is_file() {
+                    continue;
+                }
+                if let Some(path) = member_path(&entry.path()?.to_string_lossy()) {
+                    members.push(ArchiveMember {
+                        path,
+                        size: entry.size(),
+                    });
+                }
+            }
+        }
+    }
+    Ok(members)
+}
+
+/// Read every file of an archive in a single pass
+///
+/// `visit` is called with the path and content of each file listed by
+/// [`list_members`], in archive order.
+pub fn for_each_member(
+    archive: &Path,
+    format: ArchiveFormat,
+    mut visit: impl FnMut(&str, &mut dyn Read) -> Result<()>,
+) -> Result<()> {
+    match format {
+        ArchiveFormat::Zip => {
+            let mut zip = open_zip(archive)?;
+            for i in 0..zip.len() {
+                let mut file = zip.by_index(i)?;
+                if let Some(path) = file.is_file().then(|| member_path(file.name())).flatten() {
+                    visit(&path, &mut file)?;
+                }
+            }
+        }
+        ArchiveFormat::Tar | ArchiveFormat::TarGz => {
+            let mut tar = open_tar(archive, format)?;
+            for entry in tar.entries()? {
+                let mut entry = entry?;
+                if !entry.header().entry_type().is_file() {
+                    continue;
+                }
+                if let Some(path) = member_path(&entry.path()?.to_string_lossy()) {
+                    visit(&path, &mut entry)?;
+                }
+            }
+        }
+    }
+    Ok(())
+}
+
+/// Read a file of an archive into memory
+pub fn read_member(archive: &Path, format: ArchiveFormat, member: &str) -> Result<Vec<u8>> {
+    let mut content = None;
+    match format {
+        ArchiveFormat::Zip => {
+            let mut zip = open_zip(archive)?;
+            if let Ok(mut file) = zip.by_name(member) {
+                let mut buffer = Vec::with_capacity(file.size() as usize);
+                file.read_to_end(&mut buffer)?;
+                content = Some(buffer);
+            }
+        }
+        ArchiveFormat::Tar | ArchiveFormat::TarGz => {
+            // Entries are scanned in order and the scan stops at the member
+            let mut tar = open_tar(archive, format)?;
+            for entry in tar.entries()? {
+                let mut entry = entry?;
+                if entry.header().entry_type().is_file()
+                    && member_path(&entry.path()?.to_string_lossy()).as_deref() == Some(member)
+                {
+                    let mut buffer = Vec::with_capacity(entry.size() as usize);
+                    entry.read_to_end(&mut buffer)?;
+                    content = Some(buffer);
+                    break;
+                }
+            }
+        }
+    }
+    content.ok_or_else(|| Error::file_not_found(archive.join(member)))
+}
+
+/// Where a file of a dataset is stored
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum Location {
+    /// A file on disk
+    File(PathBuf),
+    /// A compressed file on disk, read decompressed
+    Compressed {
+        path: PathBuf,
+        compression: Compression,
+    },
+    /// A file inside an archive on disk
+    Member {
+        archive: PathBuf,
+        format: ArchiveFormat,
+        path: String,
+    },
+}
+
+impl Location {
+    /// Open the file for reading
+    ///
+    /// Archive members are read into memory first.
+    pub fn open(&self) -> Result<Box<dyn Read>> {
+        match self {
+            Self::File(path) => {
+                let file = File::open(path).map_err(|_| Error::file_not_found(path))?;
+                Ok(Box::new(BufReader::new(file)))
+            }
+            Self::Compressed { path, compression } => {
+                let file = File::open(path).map_err(|_| Error::file_not_found(path))?;
+                compression.decoder(file)
+            }
+            Self::Member { .. } => Ok(Box::new(Cursor::new(self.read()?))),
+        }
+    }
+
+    /// Read the whole content of the file, decompressed
+    pub fn read(&self) -> Result<Vec<u8>> {
+        match self {
+            Self::File(path) => std::fs::read(path).map_err(|_| Error::file_not_found(path)),
+            Self::Compressed { .. } => {
+                let mut content = Vec::new();
+                self.open()?.read_to_end(&mut content)?;
+                Ok(content)
+            }
+            Self::Member {
+                archive,
+                format,
+                path,
+            } => read_member(archive, *format, path),
+        }
+    }
+
+    /// Last component of the path of the file
+    pub fn file_name(&self) -> String {
+        match self {
+            Self::File(path) | Self::Compressed { path, .. } => path
+                .file_name()
+                .unwrap_or_default()
+                .to_string_lossy()
+                .to_string(),
+            Self::Member { path, .. } => path.rsplit('/').next().unwrap_or(path).to_string(),
+        }
+    }
+}
+
+impl std::fmt::Display for Location {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::File(path) | Self::Compressed { path, .. } => write!(f, "{}", path.display()),
+            Self::Member { archive, path, .. } => write!(f, "{}/{path}", archive.display()),
+        }
+    }
+}
+
+/// SHA-256 of each file as stored, reading every archive once
+///
+/// Compressed files are hashed as distributed, without decompressing them.
+pub fn sha256_all(locations: &[Location]) -> Result<Vec<String>> {
+    let mut members = HashMap::<(&Path, ArchiveFormat), HashMap<&str, Option<String>>>::new();
+    for location in locations {
+        if let Location::Member {
+            archive,
+            format,
+            path,
+        } = location
+        {
+            members
+                .entry((archive, *format))
+                .or_default()
+                .insert(path, None);
+        }
+    }
+    for ((archive, format), digests) in &mut members {
+        for_each_member(archive, *format, |path, content| {
+            if let Some(digest) = digests.get_mut(path) {
+                *digest = Some(sha256_reader(content)?);
+            }
+            Ok(())
+        })?;
+    }
+
+    locations
+        .iter()
+        .map(|location| match location {
+            Location::File(path) | Location::Compressed { path, .. } => {
+                let file = File::open(path).map_err(|_| Error::file_not_found(path))?;
+                sha256_reader(file)
+            }
+            Location::Member {
+                archive,
+                format,
+                path,
+            } => members[&(archive.as_path(), *format)][path.as_str()]
+                .clone()
+                .ok_or_else(|| Error::file_not_found(archive.join(path))),
+        })
+        .collect()
+}
+
+fn open_zip(archive: &Path) -> Result<zip::ZipArchive<BufReader<File>>> {
+    let file = File::open(archive).map_err(|_| Error::file_not_found(archive))?;
+    Ok(zip::ZipArchive::new(BufReader::new(file))?)
+}
+
+fn open_tar(archive: &Path, format: ArchiveFormat) -> Result<tar::Archive<Box<dyn Read>>> {
+    let file = BufReader::new(File::open(archive).map_err(|_| Error::file_not_found(archive))?);
+    let reader: Box<dyn Read> = match format {
+        ArchiveFormat::TarGz => Compression::Gzip.decoder(file)?,
+        _ => Box::new(file),
+    };
+    Ok(tar::Archive::new(reader))
+}
+
+/// Normalized path of a member, `None` when it escapes the archive
+fn member_path(name: &str) -> Option<String> {
+    let mut components = Vec::new();
+    for component in name.split(['/', '\\']) {
+        match component {
+            "" | "." => {}
+            ".." => return None,
+            component => components.push(component),
+        }
+    }
+    if name.starts_with(['/', '\\']) || components.is_empty() {
+        return None;
+    }
+    Some(components.join("/"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Write;
+
+    fn write_zip(path: &Path, members: &[(&str, &str)]) {
+        let mut zip = zip::ZipWriter::new(File::create(path).unwrap());
+        zip.add_directory("data/", zip::write::SimpleFileOptions::default())
+            .unwrap();
+        for (name, content) in members {
+            zip.start_file(*name, zip::write::SimpleFileOptions::default())
+                .unwrap();
+            zip.write_all(content.as_bytes()).unwrap();
+        }
+        zip.finish().unwrap();
+    }
+
+    fn write_tar_gz(path: &Path, members: &[(&str, &str)]) {
+        let encoder =
+            flate2::write::GzEncoder::new(File::create(path).unwrap(), Default::default());
+        let mut tar = tar::Builder::new(encoder);
+        for (name, content) in members {
+            let mut header = tar::Header::new_gnu();
+            header.set_size(content.len() as u64);
+            header.set_mode(0o644);
+            header.set_cksum();
+            tar.append_data(&mut header, name, content.as_bytes())
+                .unwrap();
+        }
+        tar.into_inner().unwrap().finish().unwrap();
+    }
+
+    #[test]
+    fn formats_are_recognised_by_name() {
+        assert_eq!(ArchiveFormat::from_name("a.ZIP"), Some(ArchiveFormat::Zip));
+        assert_eq!(
+            ArchiveFormat::from_name("https://host/a.tgz"),
+            Some(ArchiveFormat::TarGz)
+        );
+        assert_eq!(ArchiveFormat::from_name("a.csv.gz"), None);
+        assert_eq!(ArchiveFormat::TarGz.stem("images.tar.gz"), "images");
+        assert_eq!(ArchiveFormat::TarGz.stem("images.tgz"), "images");
+        assert_eq!(member_path("./data//a.csv"), Some("data/a.csv".to_string()));
+        assert_eq!(member_path("../a.csv"), None);
+        assert_eq!(member_path("/etc/passwd"), None);
+    }
+
+    #[test]
+    fn zip_and_tar_members_are_read_in_place() {
+        let dir = tempfile::tempdir().unwrap();
+        let members = [("data/a.csv", "x\n1\n"), ("b.txt", "hello")];
+        let zip = dir.path().join("files.zip");
+        write_zip(&zip, &members);
+        let tar = dir.path().join("files.tar.gz");
+        write_tar_gz(&tar, &members);
+
+        for (archive, format) in [(zip, ArchiveFormat::Zip), (tar, ArchiveFormat::TarGz)] {
+            let listed = list_members(&archive, format).unwrap();
+            assert_eq!(
+                listed,
+                vec![
+                    ArchiveMember {
+                        path: "data/a.csv".to_string(),
+                        size: 4
+                    },
+                    ArchiveMember {
+                        path: "b.txt".to_string(),
+                        size: 5
+                    },
+                ]
+            );
+            assert_eq!(read_member(&archive, format, "b.txt").unwrap(), b"hello");
+            assert!(read_member(&archive, format, "c.txt").is_err());
+
+            let location = Location::Member {
+                archive: archive.clone(),
+                format,
+                path: "data/a.csv".to_string(),
+            };
+            assert_eq!(location.file_name(), "a.csv");
+            let mut content = String::new();
+            location
+                .open()
+                .unwrap()
+                .read_to_string(&mut content)
+                .unwrap();
+            assert_eq!(content, "x\n1\n");
+
+            let digests = sha256_all(std::slice::from_ref(&location)).unwrap();
+            assert_eq!(digests, vec![sha256_reader(&b"x\n1\n"[..]).unwrap()]);
+        }
+    }
+}
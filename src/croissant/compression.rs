@@ -0,0 +1,163 @@
+//! Transparent decompression of gzip, zstd, bzip2 and xz files
+//!
+//! Compressed files are described as distributed: their checksums and sizes
+//! are those of the compressed bytes, their `encodingFormat` is that of the
+//! decompressed content and their compression is declared with
+//! `dcat:compressFormat`. Readers decompress them on the fly.
+use crate::croissant::errors::{Error, Result};
+use std::fs::File;
+use std::io::{BufReader, Read};
+use std::path::Path;
+
+/// Compression formats of single files
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum Compression {
+    Gzip,
+    Zstd,
+    Bzip2,
+    Xz,
+}
+
+impl Compression {
+    /// Compression of a file, from its extension
+    pub fn from_path(path: &Path) -> Option<Self> {
+        Self::from_name(&path.file_name()?.to_string_lossy())
+    }
+
+    /// Compression of a file, from the extension of a file name or URL
+    pub fn from_name(name: &str) -> Option<Self> {
+        let (_, extension) = name.rsplit_once('.')?;
+        match extension.to_lowercase().as_str() {
+            "gz" | "gzip" => Some(Self::Gzip),
+            "zst" | "zstd" => Some(Self::Zstd),
+            "bz2" => Some(Self::Bzip2),
+            "xz" => Some(Self::Xz),
+            _ => None,
+        }
+    }
+
+    /// Compression declared by an encoding format (MIME type)
+    pub fn from_encoding_format(encoding_format: &str) -> Option<Self> {
+        match encoding_format {
+            "application/gzip" | "application/x-gzip" => Some(Self::Gzip),
+            "application/zstd" => Some(Self::Zstd),
+            "application/x-bzip2" => Some(Self::Bzip2),
+            "application/x-xz" => Some(Self::Xz),
+            _ => None,
+        }
+    }
+
+    /// Encoding format (MIME type) of the compressed files
+    pub fn encoding_format(self) -> &'static str {
+        match self {
+            Self::Gzip => "application/gzip",
+            Self::Zstd => "application/zstd",
+            Self::Bzip2 => "application/x-bzip2",
+            Self::Xz => "application/x-xz",
+        }
+    }
+
+    /// Decompress what a reader yields
+    pub fn decoder<'a>(self, reader: impl Read + 'a) -> Result<Box<dyn Read + 'a>> {
+        let reader = BufReader::new(reader);
+        Ok(match self {
+            Self::Gzip => Box::new(flate2::read::MultiGzDecoder::new(reader)),
+            Self::Zstd => Box::new(zstd::Decoder::with_buffer(reader)?),
+            Self::Bzip2 => Box::new(bzip2::read::MultiBzDecoder::new(reader)),
+            Self::Xz => Box::new(xz2::read::XzDecoder::new_multi_decoder(reader)),
+        })
+    }
+}
+
+/// File name without its compression extension (`data.csv.gz` → `data.csv`)
+pub fn decompressed_name(name: &str) -> &str {
+    match Compression::from_name(name) {
+        Some(_) => name.rsplit_once('.').map_or(name, |(stem, _)| stem),
+        None => name,
+    }
+}
+
+/// Open a file, decompressing it when its extension is that of a compression
+/// format
+pub fn open_decompressed(path: &Path) -> Result<Box<dyn Read>> {
+    let file = File::open(path).map_err(|_| Error::file_not_found(path))?;
+    match Compression::from_path(path) {
+        Some(compression) => compression.decoder(file),
+        None => Ok(Box::new(BufReader::new(file))),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Write;
+
+    fn compress(compression: Compression, content: &[u8]) -> Vec<u8> {
+        match compression {
+            Compression::Gzip => {
+                let mut encoder = flate2::write::GzEncoder::new(Vec::new(), Default::default());
+                encoder.write_all(content).unwrap();
+                encoder.finish().unwrap()
+            }
+            Compression::Zstd => zstd::encode_all(content, 0).unwrap(),
+            Compression::Bzip2 => {
+                let mut encoder = bzip2::write::BzEncoder::new(Vec::new(), Default::default());
+                encoder.write_all(content).unwrap();
+                encoder.finish().unwrap()
+            }
+            Compression::Xz => {
+                let mut encoder = xz2::write::XzEncoder::new(Vec::new(), 6);
+                encoder.write_all(content).unwrap();
+                encoder.finish().unwrap()
+            }
+        }
+    }
+
+    #[test]
+    fn compressions_are_recognised_by_name_and_encoding_format() {
+        assert_eq!(
+            Compression::from_name("data.csv.GZ"),
+            Some(Compression::Gzip)
+        );
+        assert_eq!(Compression::from_name("data.csv"), None);
+        assert_eq!(decompressed_name("data.csv.zst"), "data.csv");
+        assert_eq!(decompressed_name("data.csv"), "data.csv");
+        for compression in [
+            Compression::Gzip,
+            Compression::Zstd,
+            Compression::Bzip2,
+            Compression::Xz,
+        ] {
+            assert_eq!(
+                Compression::from_encoding_format(compression.encoding_format()),
+                Some(compression)
+            );
+        }
+    }
+
+    #[test]
+    fn compressed_files_are_read_decompressed() {
+        let dir = tempfile::tempdir().unwrap();
+        for (compression, extension) in [
+            (Compression::Gzip, "gz"),
+            (Compression::Zstd, "zst"),
+            (Compression::Bzip2, "bz2"),
+            (Compression::Xz, "xz"),
+        ] {
+            let path = dir.path().join(format!("data.csv.{extension}"));
+            // Concatenated streams are read as one
+            let content = [
+                compress(compression, b"a,b\n"),
+                compress(compression, b"1,2\n"),
+            ]
+            .concat();
+            std::fs::write(&path, content).unwrap();
+            let mut read = String::new();
+            open_decompressed(&path)
+                .unwrap()
+                .read_to_string(&mut read)
+                .unwrap();
+            assert_eq!(read, "a,b\n1,2\n", "{compression:?}");
+        }
+    }
+}
@@ -0,0 +1,423 @@
+//! Dataset configuration files for `generate`
+//!
+//! A configuration declares what cannot be inferred from the data (name,
+//! description, license, creators, ...) and corrects what was inferred
+//! (column descriptions, data types and keys). It is merged over the
+//! generated metadata.
+use crate::croissant::core::{
+    Agent, DataType, LangString, Metadata, MetadataContext, Organization, Person, Ref,
+    ResponsibleAi, Text,
+};
+use crate::croissant::errors::{Error, Result};
+use garde::Validate;
+use serde::Deserialize;
+use std::collections::BTreeMap;
+use std::path::Path;
+
+/// Dataset properties merged over generated metadata
+///
+/// Read from TOML, YAML or JSON with [`GenerateConfig::from_file`]:
+///
+/// ```toml
+/// name = "titanic"
+/// description = "Passengers of the Titanic"
+/// license = "https://creativecommons.org/licenses/by/4.0/"
+/// keywords = ["shipwreck", "survival"]
+/// key = ["PassengerId"]
+///
+/// [[creators]]
+/// name = "Jane Doe"
+/// email = "jane@example.org"
+///
+/// [columns.Survived]
+/// description = "Whether the passenger survived"
+/// data_type = "sc:Boolean"
+/// ```
+#[derive(Debug, Clone, Default, PartialEq, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct GenerateConfig {
+    pub name: Option<String>,
+    pub description: Option<String>,
+    pub version: Option<String>,
+    pub url: Option<String>,
+    #[serde(deserialize_with = "one_or_many")]
+    pub license: Vec<String>,
+    #[serde(alias = "creator")]
+    pub creators: Vec<CreatorConfig>,
+    #[serde(alias = "cite_as", alias = "citeAs")]
+    pub citation: Option<String>,
+    pub keywords: Vec<String>,
+    /// Columns forming the key of the record set that holds them
+    #[serde(deserialize_with = "one_or_many")]
+    pub key: Vec<String>,
+    /// Overrides of the inferred fields, by column name
+    pub columns: BTreeMap<String, ColumnConfig>,
+    /// Responsible AI properties, with or without the `rai:` prefix
+    #[serde(deserialize_with = "responsible_ai_section")]
+    pub rai: ResponsibleAi,
+}
+
+/// Creator of the dataset
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct CreatorConfig {
+    pub name: String,
+    #[serde(default)]
+    pub email: Option<String>,
+    #[serde(default)]
+    pub url: Option<String>,
+    #[serde(rename = "type", default)]
+    pub kind: CreatorKind,
+}
+
+/// Whether a creator is a person or an organization
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum CreatorKind {
+    #[default]
+    Person,
+    Organization,
+}
+
+/// Overrides of an inferred field
+#[derive(Debug, Clone, Default, PartialEq, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct ColumnConfig {
+    pub description: Option<String>,
+    /// Replaces the inferred data type, e.g. `sc:Boolean` or `Boolean`
+    #[serde(alias = "dataType")]
+    pub data_type: Option<String>,
+}
+
+fn one_or_many<'de, D>(deserializer: D) -> std::result::Result<Vec<String>, D::Error>
+where
+    D: serde::Deserializer<'de>,
+{
+    #[derive(Deserialize)]
+    #[serde(untagged)]
+    enum OneOrMany {
+        One(String),
+        Many(Vec<String>),
+    }
+    Ok(match OneOrMany::deserialize(deserializer)? {
+        OneOrMany::One(value) => vec![value],
+        OneOrMany::Many(values) => values,
+    })
+}
+
+fn responsible_ai_section<'de, D>(deserializer: D) -> std::result::Result<ResponsibleAi, D::Error>
+where
+    D: serde::Deserializer<'de>,
+{
+    let properties = serde_json::Map::deserialize(deserializer)?;
+    responsible_ai(properties).map_err(serde::de::Error::custom)
+}
+
+/// Read Responsible AI properties from an object of RAI property names
+///
+/// Unknown names are rejected, so that misspelled properties are not dropped
+/// silently, and values are validated.
+pub(crate) fn responsible_ai(
+    properties: serde_json::Map<String, serde_json::Value>,
+) -> Result<ResponsibleAi> {
+    let rai: ResponsibleAi = serde_json::from_value(properties.clone().into())?;
+
+    let known = serde_json::to_value(&rai)?;
+    let unknown = properties.iter().find(|(key, value)| {
+        let key = format!("rai:{}", key.trim_start_matches("rai:"));
+        let empty = value.is_null() || value.as_array().is_some_and(Vec::is_empty);
+        !empty && known.get(&key).is_none()
+    });
+    if let Some((key, _)) = unknown {
+        return Err(Error::invalid_format(format!(
+            "Unknown Responsible AI property '{key}'"
+        )));
+    }
+
+    rai.validate_with(&MetadataContext::default())?;
+    Ok(rai)
+}
+
+impl GenerateConfig {
+    /// Read a configuration file
+    ///
+    /// The format follows the extension: `.toml`, `.yaml` or `.yml`, and
+    /// JSON otherwise.
+    pub fn from_file(path: &Path) -> Result<Self> {
+        let content = std::fs::read_to_string(path).map_err(|_| Error::file_not_found(path))?;
+        let extension = path.extension().map(|e| e.to_string_lossy().to_lowercase());
+        Ok(match extension.as_deref() {
+            Some("toml") => toml::from_str(&content)?,
+            Some("yaml" | "yml") => serde_yaml::from_str(&content)?,
+            _ => serde_json::from_str(&content)?,
+        })
+    }
+
+    /// Merge the configuration over generated metadata
+    ///
+    /// Columns are matched by name against the top-level fields of every
+    /// record set. Columns and keys that match no field are errors, since
+    /// they most likely come from a typo or a stale configuration.
+    pub fn apply(&self, metadata: &mut Metadata) -> Result<()> {
+        if let Some(name) = &self.name {
+            metadata.name = LangString::new(name.as_str());
+        }
+        if let Some(description) = &self.description {
+            metadata.description = LangString::new(description.as_str());
+        }
+        if let Some(version) = &self.version {
+            metadata.version = Text::new(version.as_str());
+        }
+        if let Some(url) = &self.url {
+            metadata.url = Some(url.clone());
+        }
+        if let Some(citation) = &self.citation {
+            metadata.cite_as = Some(LangString::new(citation.as_str()));
+        }
+        if !self.license.is_empty() {
+            metadata.license = self
+                .license
+                .iter()
+                .map(|l| Text::new(l.as_str()).into())
+                .collect();
+        }
+        if !self.keywords.is_empty() {
+            metadata.keywords = self
+                .keywords
+                .iter()
+                .map(|k| LangString::new(k.as_str()))
+                .collect();
+        }
+        if !self.creators.is_empty() {
+            metadata.creator = self
+                .creators
+                .iter()
+                .map(CreatorConfig::agent)
+                .collect::<Result<_>>()?;
+        }
+        if !self.rai.is_empty() {
+            metadata.rai = self.rai.clone();
+        }
+
+        for (column, config) in &self.columns {
+            let mut found = false;
+            let fields = metadata
+                .record_sets
+                .iter_mut()
+                .flat_map(|rs| rs.fields.iter_mut())
+                .filter(|field| field.name.0 == column.as_str());
+            for field in fields {
+                found = true;
+                if let Some(description) = &config.description {
+                    field.description = LangString::new(description.as_str());
+                }
+                if let Some(data_type) = &config.data_type {
+                    field.data_types = vec![parse_data_type(data_type)];
+                }
+            }
+            if !found {
+                return Err(Error::invalid_format(format!(
+                    "Column '{column}' of the configuration does not exist"
+                )));
+            }
+        }
+
+        if !self.key.is_empty() {
+            let mut found = false;
+            for record_set in &mut metadata.record_sets {
+                let keys = self
+                    .key
+                    .iter()
+                    .map(|column| {
+                        let field = record_set
+                            .fields
+                            .iter()
+                            .find(|f| f.name.0 == column.as_str());
+                        field.map(|f| Ref { id: f.id.clone() })
+                    })
+                    .collect::<Option<Vec<_>>>();
+                if let Some(keys) = keys {
+                    record_set.keys = keys;
+                    found = true;
+                }
+            }
+            if !found {
+                return Err(Error::invalid_format(format!(
+                    "No record set has the key columns {}",
+                    self.key.join(", ")
+                )));
+            }
+        }
+
+        Ok(())
+    }
+}
+
+impl CreatorConfig {
+    fn agent(&self) -> Result<Agent> {
+        let name = Text::new(self.name.as_str());
+        Ok(match self.kind {
+            CreatorKind::Person => Agent::Person(
+                Person::builder()
+                    .name(name)
+                    .email(self.email.clone())
+                    .url(self.url.clone())
+                    .build()
+                    .map_err(|e| Error::Builder(e.to_string()))?,
+            ),
+            CreatorKind::Organization => Agent::Organization(
+                Organization::builder()
+                    .name(name)
+                    .email(self.email.clone())
+                    .url(self.url.clone())
+                    .build()
+                    .map_err(|e| Error::Builder(e.to_string()))?,
+            ),
+        })
+    }
+}
+
+/// Data type of a configuration, the `sc:` or `cr:` prefix being optional
+fn parse_data_type(value: &str) -> DataType {
+    match value {
+        "BoundingBox" | "Split" | "Label" => DataType::from(format!("cr:{value}").as_str()),
+        value if value.contains(':') => DataType::from(value),
+        value => DataType::from(format!("sc:{value}").as_str()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn field(id: &str, data_type: &str) -> serde_json::Value {
+        serde_json::json!({
+            "@type": "cr:Field",
+            "@id": id,
+            "name": id.rsplit('/').next(),
+            "description": id,
+            "dataType": data_type,
+        })
+    }
+
+    /// Metadata with `passengers` (name, gender) and `genders` (label) record sets
+    fn metadata() -> Metadata {
+        crate::croissant::jsonld::parse_metadata(serde_json::json!({
+            "@context": {"@vocab": "https://schema.org/", "cr": "http://mlcommons.org/croissant/"},
+            "@type": "sc:Dataset",
+            "name": "test",
+            "description": "test",
+            "version": "1.0.0",
+            "distribution": [],
+            "recordSet": [
+                {
+                    "@type": "cr:RecordSet",
+                    "@id": "passengers",
+                    "field": [
+                        field("passengers/name", "sc:Text"),
+                        field("passengers/gender", "sc:Text"),
+                    ],
+                },
+                {
+                    "@type": "cr:RecordSet",
+                    "@id": "genders",
+                    "field": [field("genders/label", "sc:Text")],
+                },
+            ],
+        }))
+        .unwrap()
+    }
+
+    #[test]
+    fn configurations_are_read_in_every_format() {
+        let directory = tempfile::tempdir().unwrap();
+        let toml = directory.path().join("dataset.toml");
+        std::fs::write(
+            &toml,
+            "name = \"titanic\"\nlicense = \"afl-3.0\"\nkey = [\"id\"]\n\n\
+             [[creator]]\nname = \"ACME\"\ntype = \"organization\"\n\n\
+             [columns.age]\ndataType = \"Float\"\n\n\
+             [rai]\n\"rai:dataLimitations\" = \"Crew is missing\"\n",
+        )
+        .unwrap();
+        let yaml = directory.path().join("dataset.yml");
+        std::fs::write(
+            &yaml,
+            "name: titanic\nlicense: afl-3.0\nkey: id\ncreators:\n  - name: ACME\n    type: organization\n\
+             columns:\n  age:\n    data_type: Float\nrai:\n  dataLimitations: Crew is missing\n",
+        )
+        .unwrap();
+
+        let config = GenerateConfig::from_file(&toml).unwrap();
+        assert_eq!(config.license, ["afl-3.0"]);
+        assert_eq!(config.key, ["id"]);
+        assert_eq!(config.creators[0].kind, CreatorKind::Organization);
+        assert_eq!(
+            config.rai.data_limitations,
+            vec![Text::new("Crew is missing")]
+        );
+        assert_eq!(GenerateConfig::from_file(&yaml).unwrap(), config);
+
+        std::fs::write(&toml, "nmae = \"titanic\"\n").unwrap();
+        assert!(GenerateConfig::from_file(&toml).is_err());
+        std::fs::write(&toml, "[rai]\ndataLimitation = \"typo\"\n").unwrap();
+        assert!(GenerateConfig::from_file(&toml).is_err());
+    }
+
+    #[test]
+    fn configurations_are_merged_over_metadata() {
+        let config = GenerateConfig {
+            name: Some("titanic".to_string()),
+            license: vec!["https://creativecommons.org/licenses/by/4.0/".to_string()],
+            creators: vec![CreatorConfig {
+                name: "Jane Doe".to_string(),
+                email: Some("jane@example.org".to_string()),
+                url: None,
+                kind: CreatorKind::Person,
+            }],
+            key: vec!["name".to_string()],
+            columns: BTreeMap::from([(
+                "gender".to_string(),
+                ColumnConfig {
+                    description: Some("Gender of the passenger".to_string()),
+                    data_type: Some("URL".to_string()),
+                },
+            )]),
+            ..Default::default()
+        };
+        let mut metadata = metadata();
+        config.apply(&mut metadata).unwrap();
+
+        assert_eq!(metadata.name, Text::new("titanic"));
+        assert_eq!(metadata.description, Text::new("test"));
+        assert!(matches!(&metadata.creator[..], [Agent::Person(_)]));
+        let passengers = &metadata.record_sets[0];
+        assert_eq!(passengers.keys[0].id, Text::new("passengers/name"));
+        let gender = &passengers.fields[1];
+        assert_eq!(gender.description, Text::new("Gender of the passenger"));
+        assert_eq!(gender.data_types, [DataType::Url]);
+    }
+
+    #[test]
+    fn unknown_columns_and_keys_are_errors() {
+        let config = GenerateConfig {
+            columns: BTreeMap::from([("Nmae".to_string(), ColumnConfig::default())]),
+            ..Default::default()
+        };
+        assert!(config.apply(&mut metadata()).is_err());
+
+        let config = GenerateConfig {
+            key: vec!["name".to_string(), "label".to_string()],
+            ..Default::default()
+        };
+        assert!(config.apply(&mut metadata()).is_err());
+    }
+
+    #[test]
+    fn data_types_may_omit_their_prefix() {
+        assert_eq!(parse_data_type("Boolean"), DataType::Boolean);
+        assert_eq!(parse_data_type("sc:Boolean"), DataType::Boolean);
+        assert_eq!(parse_data_type("BoundingBox"), DataType::BoundingBox);
+        assert_eq!(parse_data_type("cr:BoundingBox"), DataType::BoundingBox);
+    }
+}
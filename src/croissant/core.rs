@@ -1,12 +1,10 @@
-use chrono::DateTime;
 use derive_builder::Builder;
 use garde::Validate;
 use serde::de::{self, Deserializer};
 use serde::{Deserialize, Serialize};
-use std::collections::HashSet;
 use std::{borrow::Cow, fmt};
 
-use crate::croissant::{self, errors::Error};
+use crate::croissant;
 
 fn one_or_many<'de, D, T>(deserializer: D) -> Result<Vec<T>, D::Error>
 where
@@ -21,16 +19,336 @@ where
     }
 }
 
+/// A JSON value as reported in type errors
+fn unexpected(value: &serde_json::Value) -> de::Unexpected<'_> {
+    use serde_json::Value;
+    match value {
+        Value::Null => de::Unexpected::Unit,
+        Value::Bool(b) => de::Unexpected::Bool(*b),
+        Value::Number(n) => match (n.as_u64(), n.as_i64()) {
+            (Some(n), _) => de::Unexpected::Unsigned(n),
+            (_, Some(n)) => de::Unexpected::Signed(n),
+            _ => de::Unexpected::Float(n.as_f64().unwrap_or_default()),
+        },
+        Value::String(s) => de::Unexpected::Str(s),
+        Value::Array(_) => de::Unexpected::Seq,
+        Value::Object(_) => de::Unexpected::Map,
+    }
+}
+
+/// Write a single value as itself and several as an array, see [`one_or_many`]
+fn single_or_many<S, T>(values: &[T], serializer: S) -> Result<S::Ok, S::Error>
+where
+    S: serde::Serializer,
+    T: Serialize,
+{
+    match values {
+        [value] => value.serialize(serializer),
+        values => values.serialize(serializer),
+    }
+}
+
+fn optional_single_or_many<S, T>(values: &Option<Vec<T>>, serializer: S) -> Result<S::Ok, S::Error>
+where
+    S: serde::Serializer,
+    T: Serialize,
+{
+    match values {
+        Some(values) => single_or_many(values, serializer),
+        None => serializer.serialize_none(),
+    }
+}
+
+/// Ids given either as plain strings or as `{"@id": ...}` references, one or many
+fn id_refs<'de, D>(deserializer: D) -> Result<Vec<Id>, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    #[derive(Deserialize)]
+    #[serde(untagged)]
+    enum IdOrRef {
+        Id(Id),
+        Ref {
+            #[serde(rename = "@id")]
+            id: Id,
+        },
+    }
+
+    let ids: Vec<IdOrRef> = one_or_many(deserializer)?;
+    Ok(ids
+        .into_iter()
+        .map(|id| match id {
+            IdOrRef::Id(id) | IdOrRef::Ref { id } => id,
+        })
+        .collect())
+}
+
+fn optional_id_refs<'de, D>(deserializer: D) -> Result<Option<Vec<Id>>, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    id_refs(deserializer).map(Some)
+}
+
+/// A non-empty string value
 #[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize, Validate)]
 #[garde(context(MetadataContext))]
 pub struct Text(#[garde(length(min = 1))] pub Cow<'static, str>);
 
 impl Text {
+    /// Create a new text value
     pub fn new(text: impl ToString) -> Self {
         Self(Cow::Owned(text.to_string()))
     }
 }
 
+/// Natural language text, such as a description or keyword
+///
+/// Strings in another language than the default `@language` of the document
+/// carry their language tag, and are written as
+/// `{"@value": ..., "@language": ...}`.
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Validate)]
+#[garde(context(MetadataContext))]
+pub struct LangString {
+    #[garde(dive)]
+    pub text: Text,
+    #[garde(skip)]
+    pub language: Option<String>,
+}
+
+impl LangString {
+    /// Create a new string in the default language of the document
+    pub fn new(text: impl ToString) -> Self {
+        Text::new(text).into()
+    }
+
+    /// Tag the string with a language
+    pub fn with_language(mut self, language: impl ToString) -> Self {
+        self.language = Some(language.to_string());
+        self
+    }
+}
+
+impl From<Text> for LangString {
+    fn from(text: Text) -> Self {
+        Self {
+            text,
+            language: None,
+        }
+    }
+}
+
+impl std::ops::Deref for LangString {
+    type Target = Text;
+
+    fn deref(&self) -> &Text {
+        &self.text
+    }
+}
+
+impl PartialEq<Text> for LangString {
+    fn eq(&self, other: &Text) -> bool {
+        self.language.is_none() && self.text == *other
+    }
+}
+
+impl Serialize for LangString {
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        match &self.language {
+            None => self.text.serialize(serializer),
+            Some(language) => {
+                let mut map = serde_json::Map::new();
+                map.insert("@value".into(), self.text.0.as_ref().into());
+                map.insert("@language".into(), language.as_str().into());
+                map.serialize(serializer)
+            }
+        }
+    }
+}
+
+impl<'de> Deserialize<'de> for LangString {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        #[derive(Deserialize)]
+        struct Tagged {
+            #[serde(rename = "@value")]
+            value: String,
+            #[serde(rename = "@language")]
+            language: Option<String>,
+        }
+
+        match serde_json::Value::deserialize(deserializer)? {
+            serde_json::Value::String(text) => Ok(Self::new(text)),
+            value @ serde_json::Value::Object(_) => {
+                let tagged = Tagged::deserialize(value).map_err(de::Error::custom)?;
+                Ok(Self {
+                    text: Text::new(tagged.value),
+                    language: tagged.language,
+                })
+            }
+            value => Err(de::Error::invalid_type(
+                unexpected(&value),
+                &"a string or a language-tagged string",
+            )),
+        }
+    }
+}
+
+/// Unknown properties of a node, written back with the modelled ones in the
+/// order the node was read
+#[derive(Debug, Clone, Default)]
+pub struct Extensions {
+    properties: serde_json::Map<String, serde_json::Value>,
+    /// Every property of the node as read, modelled or not, in document order
+    order: Vec<String>,
+}
+
+impl Extensions {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Forget the order the node was read in, so that it is written with its
+    /// modelled properties first
+    pub(crate) fn forget_order(&mut self) {
+        self.order.clear();
+    }
+
+    /// Reorder the properties of a written node as read
+    ///
+    /// Properties that were not read follow the property written before them.
+    fn reorder(&self, node: serde_json::Value) -> serde_json::Value {
+        let serde_json::Value::Object(mut written) = node else {
+            return node;
+        };
+        if self.order.is_empty() {
+            return serde_json::Value::Object(written);
+        }
+        let mut keys = self
+            .order
+            .iter()
+            .filter(|key| written.contains_key(*key))
+            .cloned()
+            .collect::<Vec<_>>();
+        let mut previous = None;
+        for key in written.keys() {
+            if !keys.contains(key) {
+                let at = previous
+                    .and_then(|previous| keys.iter().position(|k| k == previous))
+                    .map_or(0, |i| i + 1);
+                keys.insert(at, key.clone());
+            }
+            previous = Some(key);
+        }
+        let ordered = keys
+            .into_iter()
+            .filter_map(|key| written.shift_remove_entry(&key))
+            .collect();
+        serde_json::Value::Object(ordered)
+    }
+}
+
+impl std::ops::Deref for Extensions {
+    type Target = serde_json::Map<String, serde_json::Value>;
+
+    fn deref(&self) -> &Self::Target {
+        &self.properties
+    }
+}
+
+impl std::ops::DerefMut for Extensions {
+    fn deref_mut(&mut self) -> &mut Self::Target {
+        &mut self.properties
+    }
+}
+
+impl From<serde_json::Map<String, serde_json::Value>> for Extensions {
+    fn from(properties: serde_json::Map<String, serde_json::Value>) -> Self {
+        Self {
+            properties,
+            order: Vec::new(),
+        }
+    }
+}
+
+// The order a node was read in does not make it another node
+impl PartialEq for Extensions {
+    fn eq(&self, other: &Self) -> bool {
+        self.properties == other.properties
+    }
+}
+
+impl Serialize for Extensions {
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        self.properties.serialize(serializer)
+    }
+}
+
+impl<'de> Deserialize<'de> for Extensions {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        serde_json::Map::deserialize(deserializer).map(Self::from)
+    }
+}
+
+/// Properties of a node in document order
+fn read_order(node: &serde_json::Value) -> Vec<String> {
+    node.as_object()
+        .map(|properties| properties.keys().cloned().collect())
+        .unwrap_or_default()
+}
+
+/// Serialize nodes through their derived implementation (`#[serde(remote =
+/// "Self")]`) in the order of their properties when they were read
+macro_rules! serialize_in_read_order {
+    ($($node:ty),*) => {$(
+        impl Serialize for $node {
+            fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+                let node = <$node>::serialize(self, serde_json::value::Serializer)
+                    .map_err(serde::ser::Error::custom)?;
+                self.extensions.reorder(node).serialize(serializer)
+            }
+        }
+    )*};
+}
+
+/// Deserialize nodes through their derived implementation, remembering the
+/// order of their properties
+macro_rules! deserialize_in_read_order {
+    ($($node:ty),*) => {$(
+        impl<'de> Deserialize<'de> for $node {
+            fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+                let node = serde_json::Value::deserialize(deserializer)?;
+                let order = read_order(&node);
+                let mut node = <$node>::deserialize(node).map_err(de::Error::custom)?;
+                node.extensions.order = order;
+                Ok(node)
+            }
+        }
+    )*};
+}
+
+serialize_in_read_order!(
+    FileObject,
+    FileSet,
+    FieldSource,
+    Field,
+    RecordSet,
+    Person,
+    Organization,
+    CreativeWork,
+    Metadata
+);
+deserialize_in_read_order!(
+    FileObject,
+    FileSet,
+    Field,
+    RecordSet,
+    Person,
+    Organization,
+    CreativeWork,
+    Metadata
+);
+
+/// Identifier of a node (`@id`)
 pub type Id = Text;
 
 impl fmt::Display for Id {
@@ -39,6 +357,7 @@ impl fmt::Display for Id {
     }
 }
 
+/// Type of the top-level document (`@type`)
 #[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, Validate)]
 #[serde(rename_all = "camelCase")]
 #[garde(context(MetadataContext))]
@@ -47,6 +366,7 @@ pub enum CroissantType {
     Dataset,
 }
 
+/// Node types from the Croissant (`cr:`) vocabulary
 #[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, Validate)]
 #[serde(rename_all = "camelCase")]
 #[garde(context(MetadataContext))]
@@ -73,33 +393,26 @@ impl fmt::Display for CrType {
     }
 }
 
-#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Validate)]
-#[serde(untagged)]
+/// Data type of a field or record set (`dataType`)
+///
+/// Serialized as its compact IRI (e.g. `sc:Integer`); unknown IRIs are kept
+/// as [`DataType::CustomIri`].
+#[derive(Debug, Clone, PartialEq, Validate)]
 #[garde(context(MetadataContext))]
 pub enum DataType {
-    #[serde(rename = "sc:Enumeration")]
     Enumeration,
-    #[serde(rename = "sc:Boolean")]
     Boolean,
-    #[serde(rename = "sc:Integer")]
     Integer,
-    #[serde(rename = "sc:Float")]
     Float,
-    #[serde(rename = "sc:Text")]
     Text,
-    #[serde(rename = "sc:Date")]
     Date,
-    #[serde(rename = "sc:DateTime")]
     DateTime,
-    #[serde(rename = "sc:URL")]
     Url,
-    #[serde(rename = "sc:ImageObject")]
     ImageObject,
-    #[serde(rename = "cr:BoundingBox")]
-    BoundingBox(#[garde(dive)] BoundingBoxFormat),
-    #[serde(rename = "cr:Split")]
+    /// Coordinates of a bounding box, laid out as given by the `format` of
+    /// the field source (see [`ValueFormat::BoundingBox`])
+    BoundingBox,
     Split,
-    #[serde(rename = "cr:Label")]
     Label,
     CustomIri(#[garde(dive)] Text),
 }
@@ -113,56 +426,57 @@ impl fmt::Display for DataType {
             DataType::Float => "sc:Float",
             DataType::Text => "sc:Text",
             DataType::Date => "sc:Date",
-            DataType::DateTime => "sc:Datetime",
-            DataType::Url => "sc:Url",
+            DataType::DateTime => "sc:DateTime",
+            DataType::Url => "sc:URL",
             DataType::ImageObject => "sc:ImageObject",
-            DataType::BoundingBox(bbox) => &format!("{}", bbox),
+            DataType::BoundingBox => "cr:BoundingBox",
             DataType::Split => "cr:Split",
             DataType::Label => "cr:Label",
-            DataType::CustomIri(text) => &format!("{}", text),
+            DataType::CustomIri(text) => &text.0,
         };
         write!(f, "{}", key)
     }
 }
 
-impl From<&String> for DataType {
-    fn from(value: &String) -> Self {
-        let trimmed = value.trim();
-
-        // Try to parse as integer
-        if trimmed.parse::<i64>().is_ok() {
-            return DataType::Integer;
-        }
-
-        // Try to parse as float
-        if trimmed.parse::<f64>().is_ok() {
-            return DataType::Float;
-        }
-
-        // Try to parse as boolean
-        if trimmed.eq_ignore_ascii_case("true") || trimmed.eq_ignore_ascii_case("false") {
-            return DataType::Boolean;
-        }
-
-        // Try to parse as date (YYYY-MM-DD)
-        if chrono::NaiveDate::parse_from_str(trimmed, "%Y-%m-%d").is_ok() {
-            return DataType::Date;
+impl From<&str> for DataType {
+    fn from(iri: &str) -> Self {
+        match iri {
+            "sc:Enumeration" => DataType::Enumeration,
+            "sc:Boolean" => DataType::Boolean,
+            "sc:Integer" => DataType::Integer,
+            "sc:Float" => DataType::Float,
+            "sc:Text" => DataType::Text,
+            "sc:Date" => DataType::Date,
+            "sc:DateTime" => DataType::DateTime,
+            "sc:URL" => DataType::Url,
+            "sc:ImageObject" => DataType::ImageObject,
+            "cr:BoundingBox" => DataType::BoundingBox,
+            "cr:Split" => DataType::Split,
+            "cr:Label" => DataType::Label,
+            other => DataType::CustomIri(Text::new(other)),
         }
+    }
+}
 
-        // Try to parse as ISO 8601 datetime
-        if DateTime::parse_from_rfc3339(trimmed).is_ok() {
-            return DataType::Date;
-        }
+impl Serialize for DataType {
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.collect_str(self)
+    }
+}
 
-        // Default to Text
-        DataType::Text
+impl<'de> Deserialize<'de> for DataType {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let iri = String::deserialize(deserializer)?;
+        Ok(DataType::from(iri.as_str()))
     }
 }
 
-#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, Validate)]
+/// Coordinate layout of a bounding box
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize, Validate)]
 #[garde(context(MetadataContext))]
 pub enum BoundingBoxFormat {
     CenterXywh,
+    #[default]
     Xywh,
     Xyxy,
 }
@@ -178,8 +492,9 @@ impl fmt::Display for BoundingBoxFormat {
     }
 }
 
+/// A distribution entry: either a single file or a set of files
 #[derive(Debug, Clone, PartialEq, Serialize, Deserialize, Validate)]
-#[serde(tag = "@type")]
+#[serde(tag = "@type", remote = "Self")]
 #[garde(context(MetadataContext))]
 pub enum Resource {
     #[serde(rename = "cr:FileObject")]
@@ -191,6 +506,33 @@ pub enum Resource {
     FileSet(#[garde(dive)] FileSet),
 }
 
+// The `@type` tag is not seen by the file object or set, which only orders
+// the properties it was read with
+impl Serialize for Resource {
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let node = Resource::serialize(self, serde_json::value::Serializer)
+            .map_err(serde::ser::Error::custom)?;
+        match self {
+            Self::FileObject(object) => object.extensions.reorder(node),
+            Self::FileSet(set) => set.extensions.reorder(node),
+        }
+        .serialize(serializer)
+    }
+}
+
+impl<'de> Deserialize<'de> for Resource {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let node = serde_json::Value::deserialize(deserializer)?;
+        let order = read_order(&node);
+        let mut resource = Resource::deserialize(node).map_err(de::Error::custom)?;
+        match &mut resource {
+            Self::FileObject(object) => object.extensions.order = order,
+            Self::FileSet(set) => set.extensions.order = order,
+        }
+        Ok(resource)
+    }
+}
+
 impl fmt::Display for Resource {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         let key = match self {
@@ -201,7 +543,9 @@ impl fmt::Display for Resource {
     }
 }
 
+/// A single file of the dataset (`cr:FileObject`)
 #[derive(Debug, Clone, PartialEq, Serialize, Deserialize, Builder, Validate)]
+#[serde(remote = "Self")]
 #[garde(context(MetadataContext))]
 pub struct FileObject {
     #[serde(rename = "@id")]
@@ -209,17 +553,53 @@ pub struct FileObject {
     pub id: Id,
     #[garde(dive)]
     pub name: Text,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    #[garde(dive)]
+    #[builder(default)]
+    pub description: Option<LangString>,
     #[serde(rename = "contentUrl")]
     #[garde(dive)]
     pub content_url: Text,
-    #[serde(rename = "contentSize")]
+    #[serde(rename = "contentSize", skip_serializing_if = "Option::is_none")]
     #[garde(dive)]
+    #[builder(default)]
     pub content_size: Option<Text>,
     #[serde(rename = "encodingFormat")]
     #[garde(dive)]
     pub encoding_format: Text,
+    /// Compression of the file (`application/gzip`, ...), `encodingFormat`
+    /// being that of its decompressed content
+    #[serde(
+        rename = "dcat:compressFormat",
+        alias = "http://www.w3.org/ns/dcat#compressFormat",
+        default,
+        skip_serializing_if = "Option::is_none"
+    )]
+    #[garde(dive)]
+    #[builder(default)]
+    pub compress_format: Option<Text>,
+    /// Dialect of CSV files, when it differs from the usual one
+    #[serde(
+        rename = "csvw:dialect",
+        alias = "http://www.w3.org/ns/csvw#dialect",
+        default,
+        skip_serializing_if = "Option::is_none"
+    )]
+    #[garde(dive)]
+    #[builder(default)]
+    pub dialect: Option<CsvDialect>,
+    #[serde(skip_serializing_if = "Option::is_none")]
     #[garde(inner(pattern(r"^[a-fA-F0-9]{64}$")))]
+    #[builder(default)]
     pub sha256: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    #[garde(inner(pattern(r"^[a-fA-F0-9]{32}$")))]
+    #[builder(default)]
+    pub md5: Option<String>,
+    #[serde(flatten)]
+    #[garde(skip)]
+    #[builder(default)]
+    pub extensions: Extensions,
 }
 
 impl FileObject {
@@ -228,24 +608,166 @@ impl FileObject {
     }
 }
 
+/// A set of homogeneous files, possibly contained in an archive (`cr:FileSet`)
 #[derive(Debug, Clone, PartialEq, Serialize, Deserialize, Builder, Validate)]
+#[serde(remote = "Self")]
 #[garde(context(MetadataContext))]
 pub struct FileSet {
     #[serde(rename = "@id")]
     #[garde(dive)]
     pub id: Id,
-    #[serde(rename = "containedIn")]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    #[garde(dive)]
+    #[builder(default)]
+    pub name: Option<Text>,
+    #[serde(skip_serializing_if = "Option::is_none")]
     #[garde(dive)]
+    #[builder(default)]
+    pub description: Option<LangString>,
+    /// Archives or directories holding the files, the dataset root when empty
+    #[serde(
+        rename = "containedIn",
+        default,
+        deserialize_with = "id_refs",
+        serialize_with = "single_or_many",
+        skip_serializing_if = "Vec::is_empty"
+    )]
+    #[garde(dive)]
+    #[builder(default)]
     pub sources: Vec<Id>,
     #[serde(rename = "encodingFormat")]
     #[garde(dive)]
     pub encoding_format: Text,
+    #[serde(deserialize_with = "one_or_many", serialize_with = "single_or_many")]
     #[garde(dive)]
     pub includes: Vec<Text>,
+    #[serde(
+        deserialize_with = "one_or_many",
+        serialize_with = "single_or_many",
+        default,
+        skip_serializing_if = "Vec::is_empty"
+    )]
     #[garde(dive)]
+    #[builder(default)]
     pub excludes: Vec<Text>,
+    /// Dialect of CSV files, when it differs from the usual one
+    #[serde(
+        rename = "csvw:dialect",
+        alias = "http://www.w3.org/ns/csvw#dialect",
+        default,
+        skip_serializing_if = "Option::is_none"
+    )]
+    #[garde(dive)]
+    #[builder(default)]
+    pub dialect: Option<CsvDialect>,
+    /// Digest over the sorted paths and digests of every file of the set
+    #[serde(skip_serializing_if = "Option::is_none")]
+    #[garde(inner(pattern(r"^[a-fA-F0-9]{64}$")))]
+    #[builder(default)]
+    pub sha256: Option<String>,
+    #[serde(flatten)]
+    #[garde(skip)]
+    #[builder(default)]
+    pub extensions: Extensions,
+}
+
+impl FileSet {
+    pub fn builder() -> FileSetBuilder {
+        FileSetBuilder::default()
+    }
+}
+
+/// How a CSV file is written, with the properties of a CSVW dialect
+/// description (`csvw:Dialect`)
+///
+/// Unset properties take their usual value: UTF-8, comma-separated, quoted
+/// with `"` escaped by doubling it, a header row, `\n` or `\r\n` line
+/// terminators and no comment lines.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize, Builder, Validate)]
+#[garde(context(MetadataContext))]
+pub struct CsvDialect {
+    /// Character encoding, as an encoding label (`windows-1252`, `UTF-16LE`, ...)
+    #[serde(
+        rename = "csvw:encoding",
+        alias = "http://www.w3.org/ns/csvw#encoding",
+        default,
+        skip_serializing_if = "Option::is_none"
+    )]
+    #[garde(inner(length(min = 1)))]
+    #[builder(default)]
+    pub encoding: Option<String>,
+    #[serde(
+        rename = "csvw:delimiter",
+        alias = "http://www.w3.org/ns/csvw#delimiter",
+        default,
+        skip_serializing_if = "Option::is_none"
+    )]
+    #[garde(inner(length(min = 1, max = 1)))]
+    #[builder(default)]
+    pub delimiter: Option<String>,
+    #[serde(
+        rename = "csvw:quoteChar",
+        alias = "http://www.w3.org/ns/csvw#quoteChar",
+        default,
+        skip_serializing_if = "Option::is_none"
+    )]
+    #[garde(inner(length(min = 1, max = 1)))]
+    #[builder(default)]
+    pub quote_char: Option<String>,
+    /// Whether quotes are escaped by doubling them, `false` when they are
+    /// escaped with a backslash
+    #[serde(
+        rename = "csvw:doubleQuote",
+        alias = "http://www.w3.org/ns/csvw#doubleQuote",
+        default,
+        skip_serializing_if = "Option::is_none"
+    )]
+    #[garde(skip)]
+    #[builder(default)]
+    pub double_quote: Option<bool>,
+    /// Whether the first row holds the column names
+    #[serde(
+        rename = "csvw:header",
+        alias = "http://www.w3.org/ns/csvw#header",
+        default,
+        skip_serializing_if = "Option::is_none"
+    )]
+    #[garde(skip)]
+    #[builder(default)]
+    pub header: Option<bool>,
+    #[serde(
+        rename = "csvw:lineTerminators",
+        alias = "http://www.w3.org/ns/csvw#lineTerminators",
+        default,
+        skip_serializing_if = "Option::is_none"
+    )]
+    #[garde(inner(pattern(r"^(\r\n|\n|\r)$")))]
+    #[builder(default)]
+    pub line_terminators: Option<String>,
+    /// Lines starting with this character are skipped
+    #[serde(
+        rename = "csvw:commentPrefix",
+        alias = "http://www.w3.org/ns/csvw#commentPrefix",
+        default,
+        skip_serializing_if = "Option::is_none"
+    )]
+    #[garde(inner(length(min = 1, max = 1)))]
+    #[builder(default)]
+    pub comment_prefix: Option<String>,
+}
+
+impl CsvDialect {
+    pub fn builder() -> CsvDialectBuilder {
+        CsvDialectBuilder::default()
+    }
+
+    /// Whether no property is set
+    pub fn is_empty(&self) -> bool {
+        *self == Self::default()
+    }
 }
 
+/// How to extract values from a source
 #[derive(Debug, Clone, PartialEq, Serialize, Deserialize, Validate)]
 #[serde(untagged)]
 #[garde(context(MetadataContext))]
@@ -272,6 +794,7 @@ pub enum Extract {
     },
 }
 
+/// Properties of a file that can be extracted as values
 #[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, Validate)]
 #[garde(context(MetadataContext))]
 pub enum FileProperty {
@@ -287,6 +810,7 @@ pub enum FileProperty {
     LineNumbers,
 }
 
+/// Transformation applied to extracted values
 #[derive(Debug, Clone, PartialEq, Serialize, Deserialize, Validate)]
 #[serde(untagged)]
 #[garde(context(MetadataContext))]
@@ -313,6 +837,7 @@ pub enum Transform {
     },
 }
 
+/// Format used to parse extracted values
 #[derive(Debug, Clone, PartialEq, Serialize, Deserialize, Validate)]
 #[serde(tag = "kind")]
 #[garde(context(MetadataContext))]
@@ -339,18 +864,40 @@ pub enum ValueFormat {
     },
 }
 
-#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, Builder, Validate)]
+/// Where the values of a field come from (`source`)
+#[derive(Debug, Clone, PartialEq, Serialize, Builder, Validate)]
+#[serde(remote = "Self")]
 #[garde(context(MetadataContext))]
 pub struct FieldSource {
     #[serde(flatten)]
     #[garde(dive)]
     pub source: SourceRef,
+    #[serde(skip_serializing_if = "Option::is_none")]
     #[garde(dive)]
+    #[builder(default)]
     pub extract: Option<Extract>,
+    #[serde(skip_serializing_if = "Option::is_none")]
     #[garde(dive)]
+    #[builder(default)]
     pub transform: Option<Vec<Transform>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
     #[garde(dive)]
+    #[builder(default)]
     pub format: Option<ValueFormat>,
+    /// Strings standing for a missing value (`csvw:null`), compared with
+    /// extracted values after trimming
+    #[serde(
+        rename = "csvw:null",
+        skip_serializing_if = "Vec::is_empty",
+        serialize_with = "single_or_many"
+    )]
+    #[garde(skip)]
+    #[builder(default)]
+    pub nulls: Vec<String>,
+    #[serde(flatten)]
+    #[garde(skip)]
+    #[builder(default)]
+    pub extensions: Extensions,
 }
 
 impl FieldSource {
@@ -359,6 +906,52 @@ impl FieldSource {
     }
 }
 
+// Implemented by hand: serde hands every key to both the untagged `SourceRef`
+// and the flattened extensions, which would duplicate the source reference.
+impl<'de> Deserialize<'de> for FieldSource {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        fn take<T: serde::de::DeserializeOwned, E: de::Error>(
+            map: &mut serde_json::Map<String, serde_json::Value>,
+            key: &str,
+        ) -> Result<Option<T>, E> {
+            map.shift_remove(key)
+                .map(|v| T::deserialize(v).map_err(E::custom))
+                .transpose()
+        }
+
+        let mut map = serde_json::Map::deserialize(deserializer)?;
+        let order = map.keys().cloned().collect();
+        let mut source = serde_json::Map::new();
+        for key in ["fileObject", "fileSet", "recordSet"] {
+            if let Some(value) = map.shift_remove(key) {
+                source.insert(key.to_string(), value);
+            }
+        }
+        let source = SourceRef::deserialize(serde_json::Value::Object(source))
+            .map_err(|_| de::Error::missing_field("fileObject, fileSet or recordSet"))?;
+
+        let nulls = map
+            .shift_remove("csvw:null")
+            .or_else(|| map.shift_remove("http://www.w3.org/ns/csvw#null"));
+        Ok(Self {
+            source,
+            extract: take(&mut map, "extract")?,
+            transform: take(&mut map, "transform")?,
+            format: take(&mut map, "format")?,
+            nulls: nulls
+                .map(one_or_many)
+                .transpose()
+                .map_err(de::Error::custom)?
+                .unwrap_or_default(),
+            extensions: Extensions {
+                properties: map,
+                order,
+            },
+        })
+    }
+}
+
+/// Reference to the node a field reads from
 #[derive(Debug, Clone, PartialEq, Serialize, Deserialize, Validate)]
 #[serde(untagged)]
 #[garde(context(MetadataContext))]
@@ -380,6 +973,7 @@ pub enum SourceRef {
     },
 }
 
+/// Reference to another node by `@id`
 #[derive(Debug, Clone, PartialEq, Serialize, Deserialize, Validate)]
 #[garde(context(MetadataContext))]
 pub struct Ref {
@@ -388,6 +982,7 @@ pub struct Ref {
     pub id: Id,
 }
 
+/// Reference to a field of another record set (`references`)
 #[derive(Debug, Clone, PartialEq, Serialize, Deserialize, Validate)]
 #[garde(context(MetadataContext))]
 pub struct FieldRef {
@@ -395,39 +990,74 @@ pub struct FieldRef {
     pub field: Ref,
 }
 
+/// A field of a record set (`cr:Field`)
 #[derive(Debug, Clone, PartialEq, Serialize, Deserialize, Builder, Validate)]
+#[serde(remote = "Self")]
 #[garde(context(MetadataContext))]
 pub struct Field {
-    #[serde(rename = "@id")]
-    #[garde(dive)]
-    pub id: Id,
     #[serde(rename = "@type")]
     #[garde(dive)]
     pub kind: CrType, // TODO: Should be on Field
+    #[serde(rename = "@id")]
+    #[garde(dive)]
+    pub id: Id,
     #[garde(dive)]
     pub name: Text,
     #[garde(dive)]
-    pub description: Text,
-    #[serde(rename = "dataType", deserialize_with = "one_or_many")]
+    #[builder(setter(into))]
+    pub description: LangString,
+    /// Empty for fields only described by their `subField`s
+    #[serde(
+        rename = "dataType",
+        default,
+        deserialize_with = "one_or_many",
+        serialize_with = "single_or_many",
+        skip_serializing_if = "Vec::is_empty"
+    )]
     #[garde(dive)]
     pub data_types: Vec<DataType>,
-    #[serde(rename = "source")]
-    #[garde(dive)]
-    pub source: FieldSource,
     #[garde(dive)]
-    #[serde(rename = "references", default, deserialize_with = "one_or_many")]
+    #[serde(
+        rename = "references",
+        default,
+        deserialize_with = "one_or_many",
+        serialize_with = "single_or_many",
+        skip_serializing_if = "Vec::is_empty"
+    )]
+    #[builder(default)]
     pub references: Vec<FieldRef>,
-    #[serde(rename = "subField")]
+    /// Where values are read from, `None` for fields of record sets holding
+    /// their records inline (see [`RecordSet::data`])
+    #[serde(rename = "source", default, skip_serializing_if = "Option::is_none")]
+    #[garde(dive)]
+    #[builder(default)]
+    pub source: Option<FieldSource>,
+    #[serde(rename = "subField", skip_serializing_if = "Option::is_none")]
     #[garde(dive)]
+    #[builder(default)]
     pub sub_fields: Option<Vec<Field>>,
-    #[serde(rename = "parentField")]
+    #[serde(
+        rename = "parentField",
+        default,
+        deserialize_with = "optional_id_refs",
+        serialize_with = "optional_single_or_many",
+        skip_serializing_if = "Option::is_none"
+    )]
     #[garde(dive)]
+    #[builder(default)]
     pub parent_fields: Option<Vec<Id>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
     #[garde(skip)]
+    #[builder(default)]
     pub repeated: Option<bool>,
     #[garde(dive)]
-    #[serde(rename = "equivalentProperty")]
+    #[serde(rename = "equivalentProperty", skip_serializing_if = "Option::is_none")]
+    #[builder(default)]
     pub equivalent_properties: Option<Vec<Text>>,
+    #[serde(flatten)]
+    #[garde(skip)]
+    #[builder(default)]
+    pub extensions: Extensions,
 }
 
 impl Field {
@@ -436,45 +1066,61 @@ impl Field {
     }
 }
 
-pub fn validate_record_set_references(
-    record_set: &RecordSet,
-    ctx: &MetadataContext,
-) -> garde::Result {
-    for field in &record_set.fields {
-        let ref_id = match &field.source.source {
-            SourceRef::FileObject { file_object } => file_object.id.clone(),
-            SourceRef::RecordSet { record_set } => record_set.id.clone(),
-            SourceRef::FileSet { file_set } => file_set.id.clone(),
-        };
-
-        if !ref_id.0.is_empty() && !ctx.distribution_ids.contains(&ref_id) {
-            return Err(garde::Error::new(format!(
-                "Field '{}' references non-existent distribution id '{}'",
-                field.name, ref_id
-            )));
-        }
-    }
-    Ok(())
-}
-
+/// A set of records sharing the same fields (`cr:RecordSet`)
 #[derive(Debug, Clone, PartialEq, Serialize, Deserialize, Builder, Validate)]
+#[serde(remote = "Self")]
 #[garde(context(MetadataContext))]
 pub struct RecordSet {
-    #[serde(rename = "@id")]
-    #[garde(dive)]
-    pub id: Id,
     #[serde(rename = "@type")]
     #[garde(dive)] // TODO: Should be only one value of CrType
     pub kind: CrType,
-    #[serde(rename = "key", default, deserialize_with = "one_or_many")]
+    #[serde(rename = "@id")]
     #[garde(dive)]
-    pub keys: Vec<Ref>,
-    #[serde(rename = "field")]
+    pub id: Id,
+    #[serde(skip_serializing_if = "Option::is_none")]
     #[garde(dive)]
-    pub fields: Vec<Field>,
-    #[serde(rename = "dataType", default, deserialize_with = "one_or_many")]
+    #[builder(default)]
+    pub name: Option<Text>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    #[garde(dive)]
+    #[builder(default)]
+    pub description: Option<LangString>,
+    #[serde(
+        rename = "dataType",
+        default,
+        deserialize_with = "one_or_many",
+        serialize_with = "single_or_many",
+        skip_serializing_if = "Vec::is_empty"
+    )]
     #[garde(dive)]
+    #[builder(default)]
     pub record_types: Vec<DataType>,
+    #[serde(
+        rename = "key",
+        default,
+        deserialize_with = "one_or_many",
+        serialize_with = "single_or_many",
+        skip_serializing_if = "Vec::is_empty"
+    )]
+    #[garde(dive)]
+    #[builder(default)]
+    pub keys: Vec<Ref>,
+    #[serde(rename = "field", deserialize_with = "one_or_many")]
+    #[garde(dive)]
+    pub fields: Vec<Field>,
+    /// Records given inline (`cr:data`), each mapping field ids to values
+    #[serde(
+        default,
+        deserialize_with = "one_or_many",
+        skip_serializing_if = "Vec::is_empty"
+    )]
+    #[garde(skip)]
+    #[builder(default)]
+    pub data: Vec<serde_json::Map<String, serde_json::Value>>,
+    #[serde(flatten)]
+    #[garde(skip)]
+    #[builder(default)]
+    pub extensions: Extensions,
 }
 
 impl RecordSet {
@@ -483,6 +1129,7 @@ impl RecordSet {
     }
 }
 
+/// An entry of the dataset `distribution`
 #[derive(Debug, Clone, PartialEq, Serialize, Deserialize, Builder, Validate)]
 #[garde(context(MetadataContext))]
 pub struct Distribution {
@@ -497,139 +1144,718 @@ impl Distribution {
     }
 }
 
-#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, Builder, Validate)]
-#[garde(context(MetadataContext))]
-pub struct Context {
-    #[serde(rename = "@language")]
-    #[garde(dive)]
-    pub language: Text,
-    #[serde(rename = "@vocab")]
-    #[garde(dive)]
-    pub vocab: Text,
-    #[serde(rename = "sc")]
-    #[garde(dive)]
-    pub sc: Text,
-    #[serde(rename = "cr")]
-    #[garde(dive)]
-    pub cr: Text,
-    #[serde(rename = "dct")]
-    #[garde(dive)]
-    pub dct: Text,
-    #[serde(rename = "citeAs")]
-    #[garde(dive)]
-    pub cite_as: Text,
-    #[serde(rename = "column")]
-    #[garde(dive)]
-    pub column: Text,
-    #[serde(rename = "conformsTo")]
-    #[garde(dive)]
-    pub conforms_to: Text,
-    #[serde(rename = "data")]
-    #[garde(dive)]
-    pub data: DataContext,
-    #[serde(rename = "dataType")]
-    #[garde(dive)]
-    pub data_type: DataTypeContext,
-}
+/// The JSON-LD `@context` of a document
+///
+/// Kept as written since contexts may alias terms, reference remote contexts
+/// or combine several of them in an array. Documents are read through
+/// [`crate::croissant::parse_metadata`], which rewrites them against the
+/// canonical Croissant context.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(transparent)]
+pub struct Context(pub serde_json::Value);
 
 impl Context {
-    pub fn builder() -> ContextBuilder {
-        ContextBuilder::default()
+    /// The canonical Croissant 1.0 context
+    pub fn croissant_1_0() -> Self {
+        Self(croissant::jsonld::croissant_1_0_context())
+    }
+
+    /// The canonical Croissant 1.1 context
+    pub fn croissant_1_1() -> Self {
+        Self(croissant::jsonld::croissant_1_1_context())
     }
 }
 
+impl Default for Context {
+    fn default() -> Self {
+        Self::croissant_1_0()
+    }
+}
+
+/// Version of the Croissant specification a document conforms to
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum SpecVersion {
+    /// The 0.8 draft, whose vocabulary lived under `http://mlcommons.org/schema/`
+    V0_8,
+    #[default]
+    V1_0,
+    V1_1,
+}
+
+impl SpecVersion {
+    /// The most recent version
+    pub const LATEST: Self = Self::V1_1;
+
+    /// The `conformsTo` IRI of the version
+    pub fn conforms_to(&self) -> &'static str {
+        match self {
+            Self::V0_8 => "http://mlcommons.org/croissant/0.8",
+            Self::V1_0 => "http://mlcommons.org/croissant/1.0",
+            Self::V1_1 => "http://mlcommons.org/croissant/1.1",
+        }
+    }
+
+    /// The version named by a `conformsTo` IRI or a context URL
+    pub fn from_iri(iri: &str) -> Option<Self> {
+        let iri = iri
+            .trim_end_matches('/')
+            .trim_end_matches("/context.jsonld");
+        let version = iri
+            .strip_prefix("http://mlcommons.org/croissant/")
+            .or_else(|| iri.strip_prefix("https://mlcommons.org/croissant/"))?;
+        version.parse().ok()
+    }
+}
+
+impl fmt::Display for SpecVersion {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let version = match self {
+            Self::V0_8 => "0.8",
+            Self::V1_0 => "1.0",
+            Self::V1_1 => "1.1",
+        };
+        write!(f, "{version}")
+    }
+}
+
+impl std::str::FromStr for SpecVersion {
+    type Err = croissant::errors::Error;
+
+    fn from_str(version: &str) -> Result<Self, Self::Err> {
+        match version {
+            "0.8" => Ok(Self::V0_8),
+            "1.0" => Ok(Self::V1_0),
+            "1.1" => Ok(Self::V1_1),
+            other => Err(croissant::errors::Error::unsupported(format!(
+                "Croissant version '{other}'"
+            ))),
+        }
+    }
+}
+
+/// The standard Croissant 1.0 `@context`
 pub fn default_context() -> Result<Context, croissant::errors::Error> {
-    Ok(Context::builder()
-        .language(Text::new("en"))
-        .vocab(Text::new("https://schema.org/"))
-        .cite_as(Text::new("cr:citeAs"))
-        .column(Text::new("cr:column"))
-        .conforms_to(Text::new("dct:conforms_to"))
-        .cr(Text::new("http://purl.org/dc/terms/"))
-        .data(
-            DataContext::builder()
-                .id(Text::new("cr:data"))
-                .r#type(Text::new("@json"))
-                .build()
-                .map_err(|e| Error::Builder(e.to_string()))?,
-        )
-        .data_type(
-            DataTypeContext::builder()
-                .id(Text::new("cr:DataType"))
-                .r#type(Text::new("@vocal"))
-                .build()
-                .map_err(|e| Error::Builder(e.to_string()))?,
-        )
-        .sc(Text::new("https://schema.org/"))
-        .build()
-        .map_err(|e| Error::Builder(e.to_string()))?)
+    Ok(Context::croissant_1_0())
+}
+
+/// Check that a date property holds an ISO 8601 date or datetime
+pub fn validate_iso_date(value: &Option<Text>, _ctx: &MetadataContext) -> garde::Result {
+    match value {
+        Some(value) => check_iso_date(value),
+        None => Ok(()),
+    }
+}
+
+/// Validate that every value is an ISO 8601 date or datetime
+pub fn validate_iso_dates(values: &[Text], _ctx: &MetadataContext) -> garde::Result {
+    values.iter().try_for_each(check_iso_date)
 }
 
+fn check_iso_date(value: &Text) -> garde::Result {
+    let valid = chrono::NaiveDate::parse_from_str(&value.0, "%Y-%m-%d").is_ok()
+        || chrono::DateTime::parse_from_rfc3339(&value.0).is_ok()
+        || chrono::NaiveDateTime::parse_from_str(&value.0, "%Y-%m-%dT%H:%M:%S").is_ok();
+    if valid {
+        Ok(())
+    } else {
+        Err(garde::Error::new(format!(
+            "'{}' is not an ISO 8601 date or datetime",
+            value.0
+        )))
+    }
+}
+
+/// A person credited as creator or publisher (`sc:Person`)
 #[derive(Debug, Clone, PartialEq, Serialize, Deserialize, Builder, Validate)]
+#[serde(remote = "Self")]
 #[garde(context(MetadataContext))]
-pub struct DataContext {
-    #[serde(rename = "@id")]
+pub struct Person {
     #[garde(dive)]
-    pub id: Id,
-    #[serde(rename = "@type")]
+    pub name: Text,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    #[garde(inner(email))]
+    #[builder(default)]
+    pub email: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    #[garde(inner(url))]
+    #[builder(default)]
+    pub url: Option<String>,
+    #[serde(flatten)]
+    #[garde(skip)]
+    #[builder(default)]
+    pub extensions: Extensions,
+}
+
+impl Person {
+    pub fn builder() -> PersonBuilder {
+        PersonBuilder::default()
+    }
+}
+
+/// An organization credited as creator or publisher (`sc:Organization`)
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, Builder, Validate)]
+#[serde(remote = "Self")]
+#[garde(context(MetadataContext))]
+pub struct Organization {
     #[garde(dive)]
-    pub r#type: Text,
+    pub name: Text,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    #[garde(inner(email))]
+    #[builder(default)]
+    pub email: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    #[garde(inner(url))]
+    #[builder(default)]
+    pub url: Option<String>,
+    #[serde(flatten)]
+    #[garde(skip)]
+    #[builder(default)]
+    pub extensions: Extensions,
+}
+
+impl Organization {
+    pub fn builder() -> OrganizationBuilder {
+        OrganizationBuilder::default()
+    }
+}
+
+/// Creator or publisher of a dataset
+///
+/// Typed nodes are told apart by their `@type`; nodes without one and plain
+/// strings, which schema.org allows too, are kept as they are given.
+#[derive(Debug, Clone, PartialEq, Validate)]
+#[garde(context(MetadataContext))]
+pub enum Agent {
+    Person(#[garde(dive)] Person),
+    Organization(#[garde(dive)] Organization),
+    /// A node without `@type`, with the properties shared by persons and
+    /// organizations
+    Untyped(#[garde(dive)] Person),
+    /// A plain string, usually a name
+    Name(#[garde(dive)] LangString),
 }
 
-impl DataContext {
-    pub fn builder() -> DataContextBuilder {
-        DataContextBuilder::default()
+impl Agent {
+    pub fn name(&self) -> &Text {
+        match self {
+            Self::Person(p) | Self::Untyped(p) => &p.name,
+            Self::Organization(o) => &o.name,
+            Self::Name(name) => name,
+        }
     }
 }
 
+/// Typed agents, distinguished by their `@type`
+#[derive(Serialize, Deserialize)]
+#[serde(tag = "@type")]
+enum TypedAgent<P, O> {
+    #[serde(rename = "sc:Person", alias = "Person")]
+    Person(P),
+    #[serde(rename = "sc:Organization", alias = "Organization")]
+    Organization(O),
+}
+
+// Typed agents are ordered once tagged, the `@type` tag being among the
+// properties they were read with
+impl Serialize for Agent {
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let (node, extensions) = match self {
+            Self::Person(person) => (
+                serde_json::to_value(TypedAgent::<_, &Organization>::Person(person)),
+                &person.extensions,
+            ),
+            Self::Organization(organization) => (
+                serde_json::to_value(TypedAgent::<&Person, _>::Organization(organization)),
+                &organization.extensions,
+            ),
+            Self::Untyped(person) => return person.serialize(serializer),
+            Self::Name(name) => return name.serialize(serializer),
+        };
+        let node = node.map_err(serde::ser::Error::custom)?;
+        extensions.reorder(node).serialize(serializer)
+    }
+}
+
+impl<'de> Deserialize<'de> for Agent {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let value = serde_json::Value::deserialize(deserializer)?;
+        match &value {
+            serde_json::Value::String(_) => LangString::deserialize(value).map(Self::Name),
+            serde_json::Value::Object(node) if node.contains_key("@value") => {
+                LangString::deserialize(value).map(Self::Name)
+            }
+            serde_json::Value::Object(node) if !node.contains_key("@type") => {
+                Person::deserialize(value).map(Self::Untyped)
+            }
+            _ => {
+                let order = read_order(&value);
+                match TypedAgent::<Person, Organization>::deserialize(value) {
+                    Ok(TypedAgent::Person(mut person)) => {
+                        person.extensions.order = order;
+                        Ok(Self::Person(person))
+                    }
+                    Ok(TypedAgent::Organization(mut organization)) => {
+                        organization.extensions.order = order;
+                        Ok(Self::Organization(organization))
+                    }
+                    Err(e) => Err(e),
+                }
+            }
+        }
+        .map_err(de::Error::custom)
+    }
+}
+
+/// License of a dataset: its URL or name, or a `sc:CreativeWork` describing it
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, Validate)]
+#[serde(untagged)]
+#[garde(context(MetadataContext))]
+pub enum License {
+    Text(#[garde(dive)] Text),
+    CreativeWork(#[garde(dive)] Box<CreativeWork>),
+}
+
+impl From<Text> for License {
+    fn from(text: Text) -> Self {
+        Self::Text(text)
+    }
+}
+
+/// A creative work, such as a license document (`sc:CreativeWork`)
 #[derive(Debug, Clone, PartialEq, Serialize, Deserialize, Builder, Validate)]
+#[serde(remote = "Self")]
 #[garde(context(MetadataContext))]
-pub struct DataTypeContext {
-    #[serde(rename = "@id")]
+pub struct CreativeWork {
+    #[serde(rename = "@type", default, skip_serializing_if = "Option::is_none")]
     #[garde(dive)]
-    pub id: Id,
-    #[serde(rename = "@type")]
+    #[builder(default)]
+    pub kind: Option<Text>,
+    #[serde(rename = "@id", default, skip_serializing_if = "Option::is_none")]
+    #[garde(dive)]
+    #[builder(default)]
+    pub id: Option<Id>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
     #[garde(dive)]
-    pub r#type: Text,
+    #[builder(default)]
+    pub name: Option<Text>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    #[garde(inner(url))]
+    #[builder(default)]
+    pub url: Option<String>,
+    #[serde(flatten)]
+    #[garde(skip)]
+    #[builder(default)]
+    pub extensions: Extensions,
 }
 
-impl DataTypeContext {
-    pub fn builder() -> DataTypeContextBuilder {
-        DataTypeContextBuilder::default()
+impl CreativeWork {
+    pub fn builder() -> CreativeWorkBuilder {
+        CreativeWorkBuilder::default()
     }
 }
 
+/// State shared across nodes while validating a [`Metadata`] document
 #[derive(Clone, Default, Debug)]
-pub struct MetadataContext {
-    distribution_ids: HashSet<Id>,
+pub struct MetadataContext {}
+
+/// Responsible AI properties of a dataset
+///
+/// Serialized with the `rai:` prefix of the Croissant RAI vocabulary
+/// (`http://mlcommons.org/croissant/RAI/`). The unprefixed names are accepted
+/// as aliases, which keeps configuration files readable.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize, Builder, Validate)]
+#[garde(context(MetadataContext))]
+pub struct ResponsibleAi {
+    /// How the data was collected
+    #[serde(
+        rename = "rai:dataCollection",
+        alias = "dataCollection",
+        default,
+        skip_serializing_if = "Option::is_none"
+    )]
+    #[garde(dive)]
+    #[builder(default)]
+    pub data_collection: Option<Text>,
+    /// Kinds of collection, e.g. surveys, web scraping or sensors
+    #[serde(
+        rename = "rai:dataCollectionType",
+        alias = "dataCollectionType",
+        default,
+        deserialize_with = "one_or_many",
+        serialize_with = "single_or_many",
+        skip_serializing_if = "Vec::is_empty"
+    )]
+    #[garde(dive)]
+    #[builder(default)]
+    pub data_collection_type: Vec<Text>,
+    /// How missing data was handled during collection
+    #[serde(
+        rename = "rai:dataCollectionMissingData",
+        alias = "dataCollectionMissingData",
+        default,
+        skip_serializing_if = "Option::is_none"
+    )]
+    #[garde(dive)]
+    #[builder(default)]
+    pub data_collection_missing_data: Option<Text>,
+    /// Description of the raw data before processing
+    #[serde(
+        rename = "rai:dataCollectionRawData",
+        alias = "dataCollectionRawData",
+        default,
+        skip_serializing_if = "Option::is_none"
+    )]
+    #[garde(dive)]
+    #[builder(default)]
+    pub data_collection_raw_data: Option<Text>,
+    /// Start and end of the collection, as ISO 8601 dates or datetimes
+    #[serde(
+        rename = "rai:dataCollectionTimeframe",
+        alias = "dataCollectionTimeframe",
+        default,
+        deserialize_with = "one_or_many",
+        serialize_with = "single_or_many",
+        skip_serializing_if = "Vec::is_empty"
+    )]
+    #[garde(dive, custom(validate_iso_dates))]
+    #[builder(default)]
+    pub data_collection_timeframe: Vec<Text>,
+    /// How missing values were imputed
+    #[serde(
+        rename = "rai:dataImputationProtocol",
+        alias = "dataImputationProtocol",
+        default,
+        skip_serializing_if = "Option::is_none"
+    )]
+    #[garde(dive)]
+    #[builder(default)]
+    pub data_imputation_protocol: Option<Text>,
+    /// How the data was manipulated after collection
+    #[serde(
+        rename = "rai:dataManipulationProtocol",
+        alias = "dataManipulationProtocol",
+        default,
+        skip_serializing_if = "Option::is_none"
+    )]
+    #[garde(dive)]
+    #[builder(default)]
+    pub data_manipulation_protocol: Option<Text>,
+    /// Preprocessing steps applied to the data
+    #[serde(
+        rename = "rai:dataPreprocessingProtocol",
+        alias = "dataPreprocessingProtocol",
+        default,
+        deserialize_with = "one_or_many",
+        serialize_with = "single_or_many",
+        skip_serializing_if = "Vec::is_empty"
+    )]
+    #[garde(dive)]
+    #[builder(default)]
+    pub data_preprocessing_protocol: Vec<Text>,
+    /// How the data was annotated
+    #[serde(
+        rename = "rai:dataAnnotationProtocol",
+        alias = "dataAnnotationProtocol",
+        default,
+        skip_serializing_if = "Option::is_none"
+    )]
+    #[garde(dive)]
+    #[builder(default)]
+    pub data_annotation_protocol: Option<Text>,
+    /// Platforms or tools used to annotate the data
+    #[serde(
+        rename = "rai:dataAnnotationPlatform",
+        alias = "dataAnnotationPlatform",
+        default,
+        deserialize_with = "one_or_many",
+        serialize_with = "single_or_many",
+        skip_serializing_if = "Vec::is_empty"
+    )]
+    #[garde(dive)]
+    #[builder(default)]
+    pub data_annotation_platform: Vec<Text>,
+    /// Analyses of the annotations, e.g. inter-annotator agreement
+    #[serde(
+        rename = "rai:dataAnnotationAnalysis",
+        alias = "dataAnnotationAnalysis",
+        default,
+        deserialize_with = "one_or_many",
+        serialize_with = "single_or_many",
+        skip_serializing_if = "Vec::is_empty"
+    )]
+    #[garde(dive)]
+    #[builder(default)]
+    pub data_annotation_analysis: Vec<Text>,
+    /// Number of annotations collected per item
+    #[serde(
+        rename = "rai:annotationsPerItem",
+        alias = "annotationsPerItem",
+        default,
+        skip_serializing_if = "Option::is_none"
+    )]
+    #[garde(dive)]
+    #[builder(default)]
+    pub annotations_per_item: Option<Text>,
+    /// Demographics of the annotators
+    #[serde(
+        rename = "rai:annotatorDemographics",
+        alias = "annotatorDemographics",
+        default,
+        deserialize_with = "one_or_many",
+        serialize_with = "single_or_many",
+        skip_serializing_if = "Vec::is_empty"
+    )]
+    #[garde(dive)]
+    #[builder(default)]
+    pub annotator_demographics: Vec<Text>,
+    /// Models or tools that produced annotations
+    #[serde(
+        rename = "rai:machineAnnotationTools",
+        alias = "machineAnnotationTools",
+        default,
+        deserialize_with = "one_or_many",
+        serialize_with = "single_or_many",
+        skip_serializing_if = "Vec::is_empty"
+    )]
+    #[garde(dive)]
+    #[builder(default)]
+    pub machine_annotation_tools: Vec<Text>,
+    /// Personal or sensitive information present in the data
+    #[serde(
+        rename = "rai:personalSensitiveInformation",
+        alias = "personalSensitiveInformation",
+        default,
+        deserialize_with = "one_or_many",
+        serialize_with = "single_or_many",
+        skip_serializing_if = "Vec::is_empty"
+    )]
+    #[garde(dive)]
+    #[builder(default)]
+    pub personal_sensitive_information: Vec<Text>,
+    /// Known biases of the data
+    #[serde(
+        rename = "rai:dataBiases",
+        alias = "dataBiases",
+        default,
+        deserialize_with = "one_or_many",
+        serialize_with = "single_or_many",
+        skip_serializing_if = "Vec::is_empty"
+    )]
+    #[garde(dive)]
+    #[builder(default)]
+    pub data_biases: Vec<Text>,
+    /// Known limitations of the data
+    #[serde(
+        rename = "rai:dataLimitations",
+        alias = "dataLimitations",
+        default,
+        deserialize_with = "one_or_many",
+        serialize_with = "single_or_many",
+        skip_serializing_if = "Vec::is_empty"
+    )]
+    #[garde(dive)]
+    #[builder(default)]
+    pub data_limitations: Vec<Text>,
+    /// Intended and validated uses of the data
+    #[serde(
+        rename = "rai:dataUseCases",
+        alias = "dataUseCases",
+        default,
+        deserialize_with = "one_or_many",
+        serialize_with = "single_or_many",
+        skip_serializing_if = "Vec::is_empty"
+    )]
+    #[garde(dive)]
+    #[builder(default)]
+    pub data_use_cases: Vec<Text>,
+    /// Potential social impact of the data
+    #[serde(
+        rename = "rai:dataSocialImpact",
+        alias = "dataSocialImpact",
+        default,
+        skip_serializing_if = "Option::is_none"
+    )]
+    #[garde(dive)]
+    #[builder(default)]
+    pub data_social_impact: Option<Text>,
+    /// How the data is versioned and maintained
+    #[serde(
+        rename = "rai:dataReleaseMaintenancePlan",
+        alias = "dataReleaseMaintenancePlan",
+        default,
+        skip_serializing_if = "Option::is_none"
+    )]
+    #[garde(dive)]
+    #[builder(default)]
+    pub data_release_maintenance_plan: Option<Text>,
 }
 
+impl ResponsibleAi {
+    pub fn builder() -> ResponsibleAiBuilder {
+        ResponsibleAiBuilder::default()
+    }
+
+    /// Whether no property is set
+    pub fn is_empty(&self) -> bool {
+        self.data_collection.is_none()
+            && self.data_collection_missing_data.is_none()
+            && self.data_collection_raw_data.is_none()
+            && self.data_imputation_protocol.is_none()
+            && self.data_manipulation_protocol.is_none()
+            && self.data_annotation_protocol.is_none()
+            && self.annotations_per_item.is_none()
+            && self.data_social_impact.is_none()
+            && self.data_release_maintenance_plan.is_none()
+            && self.data_collection_type.is_empty()
+            && self.data_collection_timeframe.is_empty()
+            && self.data_preprocessing_protocol.is_empty()
+            && self.data_annotation_platform.is_empty()
+            && self.data_annotation_analysis.is_empty()
+            && self.annotator_demographics.is_empty()
+            && self.machine_annotation_tools.is_empty()
+            && self.personal_sensitive_information.is_empty()
+            && self.data_biases.is_empty()
+            && self.data_limitations.is_empty()
+            && self.data_use_cases.is_empty()
+    }
+}
+
+/// A Croissant dataset description
 #[derive(Debug, Clone, PartialEq, Serialize, Deserialize, Builder, Validate)]
+#[serde(remote = "Self")]
 #[garde(context(MetadataContext))]
 pub struct Metadata {
     #[serde(rename = "@context")]
-    #[garde(dive)]
+    #[garde(skip)]
     pub context: Context,
     #[serde(rename = "@type")]
     #[garde(dive)]
     pub kind: CroissantType,
     #[garde(dive)]
-    pub name: Text,
+    #[builder(setter(into))]
+    pub name: LangString,
+    #[garde(dive)]
+    #[builder(setter(into))]
+    pub description: LangString,
+    #[serde(
+        rename = "conformsTo",
+        default,
+        skip_serializing_if = "Option::is_none"
+    )]
+    #[garde(dive)]
+    #[builder(default)]
+    pub conforms_to: Option<Text>,
+    #[serde(rename = "citeAs", default, skip_serializing_if = "Option::is_none")]
     #[garde(dive)]
-    pub description: Text,
-    #[serde(rename = "conformsTo")]
+    #[builder(default)]
+    pub cite_as: Option<LangString>,
+    #[serde(
+        default,
+        deserialize_with = "one_or_many",
+        serialize_with = "single_or_many",
+        skip_serializing_if = "Vec::is_empty"
+    )]
     #[garde(dive)]
-    pub conforms_to: Text,
-    #[serde(rename = "datePublished")]
+    #[builder(default)]
+    pub creator: Vec<Agent>,
+    #[serde(
+        default,
+        deserialize_with = "one_or_many",
+        serialize_with = "single_or_many",
+        skip_serializing_if = "Vec::is_empty"
+    )]
     #[garde(dive)]
+    #[builder(default)]
+    pub publisher: Vec<Agent>,
+    #[serde(
+        rename = "datePublished",
+        default,
+        skip_serializing_if = "Option::is_none"
+    )]
+    #[garde(dive, custom(validate_iso_date))]
+    #[builder(default)]
     pub date_published: Option<Text>,
+    #[serde(
+        rename = "dateCreated",
+        default,
+        skip_serializing_if = "Option::is_none"
+    )]
+    #[garde(dive, custom(validate_iso_date))]
+    #[builder(default)]
+    pub date_created: Option<Text>,
+    #[serde(
+        rename = "dateModified",
+        default,
+        skip_serializing_if = "Option::is_none"
+    )]
+    #[garde(dive, custom(validate_iso_date))]
+    #[builder(default)]
+    pub date_modified: Option<Text>,
+    #[serde(
+        default,
+        deserialize_with = "one_or_many",
+        serialize_with = "single_or_many",
+        skip_serializing_if = "Vec::is_empty"
+    )]
+    #[garde(dive)]
+    #[builder(default)]
+    pub license: Vec<License>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    #[garde(inner(url))]
+    #[builder(default)]
+    pub url: Option<String>,
+    #[serde(
+        rename = "sameAs",
+        default,
+        deserialize_with = "one_or_many",
+        serialize_with = "single_or_many",
+        skip_serializing_if = "Vec::is_empty"
+    )]
+    #[garde(inner(url))]
+    #[builder(default)]
+    pub same_as: Vec<String>,
+    #[serde(
+        default,
+        deserialize_with = "one_or_many",
+        skip_serializing_if = "Vec::is_empty"
+    )]
+    #[garde(dive)]
+    #[builder(default)]
+    pub keywords: Vec<LangString>,
+    #[serde(
+        rename = "inLanguage",
+        default,
+        deserialize_with = "one_or_many",
+        serialize_with = "single_or_many",
+        skip_serializing_if = "Vec::is_empty"
+    )]
+    #[garde(dive)]
+    #[builder(default)]
+    pub in_language: Vec<Text>,
+    #[serde(
+        rename = "isLiveDataset",
+        default,
+        skip_serializing_if = "Option::is_none"
+    )]
+    #[garde(skip)]
+    #[builder(default)]
+    pub is_live_dataset: Option<bool>,
     #[garde(dive)]
     pub version: Text,
     #[garde(dive)]
     pub distribution: Vec<Distribution>,
-    #[serde(rename = "recordSet")]
-    #[garde(length(min = 1), inner(custom(validate_record_set_references)))]
+    #[serde(rename = "recordSet", deserialize_with = "one_or_many")]
+    #[garde(length(min = 1), dive)]
     pub record_sets: Vec<RecordSet>,
+    /// Responsible AI properties (`rai:` namespace)
+    #[serde(flatten)]
+    #[garde(dive)]
+    #[builder(default)]
+    pub rai: ResponsibleAi,
+    #[serde(flatten)]
+    #[garde(skip)]
+    #[builder(default)]
+    pub extensions: Extensions,
 }
 
 impl Metadata {
@@ -639,18 +1865,305 @@ impl Metadata {
 }
 
 impl Metadata {
+    /// Version of the specification declared by `conformsTo`
+    pub fn spec_version(&self) -> Option<SpecVersion> {
+        SpecVersion::from_iri(&self.conforms_to.as_ref()?.0)
+    }
+
+    /// Validate the document and its cross-references
+    ///
+    /// Dangling or mistyped references are reported at the JSON path of the
+    /// offending node. Use [`crate::croissant::validate_metadata`] for
+    /// locations, warnings and recommendations.
     pub fn check(&self) -> Result<(), garde::Report> {
-        let ctx = MetadataContext {
-            distribution_ids: self
-                .distribution
-                .iter()
-                .map(|d| match &d.resource {
-                    Resource::FileObject(o) => o.id.clone(),
-                    Resource::FileSet(s) => s.id.clone(),
-                })
-                .collect(),
-        };
+        let mut report = self
+            .check_constraints()
+            .err()
+            .unwrap_or_else(garde::Report::new);
+        for issue in croissant::references::check_references(self).errors() {
+            report.append(garde_path(&issue.path), garde::Error::new(&issue.message));
+        }
+        match report.is_empty() {
+            true => Ok(()),
+            false => Err(report),
+        }
+    }
+
+    /// Forget the order every node was read in, so that the document is
+    /// written with the modelled properties of its nodes first
+    pub(crate) fn forget_property_order(&mut self) {
+        fn forget_field(field: &mut Field) {
+            field.extensions.forget_order();
+            if let Some(source) = &mut field.source {
+                source.extensions.forget_order();
+            }
+            field.sub_fields.iter_mut().flatten().for_each(forget_field);
+        }
 
+        self.extensions.forget_order();
+        for agent in self.creator.iter_mut().chain(&mut self.publisher) {
+            match agent {
+                Agent::Person(person) | Agent::Untyped(person) => person.extensions.forget_order(),
+                Agent::Organization(organization) => organization.extensions.forget_order(),
+                Agent::Name(_) => {}
+            }
+        }
+        for license in &mut self.license {
+            if let License::CreativeWork(work) = license {
+                work.extensions.forget_order();
+            }
+        }
+        for distribution in &mut self.distribution {
+            match &mut distribution.resource {
+                Resource::FileObject(object) => object.extensions.forget_order(),
+                Resource::FileSet(set) => set.extensions.forget_order(),
+            }
+        }
+        for record_set in &mut self.record_sets {
+            record_set.extensions.forget_order();
+            record_set.fields.iter_mut().for_each(forget_field);
+        }
+    }
+
+    /// Validate the constraints of every node, without resolving references
+    pub(crate) fn check_constraints(&self) -> Result<(), garde::Report> {
+        let ctx = MetadataContext::default();
         self.validate_with(&ctx)
     }
 }
+
+/// A JSON path of a report as a garde path (`$.recordSet[0].key` → `recordSet[0].key`)
+fn garde_path(json_path: &str) -> garde::Path {
+    let segments = json_path.trim_start_matches('$').split(['.', '[']);
+    segments
+        .filter(|segment| !segment.is_empty())
+        .fold(garde::Path::empty(), |path, segment| {
+            match segment.strip_suffix(']').map(str::parse::<usize>) {
+                Some(Ok(index)) => path.join(index),
+                _ => path.join(segment),
+            }
+        })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn agents_round_trip_with_or_without_type() {
+        for value in [
+            json!({"@type": "sc:Person", "name": "Jane Doe"}),
+            json!({"@type": "sc:Organization", "name": "ACME", "url": "https://acme.org"}),
+            json!({"name": "Jane Doe", "email": "jane@acme.org"}),
+            json!("Jane Doe"),
+        ] {
+            let agent = Agent::deserialize(&value).unwrap();
+            assert_eq!(serde_json::to_value(&agent).unwrap(), value);
+        }
+        assert!(matches!(
+            Agent::deserialize(json!("Jane Doe")).unwrap(),
+            Agent::Name(_)
+        ));
+        assert!(matches!(
+            Agent::deserialize(json!({"name": "Jane Doe"})).unwrap(),
+            Agent::Untyped(_)
+        ));
+        assert!(Agent::deserialize(json!({"@type": "sc:Place", "name": "Paris"})).is_err());
+    }
+
+    #[test]
+    fn licenses_are_urls_or_creative_works() {
+        let value = json!([
+            "https://creativecommons.org/licenses/by/4.0/",
+            {"@type": "sc:CreativeWork", "name": "Custom", "url": "https://acme.org/license"},
+        ]);
+        let licenses = Vec::<License>::deserialize(&value).unwrap();
+        assert!(matches!(licenses[0], License::Text(_)));
+        assert!(matches!(licenses[1], License::CreativeWork(_)));
+        assert_eq!(serde_json::to_value(&licenses).unwrap(), value);
+    }
+
+    #[test]
+    fn nodes_are_written_in_the_order_they_were_read() {
+        let value = json!({
+            "vendorFirst": {"a": 1},
+            "@type": "sc:Person",
+            "name": "Jane Doe",
+            "vendorSecond": [1, 2],
+            "email": "jane@acme.org",
+            "vendorThird": "x",
+        });
+        let agent = Agent::deserialize(&value).unwrap();
+        let written = serde_json::to_value(&agent).unwrap();
+        assert_eq!(written, value);
+        let keys = written.as_object().unwrap().keys().collect::<Vec<_>>();
+        assert_eq!(
+            keys,
+            [
+                "vendorFirst",
+                "@type",
+                "name",
+                "vendorSecond",
+                "email",
+                "vendorThird"
+            ]
+        );
+
+        let Agent::Person(mut person) = agent else {
+            panic!("not a person");
+        };
+        person.url = Some("https://acme.org/jane".to_string());
+        person.extensions.shift_remove("vendorSecond");
+        person
+            .extensions
+            .insert("vendorFourth".to_string(), json!(4));
+        let written = serde_json::to_value(&person).unwrap();
+        let keys = written.as_object().unwrap().keys().collect::<Vec<_>>();
+        assert_eq!(
+            keys,
+            [
+                "vendorFirst",
+                "name",
+                "email",
+                "url",
+                "vendorThird",
+                "vendorFourth"
+            ]
+        );
+
+        let person = Person::builder()
+            .name(Text::new("Jane Doe"))
+            .extensions(Extensions::from(
+                json!({"vendor": 1}).as_object().unwrap().clone(),
+            ))
+            .build()
+            .unwrap();
+        let written = serde_json::to_value(&person).unwrap();
+        let keys = written.as_object().unwrap().keys().collect::<Vec<_>>();
+        assert_eq!(keys, ["name", "vendor"]);
+    }
+
+    #[test]
+    fn documents_keep_their_extensions() {
+        let document = json!({
+            "@context": {"@vocab": "https://schema.org/", "cr": "http://mlcommons.org/croissant/"},
+            "@type": "sc:Dataset",
+            "name": "test",
+            "description": "test",
+            "version": "1.0.0",
+            "vendorNote": {"reviewed": true},
+            "distribution": [],
+            "recordSet": [{
+                "@type": "cr:RecordSet",
+                "@id": "main",
+                "field": [{
+                    "@type": "cr:Field",
+                    "@id": "main/a",
+                    "name": "a",
+                    "description": "A",
+                    "dataType": "sc:Text",
+                    "vendorNote": "checked",
+                }],
+            }],
+        });
+        let metadata = croissant::jsonld::parse_metadata(document).unwrap();
+        let written = serde_json::to_value(&metadata).unwrap();
+        let reread: Metadata = serde_json::from_value(written.clone()).unwrap();
+        assert_eq!(reread, metadata);
+        assert_eq!(written["vendorNote"], json!({"reviewed": true}));
+        assert_eq!(
+            written["recordSet"][0]["field"][0]["vendorNote"],
+            json!("checked")
+        );
+    }
+
+    #[test]
+    fn bounding_box_formats_round_trip_in_sources() {
+        for format in [
+            BoundingBoxFormat::CenterXywh,
+            BoundingBoxFormat::Xywh,
+            BoundingBoxFormat::Xyxy,
+        ] {
+            let source = FieldSource::builder()
+                .source(SourceRef::FileObject {
+                    file_object: Ref {
+                        id: Id::new("boxes.csv"),
+                    },
+                })
+                .format(Some(ValueFormat::BoundingBox {
+                    format: format.clone(),
+                }))
+                .build()
+                .unwrap();
+            let value = serde_json::to_value(&source).unwrap();
+            assert_eq!(value["format"]["kind"], json!("bbox"));
+            let reread = FieldSource::deserialize(&value).unwrap();
+            assert_eq!(reread, source);
+            assert_eq!(reread.format, Some(ValueFormat::BoundingBox { format }));
+        }
+    }
+
+    #[test]
+    fn missing_value_strings_are_one_or_many() {
+        let source = json!({"fileObject": {"@id": "rows.csv"}, "csvw:null": "NA"});
+        let parsed = FieldSource::deserialize(&source).unwrap();
+        assert_eq!(parsed.nulls, ["NA"]);
+        assert!(parsed.extensions.is_empty());
+        assert_eq!(serde_json::to_value(&parsed).unwrap(), source);
+
+        let source = json!({"fileObject": {"@id": "rows.csv"}, "csvw:null": ["NA", ""]});
+        let parsed = FieldSource::deserialize(&source).unwrap();
+        assert_eq!(parsed.nulls, ["NA", ""]);
+        assert_eq!(serde_json::to_value(&parsed).unwrap(), source);
+
+        let parsed = FieldSource::deserialize(json!({
+            "fileObject": {"@id": "rows.csv"},
+            "http://www.w3.org/ns/csvw#null": ["?"],
+        }))
+        .unwrap();
+        assert_eq!(parsed.nulls, ["?"]);
+        assert!(parsed.extensions.is_empty());
+
+        let parsed = FieldSource::deserialize(json!({"fileObject": {"@id": "rows.csv"}})).unwrap();
+        assert!(parsed.nulls.is_empty());
+        assert!(
+            FieldSource::deserialize(json!({"fileObject": {"@id": "rows.csv"}, "csvw:null": 1}))
+                .is_err()
+        );
+    }
+
+    #[test]
+    fn check_reports_dangling_references() {
+        let document = json!({
+            "@context": {"@vocab": "https://schema.org/", "cr": "http://mlcommons.org/croissant/"},
+            "@type": "sc:Dataset",
+            "name": "test",
+            "description": "test",
+            "version": "1.0.0",
+            "distribution": [],
+            "recordSet": [{
+                "@type": "cr:RecordSet",
+                "@id": "main",
+                "field": [{
+                    "@type": "cr:Field",
+                    "@id": "main/a",
+                    "name": "a",
+                    "description": "A",
+                    "dataType": "sc:Text",
+                    "source": {"fileObject": {"@id": "missing.csv"}, "extract": {"column": "a"}},
+                }],
+            }],
+        });
+        let metadata = croissant::jsonld::parse_metadata(document).unwrap();
+        assert!(metadata.check_constraints().is_ok());
+        let report = metadata.check().unwrap_err();
+        let errors = report
+            .iter()
+            .map(|(path, error)| (path.to_string(), error.message().to_string()))
+            .collect::<Vec<_>>();
+        assert_eq!(errors.len(), 1);
+        assert_eq!(errors[0].0, "recordSet[0].field[0].source");
+        assert!(errors[0].1.contains("missing.csv"));
+    }
+}
@@ -0,0 +1,674 @@
+//! Validation of the data files against the record sets describing them
+//!
+//! Every record set is read through its field sources. Values must parse as
+//! the declared `dataType` (and value format), keys must be unique and non
+//! null, and fields with `references` behave like foreign keys: their values
+//! must exist in the referenced field.
+//!
+//! The inline `data` of record sets is part of the metadata and is checked
+//! with it, see [`check_inline_data`].
+use crate::croissant::core::{DataType, Field, Metadata, RecordSet};
+use crate::croissant::errors::Result;
+use crate::croissant::records::{Dataset, Value, convert_json, primitive_type};
+use crate::croissant::report::{Severity, ValidationReport, codes};
+use std::collections::hash_map::Entry;
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::path::Path;
+
+/// Number of offending records quoted in each violation summary
+const MAX_EXAMPLES: usize = 3;
+
+/// Validate the data of a Croissant metadata file
+///
+/// Content URLs are resolved against `data_dir`, or the directory of the
+/// metadata file when it is `None`. Only failures to read the metadata are
+/// returned as errors.
+pub fn validate_data_file(
+    metadata_path: &Path,
+    data_dir: Option<&Path>,
+) -> Result<ValidationReport> {
+    let dataset = Dataset::from_file(metadata_path)?;
+    Ok(match data_dir {
+        Some(dir) => validate_data(&Dataset::new(dataset.metadata().clone(), dir)),
+        None => validate_data(&dataset),
+    })
+}
+
+/// Validate the records of every record set of a dataset
+pub fn validate_data(dataset: &Dataset) -> ValidationReport {
+    let mut report = ValidationReport::new();
+    let root = format!("Metadata({})", dataset.metadata().name.0);
+    for (i, record_set) in dataset.metadata().record_sets.iter().enumerate() {
+        let location = format!("{root} > RecordSet({})", record_set.id.0);
+        let json_path = format!("$.recordSet[{i}]");
+        let mut check = RecordSetCheck::new(dataset, record_set, &location, &json_path);
+        check.run(&mut report);
+    }
+    report
+}
+
+/// Check the inline `data` of every record set against its fields
+///
+/// Each record must have exactly the ids of the fields of the record set, and
+/// nested records those of the sub-fields of their field. Values must convert
+/// to the `dataType` of their field as they do when the records are read.
+/// Fields of such record sets are read from the records and cannot have a
+/// `source`.
+pub fn check_inline_data(metadata: &Metadata) -> ValidationReport {
+    let mut report = ValidationReport::new();
+    let root = format!("Metadata({})", metadata.name.0);
+    for (i, record_set) in metadata.record_sets.iter().enumerate() {
+        if record_set.data.is_empty() {
+            continue;
+        }
+        let location = format!("{root} > RecordSet({})", record_set.id.0);
+        let json_path = format!("$.recordSet[{i}]");
+        let fields = &record_set.fields;
+        let records = record_set.data.len();
+
+        for (j, field) in fields.iter().enumerate() {
+            if field.source.is_some() {
+                report.push(
+                    Severity::Error,
+                    codes::INLINE_DATA,
+                    "Fields of a record set with inline data cannot have a source.",
+                    format!("{location} > Field({})", field.id.0),
+                    format!("{json_path}.field[{j}].source"),
+                );
+            }
+        }
+
+        let mut shapes = Violations::default();
+        // By position of the (sub-)field: index in `field`, then in `subField`s
+        let mut types = BTreeMap::<Vec<usize>, (&Field, Violations)>::new();
+        for (r, record) in record_set.data.iter().enumerate() {
+            let record_number = r + 1;
+            let problems = key_problems(fields, record);
+            if !problems.is_empty() {
+                shapes.add(|| format!("record {record_number}: {}", problems.join(", ")));
+            }
+
+            for (j, field) in fields.iter().enumerate() {
+                if let Some(value) = record.get(field.id.0.as_ref())
+                    && let Some((position, field, problem)) =
+                        inline_problem(field, vec![j], value, field.repeated == Some(true))
+                {
+                    types
+                        .entry(position)
+                        .or_insert_with(|| (field, Violations::default()))
+                        .1
+                        .add(|| format!("record {record_number}: {problem}"));
+                }
+            }
+        }
+
+        if shapes.count > 0 {
+            report.push(
+                Severity::Error,
+                codes::INLINE_DATA,
+                format!(
+                    "{} of {records} records do not have exactly the fields of the record set, e.g. {}.",
+                    shapes.count,
+                    shapes.examples.join("; ")
+                ),
+                location.as_str(),
+                format!("{json_path}.data"),
+            );
+        }
+        for (position, (field, violations)) in types {
+            let field_path = position
+                .iter()
+                .enumerate()
+                .map(|(depth, i)| match depth {
+                    0 => format!(".field[{i}]"),
+                    _ => format!(".subField[{i}]"),
+                })
+                .collect::<String>();
+            let declared = field
+                .data_types
+                .iter()
+                .map(DataType::to_string)
+                .collect::<Vec<_>>()
+                .join(", ");
+            report.push(
+                Severity::Error,
+                codes::DATA_TYPE,
+                format!(
+                    "{} of {records} values do not match the dataType {declared}, e.g. {}.",
+                    violations.count,
+                    violations.examples.join("; ")
+                ),
+                format!("{location} > Field({})", field.id.0),
+                format!("{json_path}{field_path}.dataType"),
+            );
+        }
+    }
+    report
+}
+
+/// Keys of an inline record missing from or unknown to `fields`, in nested
+/// records too
+fn key_problems(
+    fields: &[Field],
+    record: &serde_json::Map<String, serde_json::Value>,
+) -> Vec<String> {
+    use serde_json::Value as Json;
+    let mut problems = fields
+        .iter()
+        .filter(|f| !record.contains_key(f.id.0.as_ref()))
+        .map(|f| format!("missing '{}'", f.id.0))
+        .collect::<Vec<_>>();
+    problems.extend(
+        record
+            .keys()
+            .filter(|key| !fields.iter().any(|f| f.id.0.as_ref() == key.as_str()))
+            .map(|key| format!("unknown '{key}'")),
+    );
+    for field in fields {
+        let (Some(sub_fields), Some(value)) = (&field.sub_fields, record.get(field.id.0.as_ref()))
+        else {
+            continue;
+        };
+        let nested = match value {
+            Json::Array(items) => items.iter().collect(),
+            value => vec![value],
+        };
+        for nested in nested.into_iter().filter_map(Json::as_object) {
+            problems.extend(key_problems(sub_fields, nested));
+        }
+    }
+    problems
+}
+
+/// Why an inline value does not match the data types of its field
+///
+/// Lists are only allowed for `repeated` fields and objects for fields with
+/// `subField`s, whose values they hold by sub-field id. Other values must
+/// convert as they do when the records are read, see [`convert_json`].
+///
+/// Problems are returned with the (sub-)field they concern and its position.
+fn inline_problem<'a>(
+    field: &'a Field,
+    position: Vec<usize>,
+    value: &serde_json::Value,
+    repeated: bool,
+) -> Option<(Vec<usize>, &'a Field, String)> {
+    use serde_json::Value as Json;
+    let data_type = primitive_type(field);
+    let problem = match value {
+        Json::Null => None,
+        Json::Array(items) if repeated => {
+            return items
+                .iter()
+                .find_map(|item| inline_problem(field, position.clone(), item, false));
+        }
+        Json::Array(_) => Some(format!("{value} is a list but the field is not repeated")),
+        Json::Object(nested) if let Some(sub_fields) = &field.sub_fields => {
+            return sub_fields.iter().enumerate().find_map(|(k, sub_field)| {
+                let value = nested.get(sub_field.id.0.as_ref())?;
+                let position = [position.as_slice(), &[k]].concat();
+                inline_problem(sub_field, position, value, sub_field.repeated == Some(true))
+            });
+        }
+        Json::Object(_) => Some(format!(
+            "{value} is an object but the field has no subField"
+        )),
+        _ => match convert_json(value.clone(), data_type, None) {
+            Ok(converted) => type_problem(field, &converted),
+            Err(e) => Some(e.to_string()),
+        },
+    };
+    problem.map(|problem| (position, field, problem))
+}
+
+/// Violations of one rule by the values of a field
+#[derive(Default)]
+struct Violations {
+    count: usize,
+    examples: Vec<String>,
+}
+
+impl Violations {
+    fn add(&mut self, example: impl FnOnce() -> String) {
+        self.count += 1;
+        if self.examples.len() < MAX_EXAMPLES {
+            self.examples.push(example());
+        }
+    }
+}
+
+/// Values of a referenced field, or why they cannot be read
+type Targets = std::result::Result<HashSet<String>, String>;
+
+struct RecordSetCheck<'a> {
+    dataset: &'a Dataset,
+    record_set: &'a RecordSet,
+    location: &'a str,
+    json_path: &'a str,
+    records: usize,
+    types: HashMap<usize, Violations>,
+    foreign_keys: HashMap<(usize, usize), Violations>,
+    keys: Violations,
+}
+
+impl<'a> RecordSetCheck<'a> {
+    fn new(
+        dataset: &'a Dataset,
+        record_set: &'a RecordSet,
+        location: &'a str,
+        json_path: &'a str,
+    ) -> Self {
+        Self {
+            dataset,
+            record_set,
+            location,
+            json_path,
+            records: 0,
+            types: HashMap::new(),
+            foreign_keys: HashMap::new(),
+            keys: Violations::default(),
+        }
+    }
+
+    fn run(&mut self, report: &mut ValidationReport) {
+        let mut records = match self.dataset.records(&self.record_set.id.0) {
+            Ok(records) => records,
+            Err(e) => {
+                report.push(
+                    Severity::Error,
+                    codes::DATA_READ,
+                    format!("Cannot read the records: {e}"),
+                    self.location,
+                    self.json_path,
+                );
+                return;
+            }
+        };
+        let fields = records.fields();
+
+        let key_indices = self
+            .record_set
+            .keys
+            .iter()
+            .filter_map(|key| fields.iter().position(|f| f.id == key.id))
+            .collect::<Vec<_>>();
+        let mut seen_keys = HashMap::<String, usize>::new();
+
+        // Values of every referenced field, read once per target
+        let mut targets = HashMap::<String, Targets>::new();
+        for field in fields {
+            for reference in &field.references {
+                let target = reference.field.id.0.to_string();
+                if let Entry::Vacant(entry) = targets.entry(target) {
+                    let values = self.referenced_values(entry.key());
+                    entry.insert(values);
+                }
+            }
+        }
+        for (i, field) in fields.iter().enumerate() {
+            for (j, reference) in field.references.iter().enumerate() {
+                if let Some(Err(e)) = targets.get(reference.field.id.0.as_ref()) {
+                    report.push(
+                        Severity::Error,
+                        codes::DATA_READ,
+                        format!("Cannot read the referenced field: {e}"),
+                        format!("{} > Field({})", self.location, field.id.0),
+                        format!("{}.field[{i}].references[{j}]", self.json_path),
+                    );
+                }
+            }
+        }
+
+        while let Some(values) = records.next_values() {
+            let record = self.records + 1;
+            let values = match values {
+                Ok(values) => values,
+                Err(e) => {
+                    // Records read so far are still checked and summarized
+                    report.push(
+                        Severity::Error,
+                        codes::DATA_READ,
+                        format!("Cannot read record {record}: {e}"),
+                        self.location,
+                        self.json_path,
+                    );
+                    break;
+                }
+            };
+            self.records = record;
+
+            for (i, (field, value)) in fields.iter().zip(&values).enumerate() {
+                match value {
+                    Ok(value) => {
+                        if let Some(problem) = type_problem(field, value) {
+                            self.types
+                                .entry(i)
+                                .or_default()
+                                .add(|| format!("record {record}: {problem}"));
+                        }
+                        for (j, reference) in field.references.iter().enumerate() {
+                            let Some(Ok(known)) = targets.get(reference.field.id.0.as_ref()) else {
+                                continue;
+                            };
+                            let missing = lookup_keys(value)
+                                .into_iter()
+                                .find(|key| !known.contains(key));
+                            if let Some(missing) = missing {
+                                self.foreign_keys
+                                    .entry((i, j))
+                                    .or_default()
+                                    .add(|| format!("record {record}: \"{missing}\""));
+                            }
+                        }
+                    }
+                    Err(e) => self
+                        .types
+                        .entry(i)
+                        .or_default()
+                        .add(|| format!("record {record}: {e}")),
+                }
+            }
+
+            if !key_indices.is_empty() {
+                let key = key_indices
+                    .iter()
+                    .map(|i| match &values[*i] {
+                        Ok(Value::Null) | Err(_) => None,
+                        Ok(value) => Some(key_string(value)),
+                    })
+                    .collect::<Option<Vec<_>>>();
+                match key {
+                    None => self.keys.add(|| format!("record {record}: null key")),
+                    Some(key) => {
+                        let key = key.join(", ");
+                        if let Some(first) = seen_keys.get(&key) {
+                            self.keys.add(|| {
+                                format!("record {record}: ({key}) already used by record {first}")
+                            });
+                        } else {
+                            seen_keys.insert(key, record);
+                        }
+                    }
+                }
+            }
+        }
+
+        self.summarize(fields, report);
+    }
+
+    /// Values of a field of another record set, as compared with [`key_string`]
+    fn referenced_values(&self, field_id: &str) -> Targets {
+        let record_set = self
+            .dataset
+            .metadata()
+            .record_sets
+            .iter()
+            .find(|rs| rs.fields.iter().any(|f| f.id.0 == field_id))
+            .ok_or_else(|| format!("field '{field_id}' does not exist"))?;
+        let records = self
+            .dataset
+            .records(&record_set.id.0)
+            .map_err(|e| e.to_string())?;
+        let mut values = HashSet::new();
+        for record in records {
+            let record = record.map_err(|e| e.to_string())?;
+            if let Some(value) = record.get(field_id) {
+                values.extend(lookup_keys(value));
+            }
+        }
+        Ok(values)
+    }
+
+    fn summarize(&self, fields: &[Field], report: &mut ValidationReport) {
+        let records = self.records;
+        let field_location = |i: usize| format!("{} > Field({})", self.location, fields[i].id.0);
+        let field_path = |i: usize| format!("{}.field[{i}]", self.json_path);
+
+        let mut types = self.types.iter().collect::<Vec<_>>();
+        types.sort_by_key(|(i, _)| **i);
+        for (i, violations) in types {
+            let field = &fields[*i];
+            let declared = field
+                .data_types
+                .iter()
+                .map(DataType::to_string)
+                .collect::<Vec<_>>()
+                .join(", ");
+            report.push(
+                Severity::Error,
+                codes::DATA_TYPE,
+                format!(
+                    "{} of {records} values do not match the dataType {declared}, e.g. {}.",
+                    violations.count,
+                    violations.examples.join("; ")
+                ),
+                field_location(*i),
+                format!("{}.dataType", field_path(*i)),
+            );
+        }
+
+        let mut foreign_keys = self.foreign_keys.iter().collect::<Vec<_>>();
+        foreign_keys.sort_by_key(|(key, _)| **key);
+        for ((i, j), violations) in foreign_keys {
+            let target = &fields[*i].references[*j].field.id.0;
+            report.push(
+                Severity::Error,
+                codes::FOREIGN_KEY,
+                format!(
+                    "{} of {records} values are not values of the referenced field \"{target}\", e.g. {}.",
+                    violations.count,
+                    violations.examples.join("; ")
+                ),
+                field_location(*i),
+                format!("{}.references[{j}]", field_path(*i)),
+            );
+        }
+
+        if self.keys.count > 0 {
+            report.push(
+                Severity::Error,
+                codes::KEY,
+                format!(
+                    "{} of {records} records have a null or duplicate key, e.g. {}.",
+                    self.keys.count,
+                    self.keys.examples.join("; ")
+                ),
+                self.location,
+                format!("{}.key", self.json_path),
+            );
+        }
+    }
+}
+
+/// Why a converted value does not match the data types of its field
+fn type_problem(field: &Field, value: &Value) -> Option<String> {
+    match value {
+        Value::List(items) => items.iter().find_map(|item| type_problem(field, item)),
+        Value::Text(text) if field.data_types.contains(&DataType::Url) => url::Url::parse(text)
+            .err()
+            .map(|e| format!("\"{text}\" is not a URL ({e})")),
+        _ => None,
+    }
+}
+
+/// Text compared when matching keys and references
+fn key_string(value: &Value) -> String {
+    match value {
+        Value::Text(text) => text.clone(),
+        other => serde_json::to_string(other).unwrap_or_default(),
+    }
+}
+
+/// Keys of a value, one per item for lists, none for null or empty text
+fn lookup_keys(value: &Value) -> Vec<String> {
+    match value {
+        Value::Null => Vec::new(),
+        Value::Text(text) if text.is_empty() => Vec::new(),
+        Value::List(items) => items.iter().flat_map(lookup_keys).collect(),
+        value => vec![key_string(value)],
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::croissant::jsonld::parse_metadata;
+    use serde_json::json;
+
+    fn document(
+        distribution: serde_json::Value,
+        record_sets: serde_json::Value,
+    ) -> serde_json::Value {
+        json!({
+            "@context": {"@vocab": "https://schema.org/", "cr": "http://mlcommons.org/croissant/"},
+            "@type": "sc:Dataset",
+            "name": "test",
+            "description": "test",
+            "version": "1.0.0",
+            "distribution": distribution,
+            "recordSet": record_sets,
+        })
+    }
+
+    /// A dataset with a single record set of labelled points holding `data`
+    fn inline_dataset(data: serde_json::Value) -> Dataset {
+        let document = document(
+            json!([]),
+            json!([{
+                "@type": "cr:RecordSet",
+                "@id": "points",
+                "name": "points",
+                "description": "Labelled points",
+                "field": [
+                    {
+                        "@type": "cr:Field",
+                        "@id": "points/label",
+                        "name": "label",
+                        "description": "label",
+                        "dataType": "sc:Text",
+                    },
+                    {
+                        "@type": "cr:Field",
+                        "@id": "points/at",
+                        "name": "at",
+                        "description": "at",
+                        "subField": [
+                            {
+                                "@type": "cr:Field",
+                                "@id": "points/at/x",
+                                "name": "x",
+                                "description": "x",
+                                "dataType": "sc:Integer",
+                            },
+                            {
+                                "@type": "cr:Field",
+                                "@id": "points/at/y",
+                                "name": "y",
+                                "description": "y",
+                                "dataType": "sc:Integer",
+                            },
+                        ],
+                    },
+                ],
+                "data": data,
+            }]),
+        );
+        Dataset::new(parse_metadata(document).unwrap(), ".")
+    }
+
+    fn issues(dataset: &Dataset) -> Vec<(String, String)> {
+        check_inline_data(dataset.metadata())
+            .issues
+            .into_iter()
+            .map(|issue| (issue.code, issue.message))
+            .collect()
+    }
+
+    #[test]
+    fn inline_values_are_checked_as_they_are_read() {
+        let dataset = inline_dataset(json!([
+            { "points/label": 42, "points/at": { "points/at/x": 1, "points/at/y": "2" } },
+        ]));
+        assert_eq!(issues(&dataset), vec![]);
+        let record = dataset.records("points").unwrap().next().unwrap().unwrap();
+        assert_eq!(record.get("points/label"), Some(&Value::Text("42".into())));
+
+        let dataset = inline_dataset(json!([
+            { "points/label": "a", "points/at": { "points/at/x": true, "points/at/y": 2 } },
+        ]));
+        assert_eq!(
+            issues(&dataset),
+            vec![(
+                codes::DATA_TYPE.to_string(),
+                "1 of 1 values do not match the dataType sc:Integer, e.g. record 1: \
+                 Invalid data type: true cannot be parsed as sc:Integer."
+                    .to_string()
+            )]
+        );
+        let report = check_inline_data(dataset.metadata());
+        assert_eq!(
+            report.issues[0].path,
+            "$.recordSet[0].field[1].subField[0].dataType"
+        );
+        assert!(report.issues[0].location.ends_with("Field(points/at/x)"));
+        assert!(dataset.records("points").unwrap().next().unwrap().is_err());
+    }
+
+    #[test]
+    fn nested_records_need_every_sub_field() {
+        let dataset = inline_dataset(json!([
+            { "points/label": "a", "points/at": { "points/at/x": 1, "points/at/z": 3 } },
+        ]));
+        assert_eq!(
+            issues(&dataset),
+            vec![(
+                codes::INLINE_DATA.to_string(),
+                "1 of 1 records do not have exactly the fields of the record set, e.g. \
+                 record 1: missing 'points/at/y', unknown 'points/at/z'."
+                    .to_string()
+            )]
+        );
+    }
+
+    #[test]
+    fn records_read_before_an_error_are_still_checked() {
+        let dir = tempfile::tempdir().unwrap();
+        std::fs::write(
+            dir.path().join("rows.csv"),
+            "id,name\n1,Ada\n1,Alan\n2\n3,Grace\n",
+        )
+        .unwrap();
+        let metadata = parse_metadata(document(
+            json!([{
+                "@type": "cr:FileObject",
+                "@id": "rows.csv",
+                "name": "rows.csv",
+                "contentUrl": "rows.csv",
+                "encodingFormat": "text/csv",
+            }]),
+            json!([{
+                "@type": "cr:RecordSet",
+                "@id": "rows",
+                "name": "rows",
+                "description": "Rows",
+                "key": {"@id": "rows/id"},
+                "field": [{
+                    "@type": "cr:Field",
+                    "@id": "rows/id",
+                    "name": "id",
+                    "description": "Identifier",
+                    "dataType": "sc:Integer",
+                    "source": {"fileObject": {"@id": "rows.csv"}, "extract": {"column": "id"}},
+                }],
+            }]),
+        ))
+        .unwrap();
+        let dataset = Dataset::new(metadata, dir.path());
+        let codes = validate_data(&dataset)
+            .issues
+            .into_iter()
+            .map(|issue| issue.code)
+            .collect::<Vec<_>>();
+        assert_eq!(codes, [codes::DATA_READ, codes::KEY]);
+    }
+}
@@ -0,0 +1,470 @@
+//! Detection of the dialect of CSV files
+//!
+//! The first [`SAMPLE_SIZE`] bytes of a file are inspected to find, in order:
+//!
+//! - its encoding, from a byte order mark, by checking that it is valid
+//!   UTF-8, or else by statistical detection,
+//! - its line terminator,
+//! - its quote character, the one starting the most fields,
+//! - whether quotes are escaped with a backslash rather than doubled,
+//! - its delimiter among `,`, `;`, tab and `|`, the one splitting rows into
+//!   the most consistent number of fields,
+//! - whether leading `#` lines are comments,
+//! - whether the first row is a header, by comparing the type of its values
+//!   with the type of the values below them.
+//!
+//! Properties given as overrides are taken as they are instead of being
+//! detected.
+use crate::croissant::core::{CsvDialect, DataType};
+use crate::croissant::errors::{Error, Result};
+use crate::croissant::infer::{infer_value_type, widen};
+use encoding_rs::{Encoding, UTF_8};
+use encoding_rs_io::DecodeReaderBytesBuilder;
+use std::collections::HashMap;
+use std::io::{Cursor, Read};
+
+/// Number of bytes inspected to detect a dialect
+pub const SAMPLE_SIZE: usize = 64 * 1024;
+
+/// Candidate delimiters, in order of preference
+const DELIMITERS: [char; 4] = [',', ';', '\t', '|'];
+
+/// Number of rows compared to detect the delimiter and the header
+const SAMPLE_ROWS: usize = 50;
+
+/// Reader of CSV records, over content decoded to UTF-8
+pub type CsvReader<'a> = csv::Reader<Box<dyn Read + 'a>>;
+
+/// Detect the dialect of CSV content
+///
+/// Only properties that differ from the usual ones are set (see
+/// [`CsvDialect`]), and those set in `overrides` are kept.
+pub fn sniff_dialect(mut reader: impl Read, overrides: &CsvDialect) -> Result<CsvDialect> {
+    let mut sample = Vec::new();
+    reader
+        .by_ref()
+        .take(SAMPLE_SIZE as u64)
+        .read_to_end(&mut sample)?;
+    sniff_sample(&sample, overrides)
+}
+
+/// Open CSV content in its detected dialect (see [`sniff_dialect`])
+///
+/// Returns the dialect, the reader of the records and the column names, see
+/// [`CsvDialect::csv_reader`].
+pub fn open_csv<'a>(
+    mut reader: impl Read + 'a,
+    overrides: &CsvDialect,
+) -> Result<(CsvDialect, CsvReader<'a>, Vec<String>)> {
+    let mut sample = Vec::new();
+    reader
+        .by_ref()
+        .take(SAMPLE_SIZE as u64)
+        .read_to_end(&mut sample)?;
+    let dialect = sniff_sample(&sample, overrides)?;
+    let (reader, headers) = dialect.csv_reader(Cursor::new(sample).chain(reader))?;
+    Ok((dialect, reader, headers))
+}
+
+impl CsvDialect {
+    /// Character encoding of the content, UTF-8 when unset
+    pub fn character_encoding(&self) -> Result<&'static Encoding> {
+        match &self.encoding {
+            None => Ok(UTF_8),
+            Some(label) => Encoding::for_label(label.trim().as_bytes())
+                .ok_or_else(|| Error::invalid_format(format!("Unknown CSV encoding '{label}'"))),
+        }
+    }
+
+    /// Decode content to UTF-8, dropping its byte order mark
+    ///
+    /// A byte order mark takes precedence over the declared encoding.
+    pub fn decode<'a>(&self, reader: impl Read + 'a) -> Result<Box<dyn Read + 'a>> {
+        let encoding = self.character_encoding()?;
+        let mut builder = DecodeReaderBytesBuilder::new();
+        builder.bom_override(true).strip_bom(true);
+        // UTF-8 content is passed through unchanged
+        if encoding != UTF_8 {
+            builder.encoding(Some(encoding));
+        }
+        Ok(Box::new(builder.build(reader)))
+    }
+
+    /// CSV reader builder for content in the dialect, already decoded
+    pub fn reader_builder(&self) -> Result<csv::ReaderBuilder> {
+        let mut builder = csv::ReaderBuilder::new();
+        if let Some(delimiter) = &self.delimiter {
+            builder.delimiter(single_byte("delimiter", delimiter)?);
+        }
+        if let Some(quote) = &self.quote_char {
+            builder.quote(single_byte("quote character", quote)?);
+        }
+        if self.double_quote == Some(false) {
+            builder.double_quote(false).escape(Some(b'\\'));
+        }
+        if let Some(prefix) = &self.comment_prefix {
+            builder.comment(Some(single_byte("comment prefix", prefix)?));
+        }
+        builder.has_headers(self.header != Some(false));
+        Ok(builder)
+    }
+
+    /// Read CSV content written in the dialect
+    ///
+    /// Returns the reader of the records and the trimmed column names. The
+    /// columns of files without a header are named `column_1`, `column_2`...
+    pub fn csv_reader<'a>(&self, reader: impl Read + 'a) -> Result<(CsvReader<'a>, Vec<String>)> {
+        let mut reader = self.reader_builder()?.from_reader(self.decode(reader)?);
+        let headers = reader.headers()?;
+        let headers = if self.header == Some(false) {
+            (1..=headers.len()).map(|i| format!("column_{i}")).collect()
+        } else {
+            headers.iter().map(|h| h.trim().to_string()).collect()
+        };
+        Ok((reader, headers))
+    }
+
+    /// Drop the properties set to their usual value
+    fn normalized(mut self) -> Self {
+        let encoding = self.character_encoding().ok();
+        if encoding == Some(UTF_8) {
+            self.encoding = None;
+        } else if let Some(encoding) = encoding {
+            self.encoding = Some(encoding.name().to_string());
+        }
+        self.delimiter = self.delimiter.filter(|d| d != ",");
+        self.quote_char = self.quote_char.filter(|q| q != "\"");
+        self.double_quote = self.double_quote.filter(|d| !d);
+        self.header = self.header.filter(|h| !h);
+        self.line_terminators = self.line_terminators.filter(|t| t != "\n" && t != "\r\n");
+        self
+    }
+}
+
+/// The single byte of a dialect character
+fn single_byte(property: &str, value: &str) -> Result<u8> {
+    match value.as_bytes() {
+        [byte] => Ok(*byte),
+        _ => Err(Error::invalid_format(format!(
+            "CSV {property} must be a single ASCII character, got {value:?}"
+        ))),
+    }
+}
+
+fn sniff_sample(sample: &[u8], overrides: &CsvDialect) -> Result<CsvDialect> {
+    let truncated = sample.len() >= SAMPLE_SIZE;
+    let mut dialect = overrides.clone();
+    if dialect.encoding.is_none() {
+        dialect.encoding = Some(detect_encoding(sample, truncated).name().to_string());
+    }
+    let (text, _, _) = dialect.character_encoding()?.decode(sample);
+
+    let terminator = detect_line_terminator(&text);
+    if dialect.line_terminators.is_none() {
+        dialect.line_terminators = Some(terminator.to_string());
+    }
+    // The last line of a truncated sample may be incomplete
+    let mut lines = text.split(terminator).collect::<Vec<_>>();
+    if truncated {
+        lines.pop();
+    }
+    lines.retain(|line| !line.trim().is_empty());
+    let text = lines.join("\n");
+
+    let quote = match &dialect.quote_char {
+        Some(quote) => quote.chars().next().unwrap_or('"'),
+        None => detect_quote(&lines),
+    };
+    dialect.quote_char = Some(quote.to_string());
+    if dialect.double_quote.is_none() {
+        dialect.double_quote = Some(!escapes_with_backslash(&lines, quote));
+    }
+
+    // Leading `#` lines are skipped while looking for the delimiter, then
+    // kept as the header when they split like the data
+    let commented = overrides.comment_prefix.is_none()
+        && lines.first().is_some_and(|line| line.starts_with('#'));
+    if commented {
+        dialect.comment_prefix = Some("#".to_string());
+    }
+    let mode = match &dialect.delimiter {
+        Some(_) => mode(&field_counts(&text, &dialect)?).map(|(count, _)| count),
+        None => {
+            let mut best = None;
+            for delimiter in DELIMITERS {
+                let candidate = CsvDialect {
+                    delimiter: Some(delimiter.to_string()),
+                    ..dialect.clone()
+                };
+                let counts = field_counts(&text, &candidate)?;
+                let Some((count, frequency)) = mode(&counts) else {
+                    continue;
+                };
+                let score = (frequency * 1000 / counts.len(), count);
+                if count > 1 && best.as_ref().is_none_or(|(s, _, _)| score > *s) {
+                    best = Some((score, delimiter, count));
+                }
+            }
+            if let Some((_, delimiter, _)) = best {
+                dialect.delimiter = Some(delimiter.to_string());
+            }
+            best.map(|(_, _, count)| count)
+        }
+    };
+    if commented {
+        let first = CsvDialect {
+            comment_prefix: None,
+            ..dialect.clone()
+        };
+        let first_count = field_counts(lines[0], &first)?.first().copied();
+        if first_count == mode {
+            dialect.comment_prefix = None;
+        }
+    }
+
+    if dialect.header.is_none() {
+        dialect.header = Some(has_header(&text, &dialect)?);
+    }
+    Ok(dialect.normalized())
+}
+
+/// Encoding of a sample: the one of its byte order mark, UTF-8 when it is
+/// valid UTF-8, or the most likely one
+fn detect_encoding(sample: &[u8], truncated: bool) -> &'static Encoding {
+    if let Some((encoding, _)) = Encoding::for_bom(sample) {
+        return encoding;
+    }
+    match std::str::from_utf8(sample) {
+        Ok(_) => return UTF_8,
+        // A character may be cut at the end of a truncated sample
+        Err(e) if truncated && e.error_len().is_none() => return UTF_8,
+        Err(_) => {}
+    }
+    let mut detector = chardetng::EncodingDetector::new();
+    detector.feed(sample, !truncated);
+    detector.guess(None, false)
+}
+
+/// Line terminator of the first line
+fn detect_line_terminator(text: &str) -> &'static str {
+    match text.find(['\r', '\n']) {
+        Some(i) if text[i..].starts_with("\r\n") => "\r\n",
+        Some(i) if text[i..].starts_with('\r') => "\r",
+        _ => "\n",
+    }
+}
+
+/// Whether a field may start at a character following `previous`
+fn starts_field(previous: Option<char>) -> bool {
+    previous.is_none_or(|c| DELIMITERS.contains(&c) || c == ' ')
+}
+
+/// Quote character starting the most fields, `"` unless `'` starts more
+fn detect_quote(lines: &[&str]) -> char {
+    let mut counts = [0usize; 2];
+    for line in lines.iter().take(SAMPLE_ROWS) {
+        let mut previous = None;
+        for c in line.chars() {
+            if starts_field(previous) {
+                match c {
+                    '"' => counts[0] += 1,
+                    '\'' => counts[1] += 1,
+                    _ => {}
+                }
+            }
+            previous = Some(c);
+        }
+    }
+    if counts[1] > counts[0] { '\'' } else { '"' }
+}
+
+/// Whether quotes are escaped with a backslash: `\"` is found but `""` is
+/// not, other than as an empty field
+fn escapes_with_backslash(lines: &[&str], quote: char) -> bool {
+    let (mut escaped, mut doubled) = (0, 0);
+    for line in lines.iter().take(SAMPLE_ROWS) {
+        let chars = line.chars().collect::<Vec<_>>();
+        let mut i = 0;
+        while i + 1 < chars.len() {
+            match (chars[i], chars[i + 1]) {
+                ('\\', c) if c == quote => escaped += 1,
+                (a, b) if a == quote && b == quote => {
+                    let empty = starts_field(i.checked_sub(1).map(|j| chars[j]))
+                        && chars.get(i + 2).is_none_or(|c| DELIMITERS.contains(c));
+                    if !empty {
+                        doubled += 1;
+                    }
+                }
+                _ => {
+                    i += 1;
+                    continue;
+                }
+            }
+            i += 2;
+        }
+    }
+    escaped > 0 && doubled == 0
+}
+
+/// Number of fields of the first rows of a sample
+fn field_counts(text: &str, dialect: &CsvDialect) -> Result<Vec<usize>> {
+    let mut builder = dialect.reader_builder()?;
+    builder.has_headers(false).flexible(true);
+    let mut reader = builder.from_reader(text.as_bytes());
+    Ok(reader
+        .records()
+        .take(SAMPLE_ROWS)
+        .map_while(|record| record.ok())
+        .map(|record| record.len())
+        .collect())
+}
+
+/// Most frequent value and its frequency
+fn mode(counts: &[usize]) -> Option<(usize, usize)> {
+    let mut frequencies = HashMap::<usize, usize>::new();
+    for count in counts {
+        *frequencies.entry(*count).or_default() += 1;
+    }
+    frequencies
+        .into_iter()
+        .max_by_key(|(count, frequency)| (*frequency, *count))
+}
+
+/// Whether the first row is a header
+///
+/// Every column votes: typed columns (numbers, booleans, dates) for a header
+/// when the first value is not of their type and against otherwise, text
+/// columns against when the first value appears below it and for when its
+/// length differs from the identical length of the values below. Ties are
+/// read as a header.
+fn has_header(text: &str, dialect: &CsvDialect) -> Result<bool> {
+    let mut builder = dialect.reader_builder()?;
+    builder.has_headers(false).flexible(true);
+    let mut reader = builder.from_reader(text.as_bytes());
+    let rows = reader
+        .records()
+        .take(SAMPLE_ROWS)
+        .map_while(|record| record.ok())
+        .collect::<Vec<_>>();
+    let Some((first, rows)) = rows.split_first() else {
+        return Ok(true);
+    };
+    if rows.is_empty() {
+        return Ok(true);
+    }
+
+    let mut votes = 0i64;
+    for (i, name) in first.iter().map(str::trim).enumerate() {
+        let values = rows
+            .iter()
+            .filter_map(|row| row.get(i).map(str::trim))
+            .filter(|value| !value.is_empty())
+            .collect::<Vec<_>>();
+        if name.is_empty() || values.is_empty() {
+            continue;
+        }
+        let column_type = values
+            .iter()
+            .map(|value| infer_value_type(value))
+            .reduce(|a, b| widen(&a, &b))
+            .unwrap_or(DataType::Text);
+        if column_type != DataType::Text {
+            let same = widen(&infer_value_type(name), &column_type) == column_type;
+            votes += if same { -1 } else { 1 };
+        } else if values.contains(&name) {
+            votes -= 1;
+        } else if values.iter().all(|v| v.len() == values[0].len()) {
+            votes += if name.len() == values[0].len() { -1 } else { 1 };
+        }
+    }
+    Ok(votes >= 0)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn sniff(content: &[u8]) -> CsvDialect {
+        sniff_dialect(content, &CsvDialect::default()).unwrap()
+    }
+
+    #[test]
+    fn usual_csv_has_no_dialect() {
+        assert_eq!(sniff(b"name,age\nada,36\nalan,41\n"), CsvDialect::default());
+        assert_eq!(sniff(b"name,age\r\nada,36\r\n"), CsvDialect::default());
+    }
+
+    #[test]
+    fn delimiters_quotes_and_escapes_are_detected() {
+        let dialect = sniff(b"name;note\n'a;b';1.5\n'c';2.5\n");
+        assert_eq!(dialect.delimiter.as_deref(), Some(";"));
+        assert_eq!(dialect.quote_char.as_deref(), Some("'"));
+
+        let dialect = sniff(b"id\tquote\n1\t\"say \\\"hi\\\"\"\n2\t\"ok\"\n");
+        assert_eq!(dialect.delimiter.as_deref(), Some("\t"));
+        assert_eq!(dialect.double_quote, Some(false));
+        let (mut reader, headers) = dialect
+            .csv_reader(&b"id\tquote\n1\t\"say \\\"hi\\\"\"\n"[..])
+            .unwrap();
+        assert_eq!(headers, vec!["id", "quote"]);
+        let record = reader.records().next().unwrap().unwrap();
+        assert_eq!(&record[1], "say \"hi\"");
+    }
+
+    #[test]
+    fn comments_headers_and_line_terminators_are_detected() {
+        let dialect = sniff(b"# exported data\n# by hand\nx|y\n1|2\n3|4\n");
+        assert_eq!(dialect.comment_prefix.as_deref(), Some("#"));
+        assert_eq!(dialect.delimiter.as_deref(), Some("|"));
+
+        // A commented header splitting like the data is the header
+        let dialect = sniff(b"#x,y\n1,2\n3,4\n");
+        assert_eq!(dialect.comment_prefix, None);
+
+        let content = b"1,2\r3,4\r5,6\r";
+        let dialect = sniff(content);
+        assert_eq!(dialect.header, Some(false));
+        assert_eq!(dialect.line_terminators.as_deref(), Some("\r"));
+        let (_, _, headers) = open_csv(&content[..], &CsvDialect::default()).unwrap();
+        assert_eq!(headers, vec!["column_1", "column_2"]);
+    }
+
+    #[test]
+    fn legacy_encodings_are_decoded() {
+        let content = b"ville,habitants\nB\xe9ziers,78000\nS\xe8te,44000\nN\xeemes,151000\n";
+        let dialect = sniff(content);
+        assert_eq!(dialect.encoding.as_deref(), Some("windows-1252"));
+        let (_, mut reader, _) = open_csv(&content[..], &CsvDialect::default()).unwrap();
+        let record = reader.records().next().unwrap().unwrap();
+        assert_eq!(&record[0], "Béziers");
+
+        let utf16 = "a,b\n1,2\n"
+            .encode_utf16()
+            .flat_map(u16::to_le_bytes)
+            .collect::<Vec<_>>();
+        let content = [&[0xff, 0xfe][..], &utf16].concat();
+        assert_eq!(sniff(&content).encoding.as_deref(), Some("UTF-16LE"));
+        let (_, _, headers) = open_csv(&content[..], &CsvDialect::default()).unwrap();
+        assert_eq!(headers, vec!["a", "b"]);
+    }
+
+    #[test]
+    fn overrides_are_kept() {
+        let overrides = CsvDialect {
+            delimiter: Some(";".to_string()),
+            header: Some(false),
+            ..CsvDialect::default()
+        };
+        let dialect = sniff_dialect(&b"a,b;c\nd,e;f\n"[..], &overrides).unwrap();
+        assert_eq!(dialect.delimiter.as_deref(), Some(";"));
+        assert_eq!(dialect.header, Some(false));
+        assert!(
+            CsvDialect {
+                delimiter: Some("::".to_string()),
+                ..CsvDialect::default()
+            }
+            .reader_builder()
+            .is_err()
+        );
+    }
+}
@@ -17,10 +17,26 @@ pub enum Error {
     #[error("CSV error: {0}")]
     Csv(#[from] csv::Error),
 
+    /// Parquet file could not be read
+    #[error("Parquet error: {0}")]
+    Parquet(#[from] parquet::errors::ParquetError),
+
+    /// Zip archive could not be read
+    #[error("Zip error: {0}")]
+    Zip(#[from] zip::result::ZipError),
+
     /// JSON serialization/deserialization failed
     #[error("JSON error: {0}")]
     Json(#[from] serde_json::Error),
 
+    /// TOML deserialization failed
+    #[error("TOML error: {0}")]
+    Toml(#[from] toml::de::Error),
+
+    /// YAML deserialization failed
+    #[error("YAML error: {0}")]
+    Yaml(#[from] serde_yaml::Error),
+
     /// File not found
     #[error("File not found: {path}")]
     FileNotFound { path: PathBuf },
@@ -45,6 +61,14 @@ pub enum Error {
     #[error("Invalid data type: {value} cannot be parsed as {data_type}")]
     InvalidDataType { value: String, data_type: String },
 
+    /// A referenced node does not exist in the document
+    #[error("Unknown node: {id}")]
+    UnknownNode { id: String },
+
+    /// The document uses a construct that is not supported yet
+    #[error("Unsupported: {0}")]
+    Unsupported(String),
+
     /// Generic error
     #[error("Error: {0}")]
     Generic(String),
@@ -89,6 +113,16 @@ impl Error {
         }
     }
 
+    /// Create a new unknown node error
+    pub fn unknown_node(id: impl Into<String>) -> Self {
+        Self::UnknownNode { id: id.into() }
+    }
+
+    /// Create a new unsupported construct error
+    pub fn unsupported(message: impl Into<String>) -> Self {
+        Self::Unsupported(message.into())
+    }
+
     /// Create a new invalid data type error
     pub fn invalid_data_type(value: impl Into<String>, data_type: impl Into<String>) -> Self {
         Self::InvalidDataType {
@@ -0,0 +1,120 @@
+//! Canonical formatting of Croissant documents
+//!
+//! A document is parsed into the typed model (see [`jsonld::parse_metadata`])
+//! and written back from it, which gives it:
+//!
+//! - the canonical `@context`, keeping the prefixes the document declares,
+//! - properties in the order of the specification examples,
+//! - single values written as themselves and several as an array, except for
+//!   `distribution`, `recordSet`, `field`, `subField` and `keywords` that are
+//!   always arrays,
+//! - two-space indentation and a trailing newline.
+//!
+//! Properties outside the model are kept, after the known ones: the order
+//! nodes were read in is not kept, unlike when metadata is otherwise written.
+use crate::croissant::core::{Metadata, SpecVersion};
+use crate::croissant::errors::{Error, Result};
+use crate::croissant::jsonld;
+use crate::croissant::migrate;
+use serde_json::Value;
+use std::path::Path;
+
+/// Format a Croissant document
+///
+/// Croissant 0.8 documents are rejected rather than silently migrated, see
+/// [`migrate::migrate`].
+pub fn format_str(content: &str) -> Result<String> {
+    let document: Value = serde_json::from_str(content)?;
+    let version = migrate::detect_version(&document);
+    if version < SpecVersion::V1_0 {
+        return Err(Error::unsupported(format!(
+            "Formatting a Croissant {version} document, migrate it first"
+        )));
+    }
+    format_metadata(&jsonld::parse_metadata(document)?)
+}
+
+/// Write metadata in the canonical form
+pub fn format_metadata(metadata: &Metadata) -> Result<String> {
+    let mut metadata = metadata.clone();
+    metadata.forget_property_order();
+    let mut formatted = serde_json::to_string_pretty(&metadata)?;
+    formatted.push('\n');
+    Ok(formatted)
+}
+
+/// Format a Croissant metadata file in place
+///
+/// Returns whether the file changed; files already formatted are not
+/// written.
+pub fn format_file(path: &Path) -> Result<bool> {
+    let content = std::fs::read_to_string(path).map_err(|_| Error::file_not_found(path))?;
+    let formatted = format_str(&content)?;
+    if formatted == content {
+        return Ok(false);
+    }
+    std::fs::write(path, formatted)?;
+    Ok(true)
+}
+
+/// Whether a Croissant metadata file is already formatted
+pub fn is_formatted(path: &Path) -> Result<bool> {
+    let content = std::fs::read_to_string(path).map_err(|_| Error::file_not_found(path))?;
+    Ok(format_str(&content)? == content)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const TITANIC: &str = include_str!("../../samples_jsonld/titanic.jsonld");
+
+    #[test]
+    fn formatting_is_idempotent() {
+        let formatted = format_str(TITANIC).unwrap();
+        assert_ne!(formatted, TITANIC);
+        assert!(formatted.starts_with("{\n  \"@context\": {\n"));
+        assert!(formatted.ends_with("}\n"));
+        assert_eq!(format_str(&formatted).unwrap(), formatted);
+
+        let document: Value = serde_json::from_str(&formatted).unwrap();
+        assert!(document["distribution"].is_array());
+        assert!(document["recordSet"][0]["field"].is_array());
+    }
+
+    #[test]
+    fn unknown_properties_come_after_known_ones() {
+        let mut document: Value = serde_json::from_str(TITANIC).unwrap();
+        let object = document.as_object_mut().unwrap();
+        let name = object.shift_remove("name").unwrap();
+        object.insert("sc:funder".to_string(), "Someone".into());
+        object.insert("name".to_string(), name);
+
+        let formatted = format_str(&document.to_string()).unwrap();
+        let position = |key: &str| formatted.find(&format!("\n  \"{key}\":")).unwrap();
+        assert!(position("name") < position("description"));
+        assert!(position("recordSet") < position("funder"));
+    }
+
+    #[test]
+    fn croissant_0_8_documents_are_rejected() {
+        let mut document: Value = serde_json::from_str(TITANIC).unwrap();
+        document["conformsTo"] = "http://mlcommons.org/croissant/0.8".into();
+        let error = format_str(&document.to_string()).unwrap_err();
+        assert!(error.to_string().contains("migrate it first"));
+        assert!(format_str("{").is_err());
+    }
+
+    #[test]
+    fn files_are_only_written_when_they_change() {
+        let directory = tempfile::tempdir().unwrap();
+        let path = directory.path().join("metadata.json");
+        std::fs::write(&path, TITANIC).unwrap();
+
+        assert!(!is_formatted(&path).unwrap());
+        assert!(format_file(&path).unwrap());
+        assert!(is_formatted(&path).unwrap());
+        assert!(!format_file(&path).unwrap());
+        assert!(format_file(&directory.path().join("missing.json")).is_err());
+    }
+}
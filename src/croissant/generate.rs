@@ -1,15 +1,102 @@
 use chrono::Utc;
 
+use crate::croissant::archive::{ArchiveFormat, Location, list_members, sha256_all};
+use crate::croissant::compression::{Compression, decompressed_name, open_decompressed};
+use crate::croissant::config::{GenerateConfig, responsible_ai};
 use crate::croissant::core::{
-    DataType, Distribution, Extract, Field, FieldSource, FileObject, Id, Metadata, RecordSet, Ref,
-    Text, default_context,
+    CrType, CroissantType, DataType, Distribution, Extract, Field, FieldRef, FieldSource,
+    FileObject, FileProperty, FileSet, Id, LangString, Metadata, RecordSet, Ref, Resource,
+    ResponsibleAi, SourceRef, SpecVersion, Text, default_context,
 };
+use crate::croissant::dialect::open_csv;
 use crate::croissant::errors::{Error, Result};
-use crate::croissant::utils::{calculate_sha256, get_csv_columns};
+use crate::croissant::format;
+use crate::croissant::infer::{
+    ColumnProfile, InferenceOptions, JsonLayout, SchemaColumn, infer_csv_readers,
+    infer_json_columns, infer_json_reader, infer_parquet_columns, infer_parquet_reader,
+};
+use crate::croissant::jsonld::{CSVW_NS, DCAT_NS};
+use crate::croissant::jsonpath;
+use crate::croissant::relations::{
+    TableValues, inclusion_dependencies, primary_key, read_csv_values,
+};
+use crate::croissant::utils::{aggregate_sha256, calculate_sha256, encoding_format_for_extension};
+use std::collections::{BTreeMap, BTreeSet};
 use std::path::Path;
 
+/// How checksums are recorded for the files of a directory
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum ChecksumMode {
+    /// No checksum is computed
+    None,
+    /// A single `sha256` on each `FileSet`, see [`FileSet::sha256`]
+    #[default]
+    Aggregate,
+    /// An additional `FileObject` with its own `sha256` for every file
+    PerFile,
+}
+
+/// Encoding format of Parquet files
+const PARQUET_ENCODING_FORMAT: &str = "application/x-parquet";
+
+/// Options for metadata generation
+#[derive(Debug, Clone, Default)]
+pub struct GenerateOptions {
+    pub inference: InferenceOptions,
+    /// Only used when generating from a directory
+    pub checksums: ChecksumMode,
+    /// Dataset properties and field overrides merged over the inferred metadata
+    pub config: GenerateConfig,
+    /// Responsible AI properties added to the dataset, taking precedence over
+    /// the `rai` section of [`GenerateOptions::config`]
+    pub rai: ResponsibleAi,
+}
+
+/// Generate Croissant metadata from a directory, a zip, tar or tar.gz archive,
+/// or a Parquet, JSON, JSON Lines or CSV file
+pub fn generate_metadata(
+    input_path: &Path,
+    output_path: Option<&Path>,
+    options: &GenerateOptions,
+) -> Result<Metadata> {
+    if input_path.is_dir() {
+        return generate_metadata_from_directory(input_path, output_path, options);
+    }
+    if ArchiveFormat::from_path(input_path).is_some() {
+        return generate_metadata_from_archive(input_path, output_path, options);
+    }
+    let extension = file_stem_and_extension(input_path).1;
+    match extension.as_deref().map(encoding_format_for_extension) {
+        Some(PARQUET_ENCODING_FORMAT) => parquet_metadata(input_path, output_path, options),
+        Some("application/json" | "application/jsonlines") => {
+            generate_metadata_from_json(input_path, output_path, options)
+        }
+        _ => generate_metadata_from_csv_with_options(input_path, output_path, options),
+    }
+}
+
+/// Read Responsible AI properties from a JSON file
+///
+/// The file holds a single object whose keys are RAI property names, with or
+/// without the `rai:` prefix (`dataCollection`, `rai:dataBiases`, ...).
+/// Values are validated before being returned.
+pub fn read_rai_file(path: &Path) -> Result<ResponsibleAi> {
+    let content = std::fs::read_to_string(path).map_err(|_| Error::file_not_found(path))?;
+    let properties: serde_json::Map<String, serde_json::Value> = serde_json::from_str(&content)?;
+    responsible_ai(properties)
+}
+
 /// Generate Croissant metadata from a CSV file
 pub fn generate_metadata_from_csv(csv_path: &Path, output_path: Option<&Path>) -> Result<Metadata> {
+    generate_metadata_from_csv_with_options(csv_path, output_path, &GenerateOptions::default())
+}
+
+/// Generate Croissant metadata from a CSV file with custom options
+pub fn generate_metadata_from_csv_with_options(
+    csv_path: &Path,
+    output_path: Option<&Path>,
+    options: &GenerateOptions,
+) -> Result<Metadata> {
     // Get file information
     let file_name = csv_path
         .file_name()
@@ -23,95 +110,1167 @@ pub fn generate_metadata_from_csv(csv_path: &Path, output_path: Option<&Path>) -
     // Calculate SHA-256 hash
     let file_sha256 = calculate_sha256(csv_path)?;
 
-    // Get column information
-    let (headers, first_row) = get_csv_columns(csv_path)?;
+    // Detect the dialect and infer column types from the file content
+    let (columns, dialect) = infer_csv_readers([open_decompressed(csv_path)], &options.inference)?;
 
     // Create fields based on CSV columns
+    let source = SourceRef::FileObject {
+        file_object: Ref {
+            id: Text::new(file_name.to_string()),
+        },
+    };
     let mut fields = Vec::new();
-    for (i, header) in headers.iter().enumerate() {
-        let field_id = format!("main/{header}");
-        let mut data_type = DataType::Url; // Default
-
-        // Try to infer data type from first row if available
-        if let Some(ref row) = first_row {
-            if i < row.len() {
-                data_type = DataType::from(&row[i]);
-            }
+    let mut enumerations = Vec::new();
+    for column in columns {
+        let mut field = column_field("main", &column, &source)?;
+        enumerations.extend(enumeration(
+            "main",
+            &mut field,
+            &column,
+            &options.inference,
+        )?);
+        fields.push(field);
+    }
+
+    // Create metadata structure
+    let dataset_name = file_stem_and_extension(csv_path).0;
+
+    let distribution = vec![
+        Distribution::builder()
+            .resource(Resource::FileObject(
+                FileObject::builder()
+                    .id(Id::new(file_name.to_string()))
+                    .name(Text::new(file_name.to_string()))
+                    .content_url(Text::new(file_name.to_string()))
+                    .content_size(Some(Text::new(format!("{file_size} B"))))
+                    .encoding_format(Text::new("text/csv".to_string()))
+                    .compress_format(compress_format(csv_path))
+                    .dialect(Some(dialect).filter(|d| !d.is_empty()))
+                    .sha256(Some(file_sha256))
+                    .build()
+                    .map_err(|e| Error::Builder(e.to_string()))?,
+            ))
+            .build()
+            .map_err(|e| Error::Builder(e.to_string()))?,
+    ];
+    let mut record_sets = vec![
+        RecordSet::builder()
+            .id(Id::new("main"))
+            .kind(CrType::RecordSet)
+            .fields(fields)
+            .build()
+            .map_err(|e| Error::Builder(e.to_string()))?,
+    ];
+    record_sets.extend(enumerations);
+    let metadata = build_metadata(
+        format!("{dataset_name}_dataset"),
+        format!("Dataset created from {file_name}"),
+        distribution,
+        record_sets,
+        options,
+    )?;
+
+    // Write metadata to file if output path is provided
+    if let Some(output_path) = output_path {
+        let metadata_json = format::format_metadata(&metadata)?;
+        std::fs::write(output_path, metadata_json)?;
+    }
+
+    Ok(metadata)
+}
+
+/// Generate Croissant metadata describing several related CSV files
+///
+/// Every file becomes a `FileObject` and a `RecordSet` named after its stem.
+/// The primary key detected in each file (see [`primary_key`]) becomes the
+/// `key` of its record set, and columns whose values are all included in the
+/// key of another file (see [`inclusion_dependencies`]) get `references` to
+/// it. Content URLs are file names, so the metadata belongs next to the files.
+pub fn generate_metadata_from_csv_files<P: AsRef<Path>>(
+    csv_paths: &[P],
+    output_path: Option<&Path>,
+    options: &GenerateOptions,
+) -> Result<Metadata> {
+    let mut tables = Vec::new();
+    let mut file_names = Vec::new();
+    let mut distribution = Vec::new();
+    for csv_path in csv_paths {
+        let csv_path = csv_path.as_ref();
+        let file_name = csv_path
+            .file_name()
+            .ok_or_else(|| Error::invalid_format("Invalid file path"))?
+            .to_string_lossy()
+            .to_string();
+        let (table_name, extension) = file_stem_and_extension(csv_path);
+        if extension.as_deref().map(encoding_format_for_extension) != Some("text/csv") {
+            return Err(Error::unsupported(format!(
+                "Only CSV files can be described together, got {file_name}"
+            )));
+        }
+        if tables.iter().any(|t: &TableValues| t.name == table_name) {
+            return Err(Error::invalid_format(format!(
+                "Several files are named {table_name}"
+            )));
         }
 
-        let field = Field::builder()
-            .id(Id::new(field_id))
-            .kind(crate::croissant::core::CrType::Field)
-            .name(Text::new(header))
-            .description(Text::new(format!("Field for {header}")))
-            .data_types(vec![data_type])
-            .source(
-                FieldSource::builder()
-                    .extract(Some(Extract::Column {
-                        name: Text::new(header),
-                    }))
-                    .source(crate::croissant::core::SourceRef::FileObject {
-                        file_object: Ref {
-                            id: Text::new(file_name.to_string()),
-                        },
-                    })
+        let table = read_csv_values(&table_name, csv_path, &options.inference)?;
+        let file_size = std::fs::metadata(csv_path)
+            .map_err(|_| Error::file_not_found(csv_path))?
+            .len();
+        distribution.push(
+            Distribution::builder()
+                .resource(Resource::FileObject(
+                    FileObject::builder()
+                        .id(Id::new(file_name.as_str()))
+                        .name(Text::new(file_name.as_str()))
+                        .content_url(Text::new(file_name.as_str()))
+                        .content_size(Some(Text::new(format!("{file_size} B"))))
+                        .encoding_format(Text::new("text/csv"))
+                        .compress_format(compress_format(csv_path))
+                        .dialect(Some(table.dialect.clone()).filter(|d| !d.is_empty()))
+                        .sha256(Some(calculate_sha256(csv_path)?))
+                        .build()
+                        .map_err(|e| Error::Builder(e.to_string()))?,
+                ))
+                .build()
+                .map_err(|e| Error::Builder(e.to_string()))?,
+        );
+        tables.push(table);
+        file_names.push(file_name);
+    }
+
+    let keys = tables.iter().map(primary_key).collect::<Vec<_>>();
+    let dependencies = inclusion_dependencies(&tables, &keys);
+
+    let mut record_sets = Vec::new();
+    let mut enumerations = Vec::new();
+    for (t, (table, file_name)) in tables.iter().zip(&file_names).enumerate() {
+        let source = SourceRef::FileObject {
+            file_object: Ref {
+                id: Id::new(file_name.as_str()),
+            },
+        };
+        let mut fields = table
+            .columns
+            .iter()
+            .map(|column| column_field(&table.name, &column.profile, &source))
+            .collect::<Result<Vec<_>>>()?;
+        for dependency in dependencies.iter().filter(|d| d.table == t) {
+            let target = &tables[dependency.target_table];
+            let target_column = &target.columns[dependency.target_column].profile.name;
+            fields[dependency.column].references.push(FieldRef {
+                field: Ref {
+                    id: Id::new(format!("{}/{target_column}", target.name)),
+                },
+            });
+        }
+        for (field, column) in fields.iter_mut().zip(&table.columns) {
+            enumerations.extend(enumeration(
+                &table.name,
+                field,
+                &column.profile,
+                &options.inference,
+            )?);
+        }
+        let record_set_keys = keys[t]
+            .map(|k| Ref {
+                id: fields[k].id.clone(),
+            })
+            .into_iter()
+            .collect();
+
+        record_sets.push(
+            RecordSet::builder()
+                .id(Id::new(table.name.as_str()))
+                .kind(CrType::RecordSet)
+                .keys(record_set_keys)
+                .fields(fields)
+                .build()
+                .map_err(|e| Error::Builder(e.to_string()))?,
+        );
+    }
+
+    record_sets.extend(enumerations);
+
+    let dataset_name = csv_paths
+        .first()
+        .and_then(|p| p.as_ref().canonicalize().ok())
+        .and_then(|p| {
+            p.parent()?
+                .file_name()
+                .map(|n| n.to_string_lossy().to_string())
+        })
+        .unwrap_or_else(|| "tables".to_string());
+    let metadata = build_metadata(
+        format!("{dataset_name}_dataset"),
+        format!("Dataset created from {}", file_names.join(", ")),
+        distribution,
+        record_sets,
+        options,
+    )?;
+
+    if let Some(output_path) = output_path {
+        let metadata_json = format::format_metadata(&metadata)?;
+        std::fs::write(output_path, metadata_json)?;
+    }
+
+    Ok(metadata)
+}
+
+/// Generate Croissant metadata from the schema of a Parquet file
+///
+/// Struct columns become fields with `subField`s and list or map columns
+/// become `repeated` fields; nested values are extracted by their dotted
+/// column path.
+pub fn generate_metadata_from_parquet(
+    parquet_path: &Path,
+    output_path: Option<&Path>,
+) -> Result<Metadata> {
+    parquet_metadata(parquet_path, output_path, &GenerateOptions::default())
+}
+
+fn parquet_metadata(
+    parquet_path: &Path,
+    output_path: Option<&Path>,
+    options: &GenerateOptions,
+) -> Result<Metadata> {
+    let columns = infer_parquet_columns(parquet_path)?;
+    generate_metadata_from_schema(
+        parquet_path,
+        output_path,
+        PARQUET_ENCODING_FORMAT,
+        &columns,
+        None,
+        options,
+    )
+}
+
+/// Generate Croissant metadata from a JSON or JSON Lines file
+///
+/// Records are sampled (see [`InferenceOptions::max_rows`]) to infer a field
+/// tree: objects become `subField`s, arrays `repeated` fields, and every
+/// field is extracted with a `jsonPath`.
+pub fn generate_metadata_from_json(
+    json_path: &Path,
+    output_path: Option<&Path>,
+    options: &GenerateOptions,
+) -> Result<Metadata> {
+    let profile = infer_json_columns(json_path, &options.inference)?;
+    generate_metadata_from_schema(
+        json_path,
+        output_path,
+        json_encoding_format(profile.layout),
+        &profile.columns,
+        Some(profile.layout.root()),
+        options,
+    )
+}
+
+/// Describe a single file whose columns are already known
+fn generate_metadata_from_schema(
+    input_path: &Path,
+    output_path: Option<&Path>,
+    encoding_format: &str,
+    columns: &[SchemaColumn],
+    json_root: Option<&str>,
+    options: &GenerateOptions,
+) -> Result<Metadata> {
+    let file_name = input_path
+        .file_name()
+        .ok_or_else(|| Error::invalid_format("Invalid file path"))?
+        .to_string_lossy()
+        .to_string();
+    let file_size = std::fs::metadata(input_path)
+        .map_err(|_| Error::file_not_found(input_path))?
+        .len();
+    let file_sha256 = calculate_sha256(input_path)?;
+
+    let source = SourceRef::FileObject {
+        file_object: Ref {
+            id: Id::new(file_name.as_str()),
+        },
+    };
+    let fields = columns
+        .iter()
+        .map(|column| schema_field("main", column, &source, json_root))
+        .collect::<Result<Vec<_>>>()?;
+
+    let distribution = vec![
+        Distribution::builder()
+            .resource(Resource::FileObject(
+                FileObject::builder()
+                    .id(Id::new(file_name.as_str()))
+                    .name(Text::new(file_name.as_str()))
+                    .content_url(Text::new(file_name.as_str()))
+                    .content_size(Some(Text::new(format!("{file_size} B"))))
+                    .encoding_format(Text::new(encoding_format))
+                    .compress_format(compress_format(input_path))
+                    .sha256(Some(file_sha256))
                     .build()
                     .map_err(|e| Error::Builder(e.to_string()))?,
-            )
+            ))
             .build()
-            .map_err(|e| Error::Builder(e.to_string()))?;
+            .map_err(|e| Error::Builder(e.to_string()))?,
+    ];
+    let record_sets = vec![
+        RecordSet::builder()
+            .id(Id::new("main"))
+            .kind(CrType::RecordSet)
+            .fields(fields)
+            .build()
+            .map_err(|e| Error::Builder(e.to_string()))?,
+    ];
 
-        fields.push(field);
+    let dataset_name = file_stem_and_extension(input_path).0;
+    let metadata = build_metadata(
+        format!("{dataset_name}_dataset"),
+        format!("Dataset created from {file_name}"),
+        distribution,
+        record_sets,
+        options,
+    )?;
+
+    if let Some(output_path) = output_path {
+        let metadata_json = format::format_metadata(&metadata)?;
+        std::fs::write(output_path, metadata_json)?;
     }
 
-    // Create metadata structure
-    let dataset_name = csv_path
-        .file_stem()
+    Ok(metadata)
+}
+
+/// A file found while walking a directory or listing an archive
+struct DirectoryFile {
+    location: Location,
+    /// Path relative to the directory or archive, with `/` separators
+    relative: String,
+    extension: Option<String>,
+}
+
+/// Generate Croissant metadata from a directory of files
+///
+/// Files are grouped by extension into one `FileSet` and one `RecordSet`
+/// each, CSV files being further grouped by their set of columns. Record sets
+/// of CSV files expose the inferred columns of all the shards, the others
+/// expose the name, path and content of every file.
+/// Hidden files and directories are skipped.
+pub fn generate_metadata_from_directory(
+    dir_path: &Path,
+    output_path: Option<&Path>,
+    options: &GenerateOptions,
+) -> Result<Metadata> {
+    if !dir_path.is_dir() {
+        return Err(Error::file_not_found(dir_path));
+    }
+    let output = output_path.and_then(|p| p.canonicalize().ok());
+
+    let mut files = Vec::new();
+    let walker = walkdir::WalkDir::new(dir_path)
+        .sort_by_file_name()
+        .into_iter()
+        .filter_entry(|e| e.depth() == 0 || !e.file_name().to_string_lossy().starts_with('.'));
+    for entry in walker {
+        let entry = entry.map_err(|e| Error::Io(e.into()))?;
+        if !entry.file_type().is_file() {
+            continue;
+        }
+        let path = entry.into_path();
+        if output.is_some() && path.canonicalize().ok() == output {
+            continue;
+        }
+        let relative = path
+            .strip_prefix(dir_path)
+            .unwrap_or(&path)
+            .components()
+            .map(|c| c.as_os_str().to_string_lossy())
+            .collect::<Vec<_>>()
+            .join("/");
+        let extension = path
+            .extension()
+            .map(|ext| ext.to_string_lossy().to_string());
+        files.push(DirectoryFile {
+            location: Location::File(path),
+            relative,
+            extension,
+        });
+    }
+    if files.is_empty() {
+        return Err(Error::invalid_format(format!(
+            "No files found in {}",
+            dir_path.display()
+        )));
+    }
+
+    let (distribution, record_sets) = describe_files(files, None, options)?;
+    let dataset_name = dir_path
+        .canonicalize()?
+        .file_name()
         .unwrap_or_default()
         .to_string_lossy()
         .to_string();
+    let metadata = build_metadata(
+        format!("{dataset_name}_dataset"),
+        format!("Dataset created from the directory {dataset_name}"),
+        distribution,
+        record_sets,
+        options,
+    )?;
 
-    let metadata = Metadata::builder()
-        .context(default_context()?)
-        .kind(crate::croissant::core::CroissantType::Dataset)
-        .name(Text::new(format!("{dataset_name}_dataset")))
-        .description(Text::new(format!("Dataset created from {file_name}")))
-        .date_published(Some(Text::new(Utc::now().format("%Y-%m-%d"))))
-        .version(Text::new("1.0.0"))
-        .distribution(vec![
+    if let Some(output_path) = output_path {
+        let metadata_json = format::format_metadata(&metadata)?;
+        std::fs::write(output_path, metadata_json)?;
+    }
+
+    Ok(metadata)
+}
+
+/// Generate Croissant metadata from a zip, tar or tar.gz archive
+///
+/// The archive becomes a `FileObject` and its files are described as for a
+/// directory (see [`generate_metadata_from_directory`]), by `FileSet`s
+/// `containedIn` the archive. Members are read from the archive without
+/// extracting it. The content URL is the archive file name, so the metadata
+/// belongs next to it. Per-file checksums are not available for members,
+/// whose file sets get an aggregate `sha256` instead.
+pub fn generate_metadata_from_archive(
+    archive_path: &Path,
+    output_path: Option<&Path>,
+    options: &GenerateOptions,
+) -> Result<Metadata> {
+    let format = ArchiveFormat::from_path(archive_path).ok_or_else(|| {
+        Error::unsupported(format!("archive format of {}", archive_path.display()))
+    })?;
+    if !archive_path.is_file() {
+        return Err(Error::file_not_found(archive_path));
+    }
+    let archive_name = archive_path
+        .file_name()
+        .unwrap_or_default()
+        .to_string_lossy()
+        .to_string();
+
+    let files = list_members(archive_path, format)?
+        .into_iter()
+        .filter(|member| {
+            !member
+                .path
+                .split('/')
+                .any(|c| c.starts_with('.') || c == "__MACOSX")
+        })
+        .map(|member| {
+            let extension = member
+                .path
+                .rsplit('/')
+                .next()
+                .and_then(|name| name.rsplit_once('.'))
+                .map(|(_, ext)| ext.to_string());
+            DirectoryFile {
+                location: Location::Member {
+                    archive: archive_path.to_path_buf(),
+                    format,
+                    path: member.path.clone(),
+                },
+                relative: member.path,
+                extension,
+            }
+        })
+        .collect::<Vec<_>>();
+    if files.is_empty() {
+        return Err(Error::invalid_format(format!(
+            "No files found in {}",
+            archive_path.display()
+        )));
+    }
+
+    let archive_id = Id::new(archive_name.as_str());
+    let size = std::fs::metadata(archive_path)?.len();
+    let mut distribution = vec![
+        Distribution::builder()
+            .resource(Resource::FileObject(
+                FileObject::builder()
+                    .id(archive_id.clone())
+                    .name(Text::new(archive_name.as_str()))
+                    .content_url(Text::new(archive_name.as_str()))
+                    .content_size(Some(Text::new(format!("{size} B"))))
+                    .encoding_format(Text::new(format.encoding_format()))
+                    .sha256(match options.checksums {
+                        ChecksumMode::None => None,
+                        _ => Some(calculate_sha256(archive_path)?),
+                    })
+                    .build()
+                    .map_err(|e| Error::Builder(e.to_string()))?,
+            ))
+            .build()
+            .map_err(|e| Error::Builder(e.to_string()))?,
+    ];
+    let (file_sets, record_sets) = describe_files(files, Some(&archive_id), options)?;
+    distribution.extend(file_sets);
+
+    let dataset_name = format.stem(&archive_name).to_string();
+    let metadata = build_metadata(
+        format!("{dataset_name}_dataset"),
+        format!("Dataset created from the archive {archive_name}"),
+        distribution,
+        record_sets,
+        options,
+    )?;
+
+    if let Some(output_path) = output_path {
+        let metadata_json = format::format_metadata(&metadata)?;
+        std::fs::write(output_path, metadata_json)?;
+    }
+
+    Ok(metadata)
+}
+
+/// Distribution and record sets describing the files of a directory or of
+/// the archive `container`
+///
+/// Files are grouped by extension into one `FileSet` and one `RecordSet`
+/// each. CSV files with different columns are further split into one group
+/// per set of columns (`csv_1`, `csv_2`, ...), listing their files by path.
+fn describe_files(
+    files: Vec<DirectoryFile>,
+    container: Option<&Id>,
+    options: &GenerateOptions,
+) -> Result<(Vec<Distribution>, Vec<RecordSet>)> {
+    let mut extensions = BTreeMap::<String, Vec<DirectoryFile>>::new();
+    for file in files {
+        let key = file
+            .extension
+            .as_deref()
+            .map(str::to_lowercase)
+            .unwrap_or_else(|| "other".to_string());
+        extensions.entry(key).or_default().push(file);
+    }
+    let mut groups = Vec::new();
+    for (key, files) in extensions {
+        if encoding_format_for_extension(&key) != "text/csv" {
+            groups.push((key, files, false));
+            continue;
+        }
+        let schemas = split_by_columns(files, &options.inference)?;
+        if schemas.len() == 1 {
+            groups.extend(schemas.into_iter().map(|files| (key.clone(), files, false)));
+        } else {
+            for (i, files) in schemas.into_iter().enumerate() {
+                groups.push((format!("{key}_{}", i + 1), files, true));
+            }
+        }
+    }
+
+    let mut distribution = Vec::new();
+    let mut record_sets = Vec::new();
+    let mut enumerations = Vec::new();
+    for (key, files, by_path) in groups {
+        let file_set_id = format!("{key}-files");
+        let encoding_format = files[0]
+            .extension
+            .as_deref()
+            .map(encoding_format_for_extension)
+            .unwrap_or("application/octet-stream");
+        let per_file = options.checksums == ChecksumMode::PerFile && container.is_none();
+
+        let digests = match options.checksums {
+            ChecksumMode::None => Vec::new(),
+            _ => sha256_all(&files.iter().map(|f| f.location.clone()).collect::<Vec<_>>())?,
+        };
+
+        let source = SourceRef::FileSet {
+            file_set: Ref {
+                id: Id::new(file_set_id.as_str()),
+            },
+        };
+        let mut dialect = None;
+        let fields = if encoding_format == PARQUET_ENCODING_FORMAT {
+            let columns = match &files[0].location {
+                Location::File(path) => infer_parquet_columns(path)?,
+                location => infer_parquet_reader(bytes::Bytes::from(location.read()?))?,
+            };
+            columns
+                .iter()
+                .map(|column| schema_field(&key, column, &source, None))
+                .collect::<Result<Vec<_>>>()?
+        } else if matches!(
+            encoding_format,
+            "application/json" | "application/jsonlines"
+        ) {
+            let profile = infer_json_reader(
+                files[0].location.open()?,
+                encoding_format == "application/jsonlines",
+                &files[0].location.to_string(),
+                &options.inference,
+            )?;
+            profile
+                .columns
+                .iter()
+                .map(|column| schema_field(&key, column, &source, Some(profile.layout.root())))
+                .collect::<Result<Vec<_>>>()?
+        } else if encoding_format == "text/csv" {
+            let readers = files.iter().map(|f| f.location.open());
+            let (columns, detected) = infer_csv_readers(readers, &options.inference)?;
+            dialect = Some(detected).filter(|d| !d.is_empty());
+            let mut fields = Vec::new();
+            for column in columns {
+                let mut field = column_field(&key, &column, &source)?;
+                enumerations.extend(enumeration(&key, &mut field, &column, &options.inference)?);
+                fields.push(field);
+            }
+            fields
+        } else {
+            let mut properties = vec![
+                ("filename", FileProperty::FileName, DataType::Text),
+                ("fullpath", FileProperty::FullPath, DataType::Text),
+            ];
+            if let Some(data_type) = content_type(encoding_format) {
+                properties.push(("content", FileProperty::Content, data_type));
+            }
+            properties
+                .into_iter()
+                .map(|(name, property, data_type)| {
+                    let extract = Extract::FileProperty { property };
+                    source_field(&key, name, data_type, &source, extract)
+                })
+                .collect::<Result<Vec<_>>>()?
+        };
+
+        distribution.push(
             Distribution::builder()
-                .resource(crate::croissant::core::Resource::FileObject(
-                    FileObject::builder()
-                        .id(Id::new(file_name.to_string()))
-                        .name(Text::new(file_name))
-                        .content_size(Some(Text::new(format!("{file_size} B"))))
-                        .encoding_format(Text::new("text/csv".to_string()))
-                        .sha256(Some(file_sha256))
+                .resource(Resource::FileSet(
+                    FileSet::builder()
+                        .id(Id::new(file_set_id.as_str()))
+                        .sources(container.cloned().into_iter().collect())
+                        .encoding_format(Text::new(encoding_format))
+                        .includes(match by_path {
+                            true => files
+                                .iter()
+                                .map(|f| Text::new(glob::Pattern::escape(&f.relative)))
+                                .collect(),
+                            false => include_patterns(&files),
+                        })
+                        .dialect(dialect.clone())
+                        .sha256(match options.checksums {
+                            ChecksumMode::None => None,
+                            _ if per_file => None,
+                            _ => Some(aggregate_sha256(
+                                files
+                                    .iter()
+                                    .zip(&digests)
+                                    .map(|(f, d)| (f.relative.as_str(), d.as_str())),
+                            )),
+                        })
                         .build()
                         .map_err(|e| Error::Builder(e.to_string()))?,
                 ))
                 .build()
-                .unwrap(), // TODO: error
-        ])
-        .record_sets(vec![
+                .map_err(|e| Error::Builder(e.to_string()))?,
+        );
+
+        if per_file {
+            for (file, digest) in files.iter().zip(digests) {
+                let Location::File(path) = &file.location else {
+                    continue;
+                };
+                let size = std::fs::metadata(path)?.len();
+                distribution.push(
+                    Distribution::builder()
+                        .resource(Resource::FileObject(
+                            FileObject::builder()
+                                .id(Id::new(file.relative.as_str()))
+                                .name(Text::new(file.relative.as_str()))
+                                .content_url(Text::new(file.relative.as_str()))
+                                .content_size(Some(Text::new(format!("{size} B"))))
+                                .encoding_format(Text::new(encoding_format))
+                                .dialect(dialect.clone())
+                                .sha256(Some(digest))
+                                .build()
+                                .map_err(|e| Error::Builder(e.to_string()))?,
+                        ))
+                        .build()
+                        .map_err(|e| Error::Builder(e.to_string()))?,
+                );
+            }
+        }
+
+        record_sets.push(
             RecordSet::builder()
-                .id(Id::new("main"))
-                .kind(crate::croissant::core::CrType::RecordSet)
-                .keys(vec![Ref {
-                    id: Id::new("main"),
-                }])
+                .id(Id::new(key.as_str()))
+                .kind(CrType::RecordSet)
                 .fields(fields)
                 .build()
                 .map_err(|e| Error::Builder(e.to_string()))?,
-        ])
+        );
+    }
+    record_sets.extend(enumerations);
+    Ok((distribution, record_sets))
+}
+
+/// CSV files grouped by their set of columns, in order of first appearance
+fn split_by_columns(
+    files: Vec<DirectoryFile>,
+    options: &InferenceOptions,
+) -> Result<Vec<Vec<DirectoryFile>>> {
+    let mut schemas = Vec::<(BTreeSet<String>, Vec<DirectoryFile>)>::new();
+    for file in files {
+        let (_, _, headers) = open_csv(file.location.open()?, &options.dialect)?;
+        let columns = headers.into_iter().collect::<BTreeSet<_>>();
+        match schemas.iter_mut().find(|(c, _)| *c == columns) {
+            Some((_, group)) => group.push(file),
+            None => schemas.push((columns, vec![file])),
+        }
+    }
+    Ok(schemas.into_iter().map(|(_, files)| files).collect())
+}
+
+/// Stem and extension of a file name, ignoring a compression extension
+/// (`data.csv.gz` → `data`, `csv`)
+fn file_stem_and_extension(path: &Path) -> (String, Option<String>) {
+    let name = path.file_name().unwrap_or_default().to_string_lossy();
+    let name = Path::new(decompressed_name(&name));
+    let part = |p: Option<&std::ffi::OsStr>| p.map(|p| p.to_string_lossy().to_string());
+    (
+        part(name.file_stem()).unwrap_or_default(),
+        part(name.extension()),
+    )
+}
+
+/// `compressFormat` of a file compressed as a whole
+fn compress_format(path: &Path) -> Option<Text> {
+    Compression::from_path(path).map(|c| Text::new(c.encoding_format()))
+}
+
+/// Glob patterns matching every file of a group
+fn include_patterns(files: &[DirectoryFile]) -> Vec<Text> {
+    let extensions = files
+        .iter()
+        .filter_map(|f| f.extension.as_deref())
+        .collect::<BTreeSet<_>>();
+    if extensions.is_empty() {
+        return files
+            .iter()
+            .map(|f| Text::new(f.relative.as_str()))
+            .collect();
+    }
+
+    let parents = files
+        .iter()
+        .map(|f| f.relative.rsplit_once('/').map(|(parent, _)| parent))
+        .collect::<BTreeSet<_>>();
+    let prefix = match parents.into_iter().collect::<Vec<_>>().as_slice() {
+        [None] => String::new(),
+        [Some(parent)] => format!("{}/", glob::Pattern::escape(parent)),
+        _ => "**/".to_string(),
+    };
+    extensions
+        .into_iter()
+        .map(|ext| Text::new(format!("{prefix}*.{}", glob::Pattern::escape(ext))))
+        .collect()
+}
+
+/// Encoding format of JSON files with the given layout
+fn json_encoding_format(layout: JsonLayout) -> &'static str {
+    match layout {
+        JsonLayout::Lines => "application/jsonlines",
+        JsonLayout::Array | JsonLayout::Object => "application/json",
+    }
+}
+
+/// Data type of the content of files with the given encoding format
+fn content_type(encoding_format: &str) -> Option<DataType> {
+    match encoding_format.split_once('/') {
+        Some(("image", _)) => Some(DataType::ImageObject),
+        Some(("audio", _)) => Some(DataType::CustomIri(Text::new("sc:AudioObject"))),
+        Some(("video", _)) => Some(DataType::CustomIri(Text::new("sc:VideoObject"))),
+        Some(("text", _)) => Some(DataType::Text),
+        _ => None,
+    }
+}
+
+/// Field of a record set reading from a distribution
+fn source_field(
+    record_set: &str,
+    name: &str,
+    data_type: DataType,
+    source: &SourceRef,
+    extract: Extract,
+) -> Result<Field> {
+    Field::builder()
+        .id(Id::new(format!("{record_set}/{name}")))
+        .kind(CrType::Field)
+        .name(Text::new(name))
+        .description(Text::new(format!("Field for {name}")))
+        .data_types(vec![data_type])
+        .source(Some(
+            FieldSource::builder()
+                .extract(Some(extract))
+                .source(source.clone())
+                .build()
+                .map_err(|e| Error::Builder(e.to_string()))?,
+        ))
         .build()
-        .unwrap(); // TODO: error
+        .map_err(|e| Error::Builder(e.to_string()))
+}
 
-    // Write metadata to file if output path is provided
-    if let Some(output_path) = output_path {
-        let metadata_json = serde_json::to_string_pretty(&metadata)?;
-        std::fs::write(output_path, metadata_json)?;
+/// Field of a record set reading a CSV column
+///
+/// The description gives the evidence the data type was inferred from, and
+/// the values taken as missing are recorded as `csvw:null` so that they are
+/// read back as nulls.
+fn column_field(record_set: &str, column: &ColumnProfile, source: &SourceRef) -> Result<Field> {
+    let extract = Extract::Column {
+        name: Text::new(column.name.as_str()),
+    };
+    let data_type = column.data_type.clone();
+    let mut field = source_field(record_set, &column.name, data_type, source, extract)?;
+    field.description = LangString::new(format!("Field for {}: {}", column.name, evidence(column)));
+    if let Some(source) = &mut field.source {
+        source.nulls = column.null_tokens.iter().cloned().collect();
+    }
+    Ok(field)
+}
+
+/// How the data type of a column was inferred: rows inspected, missing
+/// values and widenings
+fn evidence(column: &ColumnProfile) -> String {
+    let mut evidence = format!("{} inferred from {} rows", column.data_type, column.rows);
+    if column.nulls > 0 {
+        evidence.push_str(&format!(
+            ", {} missing ({:.1}%)",
+            column.nulls,
+            column.null_ratio() * 100.0
+        ));
+    }
+    for widening in &column.widenings {
+        evidence.push_str(&format!(
+            ", widened from {} to {} at row {} ({:?})",
+            widening.from, widening.to, widening.row, widening.value
+        ));
+    }
+    evidence
+}
+
+/// Enumeration record set listing the values of a categorical column
+///
+/// The field of the column becomes a `cr:Label` referencing the `value` of
+/// the enumeration, whose values are given as inline `data`. Nothing is
+/// returned when the column is not categorical (see
+/// [`ColumnProfile::categories`]) or its field already references another.
+fn enumeration(
+    record_set: &str,
+    field: &mut Field,
+    column: &ColumnProfile,
+    options: &InferenceOptions,
+) -> Result<Option<RecordSet>> {
+    let Some(categories) = column.categories(options) else {
+        return Ok(None);
+    };
+    if !field.references.is_empty() {
+        return Ok(None);
     }
 
+    let id = format!("{record_set}_{}", column.name);
+    let value_id = format!("{id}/value");
+    let value = Field::builder()
+        .id(Id::new(value_id.as_str()))
+        .kind(CrType::Field)
+        .name(Text::new("value"))
+        .description(Text::new(format!("Value of {}", column.name)))
+        .data_types(vec![column.data_type.clone()])
+        .build()
+        .map_err(|e| Error::Builder(e.to_string()))?;
+    let data = categories
+        .iter()
+        .map(|category| serde_json::Map::from_iter([(value_id.clone(), category.as_str().into())]))
+        .collect();
+
+    field.data_types.push(DataType::Label);
+    field.references.push(FieldRef {
+        field: Ref {
+            id: Id::new(value_id.as_str()),
+        },
+    });
+    RecordSet::builder()
+        .id(Id::new(id.as_str()))
+        .kind(CrType::RecordSet)
+        .name(Some(Text::new(id.as_str())))
+        .description(Some(LangString::new(format!("Values of {}", column.name))))
+        .record_types(vec![DataType::Enumeration])
+        .keys(vec![Ref {
+            id: Id::new(value_id.as_str()),
+        }])
+        .fields(vec![value])
+        .data(data)
+        .build()
+        .map(Some)
+        .map_err(|e| Error::Builder(e.to_string()))
+}
+
+/// Field describing a column of an inferred schema, with its nested columns
+///
+/// Values are extracted by column path, or by JSONPath relative to
+/// `json_parent` when it is given. Ids nest under `parent_id`, with `%` and
+/// `/` in names percent-encoded so that a key `a.b` and a key `b` nested
+/// in `a` get different ids.
+fn schema_field(
+    parent_id: &str,
+    column: &SchemaColumn,
+    source: &SourceRef,
+    json_parent: Option<&str>,
+) -> Result<Field> {
+    let (extract, json_path) = match json_parent {
+        Some(parent) => {
+            let mut expr = jsonpath::child(parent, &column.name);
+            if column.repeated {
+                expr.push_str("[*]");
+            }
+            let extract = Extract::JsonPath {
+                expr: Text::new(expr.as_str()),
+            };
+            (extract, Some(expr))
+        }
+        None => {
+            let extract = Extract::Column {
+                name: Text::new(column.path.as_str()),
+            };
+            (extract, None)
+        }
+    };
+    let id = format!(
+        "{parent_id}/{}",
+        column.name.replace('%', "%25").replace('/', "%2F")
+    );
+    let sub_fields = column
+        .children
+        .iter()
+        .map(|child| schema_field(&id, child, source, json_path.as_deref()))
+        .collect::<Result<Vec<_>>>()?;
+
+    Field::builder()
+        .id(Id::new(id.as_str()))
+        .kind(CrType::Field)
+        .name(Text::new(column.name.as_str()))
+        .description(Text::new(format!("Field for {}", column.path)))
+        .data_types(column.data_type.iter().cloned().collect())
+        .source(Some(
+            FieldSource::builder()
+                .extract(Some(extract))
+                .source(source.clone())
+                .nulls(column.null_tokens.clone())
+                .build()
+                .map_err(|e| Error::Builder(e.to_string()))?,
+        ))
+        .sub_fields((!sub_fields.is_empty()).then_some(sub_fields))
+        .repeated(column.repeated.then_some(true))
+        .build()
+        .map_err(|e| Error::Builder(e.to_string()))
+}
+
+/// Whether some of the fields or their sub-fields have `csvw:null` values
+fn has_nulls(fields: &[Field]) -> bool {
+    fields.iter().any(|field| {
+        field.source.as_ref().is_some_and(|s| !s.nulls.is_empty())
+            || field.sub_fields.as_deref().is_some_and(has_nulls)
+    })
+}
+
+/// Dataset-level metadata shared by every generator
+fn build_metadata(
+    name: String,
+    description: String,
+    distribution: Vec<Distribution>,
+    record_sets: Vec<RecordSet>,
+    options: &GenerateOptions,
+) -> Result<Metadata> {
+    let mut metadata = Metadata::builder()
+        .context(default_context()?)
+        .kind(CroissantType::Dataset)
+        .name(Text::new(name))
+        .description(Text::new(description))
+        .conforms_to(Some(Text::new(SpecVersion::V1_0.conforms_to())))
+        .date_published(Some(Text::new(Utc::now().format("%Y-%m-%d"))))
+        .version(Text::new("1.0.0"))
+        .distribution(distribution)
+        .record_sets(record_sets)
+        .build()
+        .map_err(|e| Error::Builder(e.to_string()))?;
+    options.config.apply(&mut metadata)?;
+    if !options.rai.is_empty() {
+        metadata.rai = options.rai.clone();
+    }
+    let compressed = metadata
+        .distribution
+        .iter()
+        .any(|d| matches!(&d.resource, Resource::FileObject(o) if o.compress_format.is_some()));
+    if compressed && let Some(context) = metadata.context.0.as_object_mut() {
+        context.insert("dcat".to_string(), DCAT_NS.into());
+    }
+    let dialects = metadata.distribution.iter().any(|d| match &d.resource {
+        Resource::FileObject(object) => object.dialect.is_some(),
+        Resource::FileSet(set) => set.dialect.is_some(),
+    });
+    let nulls = metadata.record_sets.iter().any(|r| has_nulls(&r.fields));
+    if (dialects || nulls)
+        && let Some(context) = metadata.context.0.as_object_mut()
+    {
+        context.insert("csvw".to_string(), CSVW_NS.into());
+    }
     Ok(metadata)
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::croissant::config::CreatorConfig;
+    use crate::croissant::data::validate_data;
+    use crate::croissant::records::{Dataset, Value};
+    use crate::croissant::validate::validate_file;
+
+    #[test]
+    fn missing_values_are_read_back_as_nulls() {
+        let dir = tempfile::tempdir().unwrap();
+        let csv_path = dir.path().join("flow.csv");
+        std::fs::write(&csv_path, "rate\n1\n1.5\nN/A\n").unwrap();
+
+        let metadata_path = dir.path().join("metadata.json");
+        let options = GenerateOptions::default();
+        let metadata =
+            generate_metadata_from_csv_with_options(&csv_path, Some(&metadata_path), &options)
+                .unwrap();
+        let field = &metadata.record_sets[0].fields[0];
+        assert_eq!(field.data_types, vec![DataType::Float]);
+        assert_eq!(field.source.as_ref().unwrap().nulls, vec!["N/A"]);
+        assert_eq!(
+            field.description.0,
+            "Field for rate: sc:Float inferred from 3 rows, 1 missing (33.3%), \
+             widened from sc:Integer to sc:Float at row 2 (\"1.5\")"
+        );
+
+        let dataset = Dataset::from_file(&metadata_path).unwrap();
+        let values = dataset
+            .records("main")
+            .unwrap()
+            .map(|record| record.unwrap().get("main/rate").cloned().unwrap())
+            .collect::<Vec<_>>();
+        assert_eq!(
+            values,
+            vec![Value::Float(1.0), Value::Float(1.5), Value::Null]
+        );
+        assert!(!validate_data(&dataset).has_errors());
+    }
+
+    #[test]
+    fn json_keys_with_dots_and_brackets_get_distinct_fields() {
+        let dir = tempfile::tempdir().unwrap();
+        let json_path = dir.path().join("data.json");
+        std::fs::write(
+            &json_path,
+            r#"[{"a.b": 1, "a": {"b": "s"}, "x]y": 2, "it's": 3, "c/d": 4}]"#,
+        )
+        .unwrap();
+        let metadata_path = dir.path().join("metadata.json");
+        let options = GenerateOptions::default();
+        let metadata =
+            generate_metadata_from_json(&json_path, Some(&metadata_path), &options).unwrap();
+        let fields = &metadata.record_sets[0].fields;
+        let ids = fields
+            .iter()
+            .map(|f| f.id.0.to_string())
+            .collect::<Vec<_>>();
+        assert_eq!(
+            ids,
+            vec!["main/a.b", "main/a", "main/x]y", "main/it's", "main/c%2Fd"]
+        );
+        let sub_fields = fields[1].sub_fields.as_ref().unwrap();
+        assert_eq!(sub_fields[0].id.0.to_string(), "main/a/b");
+
+        let dataset = Dataset::from_file(&metadata_path).unwrap();
+        assert!(!validate_data(&dataset).has_errors());
+        let records = dataset
+            .records("main")
+            .unwrap()
+            .map(|r| serde_json::to_value(r.unwrap()).unwrap())
+            .collect::<Vec<_>>();
+        assert_eq!(
+            records,
+            vec![serde_json::json!({
+                "main/a.b": 1,
+                "main/a": {"main/a/b": "s"},
+                "main/x]y": 2,
+                "main/it's": 3,
+                "main/c%2Fd": 4,
+            })]
+        );
+    }
+
+    #[test]
+    fn csv_files_with_different_columns_get_their_own_record_set() {
+        let dir = tempfile::tempdir().unwrap();
+        std::fs::create_dir(dir.path().join("sub")).unwrap();
+        std::fs::write(dir.path().join("a.csv"), "a,b\n1,2\n").unwrap();
+        std::fs::write(dir.path().join("c.csv"), "b,a\n4,3\n").unwrap();
+        std::fs::write(dir.path().join("sub/u.csv"), "x,y\n5,6\n").unwrap();
+        let metadata_path = dir.path().join("metadata.json");
+        let options = GenerateOptions::default();
+        let metadata =
+            generate_metadata_from_directory(dir.path(), Some(&metadata_path), &options).unwrap();
+
+        let includes = metadata
+            .distribution
+            .iter()
+            .map(|d| match &d.resource {
+                Resource::FileSet(set) => (set.id.0.to_string(), set.includes.clone()),
+                Resource::FileObject(object) => panic!("unexpected {object:?}"),
+            })
+            .collect::<Vec<_>>();
+        assert_eq!(
+            includes,
+            vec![
+                (
+                    "csv_1-files".to_string(),
+                    vec![Text::new("a.csv"), Text::new("c.csv")]
+                ),
+                ("csv_2-files".to_string(), vec![Text::new("sub/u.csv")]),
+            ]
+        );
+
+        let dataset = Dataset::from_file(&metadata_path).unwrap();
+        let records = |id: &str| {
+            dataset
+                .records(id)
+                .unwrap()
+                .map(|r| serde_json::to_value(r.unwrap()).unwrap())
+                .collect::<Vec<_>>()
+        };
+        assert_eq!(
+            records("csv_1"),
+            vec![
+                serde_json::json!({"csv_1/a": 1, "csv_1/b": 2}),
+                serde_json::json!({"csv_1/a": 3, "csv_1/b": 4}),
+            ]
+        );
+        assert_eq!(
+            records("csv_2"),
+            vec![serde_json::json!({"csv_2/x": 5, "csv_2/y": 6})]
+        );
+    }
+
+    #[test]
+    fn generated_metadata_with_dataset_properties_validates() {
+        let dir = tempfile::tempdir().unwrap();
+        let csv_path = dir.path().join("people.csv");
+        std::fs::write(&csv_path, "id,name\n1,Ada\n2,Alan\n").unwrap();
+        let metadata_path = dir.path().join("metadata.json");
+        let mut options = GenerateOptions::default();
+        options.config.license = vec!["https://creativecommons.org/licenses/by/4.0/".to_string()];
+        options.config.url = Some("https://example.org/people".to_string());
+        options.config.creators = vec![CreatorConfig {
+            name: "Jane Doe".to_string(),
+            email: None,
+            url: None,
+            kind: Default::default(),
+        }];
+        generate_metadata(&csv_path, Some(&metadata_path), &options).unwrap();
+
+        let report = validate_file(&metadata_path).unwrap();
+        assert!(!report.has_errors(), "{report}");
+    }
+}
@@ -0,0 +1,757 @@
+//! Streaming type inference for tabular data
+use crate::croissant::compression::{Compression, decompressed_name, open_decompressed};
+use crate::croissant::core::{CsvDialect, DataType};
+use crate::croissant::dialect::open_csv;
+use crate::croissant::errors::{Error, Result};
+use chrono::{DateTime, NaiveDate, NaiveDateTime};
+use parquet::basic::{ConvertedType, LogicalType, Repetition, Type as PhysicalType};
+use parquet::file::reader::{ChunkReader, FileReader, SerializedFileReader};
+use parquet::schema::types::Type as SchemaType;
+use std::collections::{BTreeMap, BTreeSet};
+use std::fs::File;
+use std::io::{BufRead, BufReader, Read};
+use std::path::Path;
+
+/// Number of example values kept for each column
+const MAX_EXAMPLES: usize = 3;
+
+/// Options controlling type inference
+#[derive(Debug, Clone)]
+pub struct InferenceOptions {
+    /// Maximum number of data rows to read, `None` reads the whole file
+    pub max_rows: Option<usize>,
+    /// Values treated as missing (compared case-insensitively after trimming)
+    pub null_values: Vec<String>,
+    /// CSV dialect properties taken as given instead of being detected, see
+    /// [`sniff_dialect`](crate::croissant::dialect::sniff_dialect)
+    pub dialect: CsvDialect,
+    /// Most distinct values of a categorical column, `0` disables the
+    /// detection of categorical columns
+    pub max_categories: usize,
+    /// Highest ratio of distinct values to non-null values of a categorical
+    /// column, so that values repeat
+    pub max_category_ratio: f64,
+}
+
+impl Default for InferenceOptions {
+    fn default() -> Self {
+        Self {
+            max_rows: None,
+            null_values: ["", "na", "n/a", "null", "none", "-"]
+                .into_iter()
+                .map(String::from)
+                .collect(),
+            dialect: CsvDialect::default(),
+            max_categories: 20,
+            max_category_ratio: 0.5,
+        }
+    }
+}
+
+impl InferenceOptions {
+    pub(crate) fn is_null(&self, value: &str) -> bool {
+        self.null_values
+            .iter()
+            .any(|null| null.eq_ignore_ascii_case(value))
+    }
+}
+
+/// A value that forced the inferred type of a column to widen
+#[derive(Debug, Clone, PartialEq)]
+pub struct Widening {
+    /// 1-based data row (the header is not counted)
+    pub row: usize,
+    pub value: String,
+    pub from: DataType,
+    pub to: DataType,
+}
+
+/// Inferred type of a column and the evidence it is based on
+#[derive(Debug, Clone, PartialEq)]
+pub struct ColumnProfile {
+    pub name: String,
+    /// Widest type observed, `sc:Text` when the column only holds nulls
+    pub data_type: DataType,
+    /// Number of rows inspected
+    pub rows: usize,
+    /// Number of missing or empty values
+    pub nulls: usize,
+    /// Distinct non-empty values taken as missing, as written in the data
+    pub null_tokens: BTreeSet<String>,
+    /// Number of values matching each elementary type
+    pub type_counts: BTreeMap<String, usize>,
+    /// First distinct non-null values
+    pub examples: Vec<String>,
+    /// Every widening step, in row order
+    pub widenings: Vec<Widening>,
+    /// Distinct non-null values, `None` once there are more than
+    /// [`InferenceOptions::max_categories`]
+    pub categories: Option<BTreeSet<String>>,
+    observed: Option<DataType>,
+}
+
+impl ColumnProfile {
+    /// Create an empty profile for the given column
+    pub fn new(name: impl Into<String>) -> Self {
+        Self {
+            name: name.into(),
+            data_type: DataType::Text,
+            rows: 0,
+            nulls: 0,
+            null_tokens: BTreeSet::new(),
+            type_counts: BTreeMap::new(),
+            examples: Vec::new(),
+            widenings: Vec::new(),
+            categories: Some(BTreeSet::new()),
+            observed: None,
+        }
+    }
+
+    /// Ratio of missing values among the inspected rows
+    pub fn null_ratio(&self) -> f64 {
+        if self.rows == 0 {
+            0.0
+        } else {
+            self.nulls as f64 / self.rows as f64
+        }
+    }
+
+    /// Values of a categorical column: a text column with at least two and
+    /// at most [`InferenceOptions::max_categories`] distinct values, repeating
+    /// enough to stay under [`InferenceOptions::max_category_ratio`]
+    pub fn categories(&self, options: &InferenceOptions) -> Option<&BTreeSet<String>> {
+        let categories = self.categories.as_ref()?;
+        let values = self.rows - self.nulls;
+        (self.data_type == DataType::Text
+            && categories.len() >= 2
+            && categories.len() as f64 <= options.max_category_ratio * values as f64)
+            .then_some(categories)
+    }
+
+    /// Account for one more value of the column
+    pub fn observe(&mut self, value: &str, options: &InferenceOptions) {
+        self.rows += 1;
+        let value = value.trim();
+        if options.is_null(value) {
+            self.nulls += 1;
+            if !value.is_empty() && !self.null_tokens.contains(value) {
+                self.null_tokens.insert(value.to_string());
+            }
+            return;
+        }
+
+        let value_type = infer_value_type(value);
+        *self.type_counts.entry(value_type.to_string()).or_default() += 1;
+        if self.examples.len() < MAX_EXAMPLES && !self.examples.iter().any(|e| e == value) {
+            self.examples.push(value.to_string());
+        }
+        if let Some(categories) = &mut self.categories
+            && !categories.contains(value)
+        {
+            if categories.len() < options.max_categories {
+                categories.insert(value.to_string());
+            } else {
+                self.categories = None;
+            }
+        }
+
+        let widened = match &self.observed {
+            None => value_type,
+            Some(current) => widen(current, &value_type),
+        };
+        if let Some(current) = &self.observed
+            && *current != widened
+        {
+            self.widenings.push(Widening {
+                row: self.rows,
+                value: value.to_string(),
+                from: current.clone(),
+                to: widened.clone(),
+            });
+        }
+        self.data_type = widened.clone();
+        self.observed = Some(widened);
+    }
+}
+
+/// Elementary type of a single value
+pub fn infer_value_type(value: &str) -> DataType {
+    let trimmed = value.trim();
+
+    if trimmed.parse::<i64>().is_ok() {
+        return DataType::Integer;
+    }
+
+    if trimmed.parse::<f64>().is_ok() {
+        return DataType::Float;
+    }
+
+    if trimmed.eq_ignore_ascii_case("true") || trimmed.eq_ignore_ascii_case("false") {
+        return DataType::Boolean;
+    }
+
+    if NaiveDate::parse_from_str(trimmed, "%Y-%m-%d").is_ok() {
+        return DataType::Date;
+    }
+
+    if DateTime::parse_from_rfc3339(trimmed).is_ok()
+        || NaiveDateTime::parse_from_str(trimmed, "%Y-%m-%d %H:%M:%S").is_ok()
+        || NaiveDateTime::parse_from_str(trimmed, "%Y-%m-%dT%H:%M:%S").is_ok()
+    {
+        return DataType::DateTime;
+    }
+
+    DataType::Text
+}
+
+/// Smallest type able to represent values of both types
+pub fn widen(a: &DataType, b: &DataType) -> DataType {
+    match (a, b) {
+        (a, b) if a == b => a.clone(),
+        (DataType::Integer, DataType::Float) | (DataType::Float, DataType::Integer) => {
+            DataType::Float
+        }
+        (DataType::Date, DataType::DateTime) | (DataType::DateTime, DataType::Date) => {
+            DataType::DateTime
+        }
+        _ => DataType::Text,
+    }
+}
+
+/// Stream a CSV file and infer the type of each of its columns
+pub fn infer_csv_columns(
+    csv_path: &Path,
+    options: &InferenceOptions,
+) -> Result<Vec<ColumnProfile>> {
+    infer_csv_files(&[csv_path], options)
+}
+
+/// Infer the column types of several CSV files sharing the same schema
+///
+/// Columns are matched by header; columns missing from the first file are
+/// appended in order of appearance. `max_rows` applies to each file.
+/// Compressed files (`.csv.gz`, `.csv.zst`, ...) are decompressed on the fly.
+/// The dialect of the files is detected from the first one.
+pub fn infer_csv_files<P: AsRef<Path>>(
+    csv_paths: &[P],
+    options: &InferenceOptions,
+) -> Result<Vec<ColumnProfile>> {
+    let files = csv_paths
+        .iter()
+        .map(|csv_path| open_decompressed(csv_path.as_ref()));
+    Ok(infer_csv_readers(files, options)?.0)
+}
+
+/// Infer the column types of several CSV streams sharing the same schema,
+/// see [`infer_csv_files`]
+///
+/// Returns the profiles with the dialect detected from the first stream.
+pub(crate) fn infer_csv_readers<R: Read>(
+    readers: impl IntoIterator<Item = Result<R>>,
+    options: &InferenceOptions,
+) -> Result<(Vec<ColumnProfile>, CsvDialect)> {
+    let mut profiles = Vec::<ColumnProfile>::new();
+    let mut dialect: Option<CsvDialect> = None;
+    for reader in readers {
+        let (mut reader, headers) = match &dialect {
+            Some(dialect) => dialect.csv_reader(reader?)?,
+            None => {
+                let (detected, reader, headers) = open_csv(reader?, &options.dialect)?;
+                dialect = Some(detected);
+                (reader, headers)
+            }
+        };
+
+        let mut columns = Vec::new();
+        for header in headers {
+            let index = match profiles.iter().position(|p| p.name == header) {
+                Some(index) => index,
+                None => {
+                    profiles.push(ColumnProfile::new(header));
+                    profiles.len() - 1
+                }
+            };
+            columns.push(index);
+        }
+
+        for (row, result) in reader.records().enumerate() {
+            if options.max_rows.is_some_and(|max| row >= max) {
+                break;
+            }
+            let record = result?;
+            for (i, index) in columns.iter().enumerate() {
+                profiles[*index].observe(record.get(i).unwrap_or_default(), options);
+            }
+        }
+    }
+
+    Ok((profiles, dialect.unwrap_or_default()))
+}
+
+/// A column declared in the schema of a columnar file
+#[derive(Debug, Clone, PartialEq)]
+pub struct SchemaColumn {
+    pub name: String,
+    /// Dotted path from the root of the schema, list and map wrappers omitted
+    pub path: String,
+    /// `None` for struct columns, described by their `children`
+    pub data_type: Option<DataType>,
+    /// The column holds a list of values
+    pub repeated: bool,
+    /// Strings taken as missing values, as written in the data
+    pub null_tokens: Vec<String>,
+    pub children: Vec<SchemaColumn>,
+}
+
+/// Read the schema from the footer of a Parquet file
+///
+/// Compressed files are decompressed into memory first.
+pub fn infer_parquet_columns(parquet_path: &Path) -> Result<Vec<SchemaColumn>> {
+    if Compression::from_path(parquet_path).is_some() {
+        let mut content = Vec::new();
+        open_decompressed(parquet_path)?.read_to_end(&mut content)?;
+        return infer_parquet_reader(bytes::Bytes::from(content));
+    }
+    let file = File::open(parquet_path).map_err(|_| Error::file_not_found(parquet_path))?;
+    infer_parquet_reader(file)
+}
+
+/// Read the schema from the footer of Parquet data, see [`infer_parquet_columns`]
+pub(crate) fn infer_parquet_reader<R: ChunkReader + 'static>(data: R) -> Result<Vec<SchemaColumn>> {
+    let reader = SerializedFileReader::new(data)?;
+    let root = reader
+        .metadata()
+        .file_metadata()
+        .schema_descr()
+        .root_schema_ptr();
+    Ok(root
+        .get_fields()
+        .iter()
+        .map(|field| schema_column(field, field.name(), field.name()))
+        .collect())
+}
+
+/// Describe a schema node under its logical name and path
+fn schema_column(field: &SchemaType, name: &str, path: &str) -> SchemaColumn {
+    let (name, path) = (name.to_string(), path.to_string());
+    let children = |group: &SchemaType| {
+        group
+            .get_fields()
+            .iter()
+            .map(|f| schema_column(f, f.name(), &format!("{path}.{}", f.name())))
+            .collect::<Vec<_>>()
+    };
+    let info = field.get_basic_info();
+    let repeated = info.has_repetition() && info.repetition() == Repetition::REPEATED;
+
+    if field.is_primitive() {
+        return SchemaColumn {
+            name,
+            path,
+            data_type: Some(parquet_type(field)),
+            repeated,
+            null_tokens: Vec::new(),
+            children: Vec::new(),
+        };
+    }
+
+    let is_list = matches!(info.logical_type(), Some(LogicalType::List))
+        || info.converted_type() == ConvertedType::LIST;
+    let is_map = matches!(info.logical_type(), Some(LogicalType::Map))
+        || matches!(
+            info.converted_type(),
+            ConvertedType::MAP | ConvertedType::MAP_KEY_VALUE
+        );
+
+    if let [inner] = field.get_fields()
+        && is_list
+    {
+        // Three-level lists wrap the element in a repeated group, two-level
+        // lists repeat the element directly
+        let element = match inner.get_fields() {
+            [element] if !inner.is_primitive() && inner.name() != "array" => element,
+            _ => inner,
+        };
+        let mut column = schema_column(element, &name, &path);
+        column.repeated = true;
+        return column;
+    }
+
+    if let [key_value] = field.get_fields()
+        && is_map
+        && !key_value.is_primitive()
+    {
+        return SchemaColumn {
+            children: children(key_value),
+            name,
+            path,
+            data_type: None,
+            repeated: true,
+            null_tokens: Vec::new(),
+        };
+    }
+
+    SchemaColumn {
+        children: children(field),
+        name,
+        path,
+        data_type: None,
+        repeated,
+        null_tokens: Vec::new(),
+    }
+}
+
+/// Croissant data type of a primitive Parquet column
+fn parquet_type(field: &SchemaType) -> DataType {
+    let info = field.get_basic_info();
+    match info.logical_type() {
+        Some(LogicalType::String | LogicalType::Enum | LogicalType::Json | LogicalType::Uuid) => {
+            return DataType::Text;
+        }
+        Some(LogicalType::Integer { .. }) => return DataType::Integer,
+        Some(LogicalType::Decimal { .. } | LogicalType::Float16) => return DataType::Float,
+        Some(LogicalType::Date) => return DataType::Date,
+        Some(LogicalType::Timestamp { .. }) => return DataType::DateTime,
+        Some(LogicalType::Time { .. }) => return DataType::Text,
+        _ => {}
+    }
+    match info.converted_type() {
+        ConvertedType::UTF8 | ConvertedType::ENUM | ConvertedType::JSON => return DataType::Text,
+        ConvertedType::DECIMAL => return DataType::Float,
+        ConvertedType::DATE => return DataType::Date,
+        ConvertedType::TIMESTAMP_MILLIS | ConvertedType::TIMESTAMP_MICROS => {
+            return DataType::DateTime;
+        }
+        ConvertedType::TIME_MILLIS | ConvertedType::TIME_MICROS => return DataType::Text,
+        _ => {}
+    }
+    match field.get_physical_type() {
+        PhysicalType::BOOLEAN => DataType::Boolean,
+        PhysicalType::INT32 | PhysicalType::INT64 => DataType::Integer,
+        // Legacy nanosecond timestamps
+        PhysicalType::INT96 => DataType::DateTime,
+        PhysicalType::FLOAT | PhysicalType::DOUBLE => DataType::Float,
+        PhysicalType::BYTE_ARRAY | PhysicalType::FIXED_LEN_BYTE_ARRAY => DataType::Text,
+    }
+}
+
+/// How the records of a JSON file are laid out
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum JsonLayout {
+    /// One document per line (JSON Lines)
+    Lines,
+    /// A single array of records
+    Array,
+    /// A single record
+    Object,
+}
+
+impl JsonLayout {
+    /// JSONPath of a record, relative to the document it is read from
+    pub fn root(&self) -> &'static str {
+        match self {
+            Self::Array => "$[*]",
+            Self::Lines | Self::Object => "$",
+        }
+    }
+}
+
+/// Inferred structure of the records of a JSON or JSON Lines file
+#[derive(Debug, Clone, PartialEq)]
+pub struct JsonProfile {
+    pub layout: JsonLayout,
+    /// Number of records inspected
+    pub records: usize,
+    pub columns: Vec<SchemaColumn>,
+}
+
+/// Sample the records of a JSON or JSON Lines file and infer their structure
+///
+/// Objects become columns with children and arrays repeated columns. Files
+/// with a `.jsonl` or `.ndjson` extension, possibly followed by a compression
+/// extension (`.jsonl.zst`), are read line by line.
+pub fn infer_json_columns(json_path: &Path, options: &InferenceOptions) -> Result<JsonProfile> {
+    let file = open_decompressed(json_path)?;
+    let name = json_path.file_name().unwrap_or_default().to_string_lossy();
+    let lines = decompressed_name(&name)
+        .rsplit_once('.')
+        .is_some_and(|(_, e)| e.eq_ignore_ascii_case("jsonl") || e.eq_ignore_ascii_case("ndjson"));
+    infer_json_reader(file, lines, &json_path.display().to_string(), options)
+}
+
+/// Sample the records of a JSON or JSON Lines stream named `name` in errors,
+/// see [`infer_json_columns`]
+pub(crate) fn infer_json_reader(
+    file: impl Read,
+    lines: bool,
+    name: &str,
+    options: &InferenceOptions,
+) -> Result<JsonProfile> {
+    let limit = options.max_rows.unwrap_or(usize::MAX);
+
+    let mut root = JsonNode::default();
+    let mut records = 0;
+    let layout = if lines {
+        for line in BufReader::new(file).lines() {
+            let line = line?;
+            if line.trim().is_empty() {
+                continue;
+            }
+            if records >= limit {
+                break;
+            }
+            root.observe(&serde_json::from_str(&line)?, options);
+            records += 1;
+        }
+        JsonLayout::Lines
+    } else {
+        match serde_json::from_reader(BufReader::new(file))? {
+            serde_json::Value::Array(items) => {
+                for item in items.iter().take(limit) {
+                    root.observe(item, options);
+                    records += 1;
+                }
+                JsonLayout::Array
+            }
+            document => {
+                root.observe(&document, options);
+                records += 1;
+                JsonLayout::Object
+            }
+        }
+    };
+
+    if root.data_type.is_some() || root.repeated {
+        return Err(Error::invalid_format(format!(
+            "{name} does not hold JSON objects"
+        )));
+    }
+    let columns = root
+        .children
+        .into_iter()
+        .map(|(name, node)| {
+            let path = name.clone();
+            node.into_column(name, path)
+        })
+        .collect();
+
+    Ok(JsonProfile {
+        layout,
+        records,
+        columns,
+    })
+}
+
+/// Structure accumulated over the values found at a JSON location
+#[derive(Debug, Default)]
+struct JsonNode {
+    data_type: Option<DataType>,
+    repeated: bool,
+    object: bool,
+    null_tokens: BTreeSet<String>,
+    children: Vec<(String, JsonNode)>,
+}
+
+impl JsonNode {
+    fn observe(&mut self, value: &serde_json::Value, options: &InferenceOptions) {
+        use serde_json::Value as Json;
+        let value_type = match value {
+            Json::Null => return,
+            Json::Array(items) => {
+                self.repeated = true;
+                for item in items {
+                    self.observe(item, options);
+                }
+                return;
+            }
+            Json::Object(map) => {
+                self.object = true;
+                for (key, value) in map {
+                    let index = match self.children.iter().position(|(k, _)| k == key) {
+                        Some(index) => index,
+                        None => {
+                            self.children.push((key.clone(), JsonNode::default()));
+                            self.children.len() - 1
+                        }
+                    };
+                    self.children[index].1.observe(value, options);
+                }
+                return;
+            }
+            Json::Bool(_) => DataType::Boolean,
+            Json::Number(n) if n.is_i64() || n.is_u64() => DataType::Integer,
+            Json::Number(_) => DataType::Float,
+            Json::String(s) if options.is_null(s.trim()) => {
+                let token = s.trim();
+                if !token.is_empty() && !self.null_tokens.contains(token) {
+                    self.null_tokens.insert(token.to_string());
+                }
+                return;
+            }
+            // Strings are only promoted to temporal types
+            Json::String(s) => match infer_value_type(s) {
+                t @ (DataType::Date | DataType::DateTime) => t,
+                _ => DataType::Text,
+            },
+        };
+        self.data_type = Some(match &self.data_type {
+            Some(current) => widen(current, &value_type),
+            None => value_type,
+        });
+    }
+
+    fn into_column(self, name: String, path: String) -> SchemaColumn {
+        // Locations holding both objects and scalars are kept as text
+        if self.object && self.data_type.is_none() {
+            let children = self
+                .children
+                .into_iter()
+                .map(|(child, node)| {
+                    let child_path = format!("{path}.{child}");
+                    node.into_column(child, child_path)
+                })
+                .collect();
+            return SchemaColumn {
+                name,
+                path,
+                data_type: None,
+                repeated: self.repeated,
+                null_tokens: Vec::new(),
+                children,
+            };
+        }
+        let data_type = match self.object {
+            true => DataType::Text,
+            false => self.data_type.unwrap_or(DataType::Text),
+        };
+        SchemaColumn {
+            name,
+            path,
+            data_type: Some(data_type),
+            repeated: self.repeated,
+            null_tokens: self.null_tokens.into_iter().collect(),
+            children: Vec::new(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn values_are_typed_and_widened() {
+        assert_eq!(infer_value_type(" 42 "), DataType::Integer);
+        assert_eq!(infer_value_type("4.2e1"), DataType::Float);
+        assert_eq!(infer_value_type("TRUE"), DataType::Boolean);
+        assert_eq!(infer_value_type("2024-02-29"), DataType::Date);
+        assert_eq!(infer_value_type("2024-02-29T12:00:00Z"), DataType::DateTime);
+        assert_eq!(infer_value_type("2024-02-30"), DataType::Text);
+        assert_eq!(widen(&DataType::Integer, &DataType::Float), DataType::Float);
+        assert_eq!(
+            widen(&DataType::Date, &DataType::DateTime),
+            DataType::DateTime
+        );
+        assert_eq!(widen(&DataType::Integer, &DataType::Date), DataType::Text);
+    }
+
+    #[test]
+    fn profiles_record_nulls_widenings_and_categories() {
+        let options = InferenceOptions::default();
+        let mut profile = ColumnProfile::new("size");
+        for value in ["1", "NA", "2", "", "2.5", "3"] {
+            profile.observe(value, &options);
+        }
+        assert_eq!(profile.data_type, DataType::Float);
+        assert_eq!((profile.rows, profile.nulls), (6, 2));
+        assert_eq!(profile.null_tokens, BTreeSet::from(["NA".to_string()]));
+        assert_eq!(profile.examples, vec!["1", "2", "2.5"]);
+        assert_eq!(
+            profile.widenings,
+            vec![Widening {
+                row: 5,
+                value: "2.5".to_string(),
+                from: DataType::Integer,
+                to: DataType::Float,
+            }]
+        );
+        assert_eq!(profile.categories(&options), None);
+
+        let mut profile = ColumnProfile::new("split");
+        for value in ["train", "test", "train", "train", "test"] {
+            profile.observe(value, &options);
+        }
+        let categories = profile.categories(&options).unwrap();
+        assert_eq!(categories.iter().collect::<Vec<_>>(), vec!["test", "train"]);
+    }
+
+    #[test]
+    fn csv_files_are_profiled_by_header() {
+        let dir = tempfile::tempdir().unwrap();
+        let first = dir.path().join("a.csv");
+        let second = dir.path().join("b.csv");
+        std::fs::write(&first, "id,when\n1,2024-01-01\n2,2024-01-02\n").unwrap();
+        std::fs::write(&second, "when,id,note\n2024-01-03 10:00:00,3,late\n").unwrap();
+
+        let options = InferenceOptions {
+            max_rows: Some(1),
+            ..InferenceOptions::default()
+        };
+        let profiles = infer_csv_files(&[&first, &second], &options).unwrap();
+        let types = profiles
+            .iter()
+            .map(|p| (p.name.as_str(), p.data_type.clone(), p.rows))
+            .collect::<Vec<_>>();
+        assert_eq!(
+            types,
+            vec![
+                ("id", DataType::Integer, 2),
+                ("when", DataType::DateTime, 2),
+                ("note", DataType::Text, 1),
+            ]
+        );
+    }
+
+    #[test]
+    fn json_records_become_nested_columns() {
+        let content = r#"{"id": 1, "tags": ["a"], "meta": {"score": 0.5, "at": "2024-01-01"}}
+{"id": 2, "tags": [], "meta": {"score": 1, "at": "n/a"}, "extra": {"x": 1}}
+"#;
+        let profile = infer_json_reader(
+            content.as_bytes(),
+            true,
+            "test",
+            &InferenceOptions::default(),
+        )
+        .unwrap();
+        assert_eq!(profile.layout, JsonLayout::Lines);
+        assert_eq!(profile.records, 2);
+        let columns = &profile.columns;
+        assert_eq!(
+            columns.iter().map(|c| c.path.as_str()).collect::<Vec<_>>(),
+            vec!["id", "tags", "meta", "extra"]
+        );
+        assert!(columns[1].repeated);
+        assert_eq!(columns[1].data_type, Some(DataType::Text));
+        assert_eq!(columns[2].data_type, None);
+        let meta = &columns[2].children;
+        assert_eq!(meta[0].path, "meta.score");
+        assert_eq!(meta[0].data_type, Some(DataType::Float));
+        assert_eq!(meta[1].data_type, Some(DataType::Date));
+        assert_eq!(meta[1].null_tokens, vec!["n/a"]);
+
+        let profile = infer_json_reader(
+            &b"[{\"a\": 1}, {\"a\": true}]"[..],
+            false,
+            "test",
+            &InferenceOptions::default(),
+        )
+        .unwrap();
+        assert_eq!(profile.layout, JsonLayout::Array);
+        assert_eq!(profile.layout.root(), "$[*]");
+        assert_eq!(profile.columns[0].data_type, Some(DataType::Text));
+        assert!(
+            infer_json_reader(&b"[1, 2]"[..], false, "t", &InferenceOptions::default()).is_err()
+        );
+    }
+}
@@ -0,0 +1,963 @@
+//! JSON-LD processing of Croissant documents
+//!
+//! Documents are expanded against their own `@context` and compacted against
+//! the canonical Croissant context before being mapped onto the typed model,
+//! so aliased terms, full IRIs and array-valued contexts are understood.
+//!
+//! Only the subset of JSON-LD 1.1 used by dataset descriptions is supported:
+//! term definitions with `@id`, `@type` (`@id`, `@vocab`, `@json` or an IRI)
+//! and `@container` (`@set` or `@list`), compact IRIs, `@vocab` and
+//! `@language`. Other keywords (`@reverse`, `@nest`, scoped contexts, ...)
+//! are errors rather than being ignored. Remote contexts are limited to the
+//! bundled ones, see [`bundled_context`].
+use crate::croissant::core::Metadata;
+use crate::croissant::errors::{Error, Result};
+use serde_json::{Map, Value, json};
+use std::collections::HashMap;
+
+/// Prefix of the Croissant vocabulary
+pub const CROISSANT_NS: &str = "http://mlcommons.org/croissant/";
+/// Prefix of the vocabulary of the Croissant 0.8 draft
+pub const LEGACY_CROISSANT_NS: &str = "http://mlcommons.org/schema/";
+/// Prefix of the CSV on the Web vocabulary, used for `csvw:dialect`
+pub const CSVW_NS: &str = "http://www.w3.org/ns/csvw#";
+/// Prefix of the DCAT vocabulary, used for `dcat:compressFormat`
+pub const DCAT_NS: &str = "http://www.w3.org/ns/dcat#";
+/// Prefix of the schema.org vocabulary
+pub const SCHEMA_ORG_NS: &str = "https://schema.org/";
+
+/// The canonical Croissant 1.0 `@context`
+pub fn croissant_1_0_context() -> Value {
+    json!({
+        "@language": "en",
+        "@vocab": "https://schema.org/",
+        "citeAs": "cr:citeAs",
+        "column": "cr:column",
+        "conformsTo": "dct:conformsTo",
+        "cr": "http://mlcommons.org/croissant/",
+        "rai": "http://mlcommons.org/croissant/RAI/",
+        "data": { "@id": "cr:data", "@type": "@json" },
+        "dataType": { "@id": "cr:dataType", "@type": "@vocab" },
+        "dct": "http://purl.org/dc/terms/",
+        "examples": { "@id": "cr:examples", "@type": "@json" },
+        "extract": "cr:extract",
+        "field": "cr:field",
+        "fileProperty": "cr:fileProperty",
+        "fileObject": "cr:fileObject",
+        "fileSet": "cr:fileSet",
+        "format": "cr:format",
+        "includes": "cr:includes",
+        "isLiveDataset": "cr:isLiveDataset",
+        "jsonPath": "cr:jsonPath",
+        "key": "cr:key",
+        "md5": "cr:md5",
+        "parentField": "cr:parentField",
+        "path": "cr:path",
+        "recordSet": "cr:recordSet",
+        "references": "cr:references",
+        "regex": "cr:regex",
+        "repeated": "cr:repeated",
+        "replace": "cr:replace",
+        "sc": "https://schema.org/",
+        "separator": "cr:separator",
+        "source": "cr:source",
+        "subField": "cr:subField",
+        "transform": "cr:transform"
+    })
+}
+
+/// The canonical Croissant 1.1 `@context`
+pub fn croissant_1_1_context() -> Value {
+    let mut context = croissant_1_0_context();
+    let terms = json!({
+        "prov": "http://www.w3.org/ns/prov#",
+        "equivalentProperty": "cr:equivalentProperty",
+        "samplingRate": "cr:samplingRate"
+    });
+    if let (Some(context), Value::Object(terms)) = (context.as_object_mut(), terms) {
+        context.extend(terms);
+    }
+    context
+}
+
+/// Contexts resolved without network access, by URL
+pub fn bundled_context(url: &str) -> Option<Value> {
+    let url = url.trim_end_matches('/');
+    match url {
+        "http://mlcommons.org/croissant/1.0"
+        | "https://mlcommons.org/croissant/1.0"
+        | "http://mlcommons.org/croissant/1.0/context.jsonld"
+        | "https://mlcommons.org/croissant/1.0/context.jsonld" => Some(croissant_1_0_context()),
+        "http://mlcommons.org/croissant/1.1"
+        | "https://mlcommons.org/croissant/1.1"
+        | "http://mlcommons.org/croissant/1.1/context.jsonld"
+        | "https://mlcommons.org/croissant/1.1/context.jsonld" => Some(croissant_1_1_context()),
+        "http://schema.org" | "https://schema.org" => Some(json!({
+            "@vocab": SCHEMA_ORG_NS,
+            "sc": SCHEMA_ORG_NS
+        })),
+        _ => None,
+    }
+}
+
+/// Full IRI of a property named as in the canonical context (`name`,
+/// `cr:field`, `csvw:dialect`, ...), keywords being returned as they are
+pub fn property_iri(term: &str) -> String {
+    let mut context = croissant_1_1_context();
+    if let Some(map) = context.as_object_mut() {
+        map.insert("csvw".to_string(), CSVW_NS.into());
+        map.insert("dcat".to_string(), DCAT_NS.into());
+    }
+    match process_context(&ActiveContext::default(), &context) {
+        Ok(active) => expand_iri(&active, term, true).unwrap_or_else(|| term.to_string()),
+        Err(_) => term.to_string(),
+    }
+}
+
+/// Parse a Croissant document of any JSON-LD shape into the typed model
+///
+/// The document is expanded, compacted against the canonical context, and
+/// its `@context` replaced by the canonical one, keeping the prefixes and the
+/// default `@language` the document declares on top of it. Documents of
+/// older versions are read as they are, see
+/// [`migrate::load_metadata`](crate::croissant::migrate::load_metadata) to
+/// migrate them first.
+pub fn parse_metadata(document: Value) -> Result<Metadata> {
+    Ok(serde_json::from_value(normalize(document)?)?)
+}
+
+/// Rewrite a Croissant document against the canonical context
+///
+/// See [`parse_metadata`]; the result is plain JSON ready to be deserialized.
+pub fn normalize(document: Value) -> Result<Value> {
+    let input_context = match &document {
+        Value::Object(map) => match map.get("@context") {
+            Some(context) => process_context(&ActiveContext::default(), context)?,
+            None => ActiveContext::default(),
+        },
+        _ => return Err(Error::invalid_format("The document is not a JSON object")),
+    };
+
+    let expanded = expand(&document)?;
+    let mut node = dataset_node(expanded)?;
+    let croissant_terms = croissant_1_1_context()
+        .as_object()
+        .into_iter()
+        .flatten()
+        .filter(|(_, definition)| match definition {
+            Value::String(id) => id.starts_with("cr:"),
+            definition => definition["@id"]
+                .as_str()
+                .is_some_and(|id| id.starts_with("cr:")),
+        })
+        .map(|(term, _)| term.clone())
+        .collect::<Vec<_>>();
+    rename_legacy_terms(&mut node, &croissant_terms);
+
+    let mut output_context = croissant_1_0_context();
+    if let Some(map) = output_context.as_object_mut() {
+        if let Some(language) = &input_context.language {
+            map.insert("@language".into(), Value::String(language.clone()));
+        }
+        let mut terms = input_context.terms.iter().collect::<Vec<_>>();
+        terms.sort_by(|a, b| a.0.cmp(b.0));
+        for (term, definition) in terms {
+            let Some(definition) = definition else {
+                continue;
+            };
+            if map.contains_key(term) {
+                continue;
+            }
+            // Prefixes and Croissant terms of later versions are kept, aliases are not
+            if definition.prefix {
+                map.insert(term.clone(), Value::String(definition.iri.clone()));
+            } else if definition.iri == format!("{CROISSANT_NS}{term}") {
+                let id = Value::String(format!("cr:{term}"));
+                let value = match &definition.type_mapping {
+                    Some(type_mapping) => json!({ "@id": id, "@type": type_mapping }),
+                    None => id,
+                };
+                map.insert(term.clone(), value);
+            }
+        }
+    }
+
+    // Terms the typed model holds as lists always compact to arrays
+    let mut model_context = output_context.clone();
+    if let Some(map) = model_context.as_object_mut() {
+        for term in SET_TERMS {
+            let definition = match map.get(*term) {
+                Some(Value::String(iri)) => json!({ "@id": iri, "@container": "@set" }),
+                Some(Value::Object(definition)) => {
+                    let mut definition = definition.clone();
+                    definition.insert("@container".into(), "@set".into());
+                    Value::Object(definition)
+                }
+                _ => json!({ "@id": format!("{SCHEMA_ORG_NS}{term}"), "@container": "@set" }),
+            };
+            map.insert(term.to_string(), definition);
+        }
+    }
+    let model_context = process_context(&ActiveContext::default(), &model_context)?;
+
+    let mut compacted = match compact(&model_context, &node, None) {
+        Value::Object(map) => map,
+        _ => {
+            return Err(Error::invalid_format(
+                "The document does not describe a node",
+            ));
+        }
+    };
+    let mut result = Map::new();
+    result.insert("@context".into(), output_context);
+    result.append(&mut compacted);
+    Ok(Value::Object(result))
+}
+
+/// Terms of the typed model holding lists of values
+///
+/// `recordSet`, `field` and `references` are left out as they also name
+/// single references; the model accepts them either way.
+const SET_TERMS: &[&str] = &[
+    "distribution",
+    "subField",
+    "includes",
+    "excludes",
+    "containedIn",
+    "keywords",
+    "sameAs",
+    "license",
+    "creator",
+    "publisher",
+    "inLanguage",
+    "dataType",
+    "key",
+    "transform",
+    "parentField",
+    "equivalentProperty",
+];
+
+/// Move Croissant properties left to `@vocab` into the Croissant namespace
+///
+/// Documents written against an incomplete context, such as the one this
+/// crate used to generate, expand `recordSet` or `field` to schema.org IRIs.
+/// Properties of the 0.8 vocabulary (`ml:`) moved unchanged to `cr:`.
+fn rename_legacy_terms(element: &mut Value, terms: &[String]) {
+    match element {
+        Value::Array(items) => items
+            .iter_mut()
+            .for_each(|item| rename_legacy_terms(item, terms)),
+        Value::Object(map) => {
+            *map = std::mem::take(map)
+                .into_iter()
+                .map(|(key, mut value)| {
+                    rename_legacy_terms(&mut value, terms);
+                    let iri = key
+                        .strip_prefix(SCHEMA_ORG_NS)
+                        .filter(|term| terms.iter().any(|t| t == term))
+                        .or_else(|| key.strip_prefix(LEGACY_CROISSANT_NS))
+                        .map(|term| format!("{CROISSANT_NS}{term}"));
+                    (iri.unwrap_or(key), value)
+                })
+                .collect();
+        }
+        _ => {}
+    }
+}
+
+/// The node describing the dataset among the expanded nodes
+fn dataset_node(expanded: Vec<Value>) -> Result<Value> {
+    let dataset = format!("{SCHEMA_ORG_NS}Dataset");
+    let is_dataset = |node: &Value| {
+        node.get("@type")
+            .and_then(Value::as_array)
+            .is_some_and(|types| types.iter().any(|t| t.as_str() == Some(&dataset)))
+    };
+    let mut nodes = expanded
+        .into_iter()
+        .flat_map(|node| match node.get("@graph") {
+            Some(Value::Array(graph)) => graph.clone(),
+            _ => vec![node],
+        })
+        .collect::<Vec<_>>();
+    match nodes.iter().position(is_dataset) {
+        Some(index) => Ok(nodes.swap_remove(index)),
+        None if nodes.len() == 1 => Ok(nodes.remove(0)),
+        None => Err(Error::invalid_format(
+            "The document does not describe a single dataset",
+        )),
+    }
+}
+
+/// Definition of a term in an active context
+#[derive(Debug, Clone, PartialEq)]
+struct TermDefinition {
+    iri: String,
+    /// `@id`, `@vocab`, `@json` or a datatype IRI
+    type_mapping: Option<String>,
+    container: Option<String>,
+    /// The term can be used as the prefix of compact IRIs
+    prefix: bool,
+}
+
+/// Context in effect while processing a node
+#[derive(Debug, Clone, Default)]
+struct ActiveContext {
+    vocab: Option<String>,
+    language: Option<String>,
+    /// Term definitions, `None` for terms explicitly mapped to `null`
+    terms: HashMap<String, Option<TermDefinition>>,
+}
+
+impl ActiveContext {
+    fn term(&self, term: &str) -> Option<&TermDefinition> {
+        self.terms.get(term).and_then(Option::as_ref)
+    }
+}
+
+/// Apply a local context (object, URL, array or `null`) on an active context
+fn process_context(active: &ActiveContext, local: &Value) -> Result<ActiveContext> {
+    match local {
+        Value::Null => Ok(ActiveContext::default()),
+        Value::String(url) => {
+            let context = bundled_context(url)
+                .ok_or_else(|| Error::unsupported(format!("remote JSON-LD context '{url}'")))?;
+            process_context(active, &context)
+        }
+        Value::Array(contexts) => contexts.iter().try_fold(active.clone(), |active, local| {
+            process_context(&active, local)
+        }),
+        Value::Object(map) => {
+            let mut result = active.clone();
+            if let Some(vocab) = map.get("@vocab") {
+                result.vocab = match vocab {
+                    Value::Null => None,
+                    Value::String(vocab) => {
+                        let mut defined = HashMap::new();
+                        expand_iri_in(&mut result, map, &mut defined, vocab, true)?
+                    }
+                    _ => return Err(Error::invalid_format("Invalid @vocab in JSON-LD context")),
+                };
+            }
+            if let Some(language) = map.get("@language") {
+                result.language = language.as_str().map(str::to_lowercase);
+            }
+            for (keyword, value) in map {
+                match keyword.as_str() {
+                    "@vocab" | "@language" | "@protected" => {}
+                    "@version" if value.as_f64() == Some(1.1) => {}
+                    "@base" if value.is_null() => {}
+                    keyword if keyword.starts_with('@') => {
+                        return Err(Error::unsupported(format!(
+                            "JSON-LD context keyword '{keyword}'"
+                        )));
+                    }
+                    _ => {}
+                }
+            }
+            let mut defined = HashMap::new();
+            for term in map.keys() {
+                create_term(&mut result, map, &mut defined, term)?;
+            }
+            Ok(result)
+        }
+        _ => Err(Error::invalid_format("Invalid JSON-LD context")),
+    }
+}
+
+/// Define `term` from the local context, defining its dependencies first
+fn create_term(
+    active: &mut ActiveContext,
+    local: &Map<String, Value>,
+    defined: &mut HashMap<String, bool>,
+    term: &str,
+) -> Result<()> {
+    match defined.get(term) {
+        Some(true) => return Ok(()),
+        Some(false) => {
+            return Err(Error::invalid_format(format!(
+                "Cyclic IRI mapping for JSON-LD term '{term}'"
+            )));
+        }
+        None => {}
+    }
+    if term.starts_with('@') {
+        return Ok(());
+    }
+    defined.insert(term.to_string(), false);
+
+    let value = match local.get(term) {
+        Some(Value::String(id)) => json!({ "@id": id }),
+        Some(value) => value.clone(),
+        None => Value::Null,
+    };
+    let definition = match &value {
+        Value::Null => None,
+        Value::Object(definition) if definition.get("@id") == Some(&Value::Null) => None,
+        Value::Object(definition) => {
+            if let Some(keyword) = definition
+                .keys()
+                .find(|k| !matches!(k.as_str(), "@id" | "@type" | "@container" | "@protected"))
+            {
+                return Err(Error::unsupported(format!(
+                    "'{keyword}' in the definition of the JSON-LD term '{term}'"
+                )));
+            }
+            let iri = match definition.get("@id").and_then(Value::as_str) {
+                Some(id) => expand_iri_in(active, local, defined, id, true)?,
+                None if term.contains(':') => expand_iri_in(active, local, defined, term, true)?,
+                None => active.vocab.as_ref().map(|vocab| format!("{vocab}{term}")),
+            }
+            .ok_or_else(|| {
+                Error::invalid_format(format!("Invalid IRI mapping for JSON-LD term '{term}'"))
+            })?;
+            let type_mapping = match definition.get("@type").and_then(Value::as_str) {
+                Some(keyword @ ("@id" | "@vocab" | "@json")) => Some(keyword.to_string()),
+                Some(keyword) if keyword.starts_with('@') => {
+                    return Err(Error::unsupported(format!(
+                        "'@type: {keyword}' in the definition of the JSON-LD term '{term}'"
+                    )));
+                }
+                Some(datatype) => expand_iri_in(active, local, defined, datatype, true)?,
+                None => None,
+            };
+            let container = match definition.get("@container") {
+                None | Some(Value::Null) => None,
+                Some(Value::String(container)) if container == "@set" || container == "@list" => {
+                    Some(container.clone())
+                }
+                Some(container) => {
+                    return Err(Error::unsupported(format!(
+                        "'@container: {container}' in the definition of the JSON-LD term '{term}'"
+                    )));
+                }
+            };
+            let prefix = !term.contains(':')
+                && iri.ends_with(['/', '#', ':', '?', '[', ']', '@'])
+                && definition.len() == 1;
+            Some(TermDefinition {
+                iri,
+                type_mapping,
+                container,
+                prefix,
+            })
+        }
+        _ => {
+            return Err(Error::invalid_format(format!(
+                "Invalid definition of the JSON-LD term '{term}'"
+            )));
+        }
+    };
+    active.terms.insert(term.to_string(), definition);
+    defined.insert(term.to_string(), true);
+    Ok(())
+}
+
+/// Expand an IRI while a local context is being processed
+fn expand_iri_in(
+    active: &mut ActiveContext,
+    local: &Map<String, Value>,
+    defined: &mut HashMap<String, bool>,
+    value: &str,
+    vocab: bool,
+) -> Result<Option<String>> {
+    if local.contains_key(value) && !defined.contains_key(value) {
+        create_term(active, local, defined, value)?;
+    }
+    if let Some((prefix, _)) = value.split_once(':')
+        && local.contains_key(prefix)
+        && !defined.contains_key(prefix)
+    {
+        create_term(active, local, defined, prefix)?;
+    }
+    Ok(expand_iri(active, value, vocab))
+}
+
+/// Expand a term, compact IRI or relative IRI
+///
+/// Returns `None` for terms mapped to `null`. Relative IRIs are kept as they
+/// are since documents have no base IRI.
+fn expand_iri(active: &ActiveContext, value: &str, vocab: bool) -> Option<String> {
+    if value.starts_with('@') {
+        return Some(value.to_string());
+    }
+    if vocab && let Some(definition) = active.terms.get(value) {
+        return definition.as_ref().map(|d| d.iri.clone());
+    }
+    if let Some((prefix, suffix)) = value.split_once(':') {
+        if prefix == "_" || suffix.starts_with("//") {
+            return Some(value.to_string());
+        }
+        if let Some(definition) = active.term(prefix)
+            && definition.prefix
+        {
+            return Some(format!("{}{suffix}", definition.iri));
+        }
+        return Some(value.to_string());
+    }
+    if vocab && let Some(base) = &active.vocab {
+        return Some(format!("{base}{value}"));
+    }
+    Some(value.to_string())
+}
+
+/// Expand a document into a list of node objects
+pub(crate) fn expand(document: &Value) -> Result<Vec<Value>> {
+    let expanded = expand_element(&ActiveContext::default(), document, None)?;
+    Ok(match expanded {
+        Value::Array(nodes) => nodes,
+        Value::Null => Vec::new(),
+        node => vec![node],
+    })
+}
+
+fn expand_element(
+    active: &ActiveContext,
+    element: &Value,
+    property: Option<&str>,
+) -> Result<Value> {
+    match element {
+        Value::Null => Ok(Value::Null),
+        Value::Array(items) => {
+            let mut result = Vec::new();
+            for item in items {
+                match expand_element(active, item, property)? {
+                    Value::Null => {}
+                    Value::Array(nested) => result.extend(nested),
+                    value => result.push(value),
+                }
+            }
+            Ok(Value::Array(result))
+        }
+        Value::Object(map) => {
+            let active = match map.get("@context") {
+                Some(local) => process_context(active, local)?,
+                None => active.clone(),
+            };
+            expand_object(&active, map)
+        }
+        scalar => match property {
+            // Free-floating values are dropped
+            None => Ok(Value::Null),
+            Some(property) => Ok(expand_value(active, property, scalar)),
+        },
+    }
+}
+
+fn expand_object(active: &ActiveContext, map: &Map<String, Value>) -> Result<Value> {
+    let mut result = Map::new();
+    for (key, value) in map {
+        if key == "@context" {
+            continue;
+        }
+        let Some(iri) = expand_iri(active, key, true) else {
+            continue;
+        };
+        match iri.as_str() {
+            "@id" => {
+                let id = value
+                    .as_str()
+                    .ok_or_else(|| Error::invalid_format("@id must be a string"))?;
+                if let Some(id) = expand_iri(active, id, false) {
+                    result.insert(iri, Value::String(id));
+                }
+            }
+            "@type" => {
+                let types = match value {
+                    Value::Array(types) => types.iter().collect(),
+                    value => vec![value],
+                };
+                let types = types
+                    .into_iter()
+                    .filter_map(|t| t.as_str())
+                    .filter_map(|t| expand_iri(active, t, true))
+                    .map(Value::String)
+                    .collect();
+                result.insert(iri, Value::Array(types));
+            }
+            "@value" | "@language" | "@index" | "@direction" => {
+                result.insert(iri, value.clone());
+            }
+            "@graph" | "@list" | "@set" | "@included" => {
+                result.insert(iri, expand_element(active, value, None)?);
+            }
+            "@reverse" | "@nest" => {
+                return Err(Error::unsupported(format!("JSON-LD keyword '{iri}'")));
+            }
+            keyword if keyword.starts_with('@') => {}
+            // Terms neither defined nor covered by @vocab are dropped
+            iri if !iri.contains(':') => {}
+            _ => {
+                let definition = active.term(key);
+                let expanded = match definition.and_then(|d| d.type_mapping.as_deref()) {
+                    Some("@json") => json!([{ "@value": value, "@type": "@json" }]),
+                    _ => match expand_element(active, value, Some(key))? {
+                        Value::Null => continue,
+                        Value::Array(values) => Value::Array(values),
+                        value => Value::Array(vec![value]),
+                    },
+                };
+                match result.get_mut(&iri) {
+                    Some(Value::Array(values)) => {
+                        values.extend(expanded.as_array().cloned().unwrap_or_default());
+                    }
+                    _ => {
+                        result.insert(iri, expanded);
+                    }
+                }
+            }
+        }
+    }
+
+    // A map holding only @set wraps its values
+    if let Some(set) = result.remove("@set")
+        && result.is_empty()
+    {
+        return Ok(set);
+    }
+    Ok(Value::Object(result))
+}
+
+fn expand_value(active: &ActiveContext, property: &str, value: &Value) -> Value {
+    let definition = active.term(property);
+    match (definition.and_then(|d| d.type_mapping.as_deref()), value) {
+        (Some("@id"), Value::String(id)) => json!({ "@id": expand_iri(active, id, false) }),
+        (Some("@vocab"), Value::String(id)) => json!({ "@id": expand_iri(active, id, true) }),
+        (Some(datatype), _) if !datatype.starts_with('@') => {
+            json!({ "@value": value, "@type": datatype })
+        }
+        (_, Value::String(_)) => match &active.language {
+            Some(language) => json!({ "@value": value, "@language": language }),
+            None => json!({ "@value": value }),
+        },
+        _ => json!({ "@value": value }),
+    }
+}
+
+/// How an IRI is used, which decides the preferred compact form
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum IriUse {
+    /// Property names: terms, then @vocab-relative names, then compact IRIs
+    Property,
+    /// Types and @vocab-typed values: compact IRIs (`sc:Text`) first
+    Vocab,
+    /// Node identifiers: only compact IRIs
+    Id,
+}
+
+fn compact(active: &ActiveContext, element: &Value, property: Option<&str>) -> Value {
+    let definition = property.and_then(|p| active.term(p));
+    match element {
+        Value::Array(items) => {
+            let mut values = items
+                .iter()
+                .map(|item| compact(active, item, property))
+                .collect::<Vec<_>>();
+            let set = definition.is_some_and(|d| d.container.as_deref() == Some("@set"));
+            if values.len() == 1 && !set {
+                values.remove(0)
+            } else {
+                Value::Array(values)
+            }
+        }
+        Value::Object(map) if map.contains_key("@value") => compact_value(active, map, definition),
+        Value::Object(map) if map.len() == 1 && map.contains_key("@id") => {
+            let id = map["@id"].as_str().unwrap_or_default();
+            match definition.and_then(|d| d.type_mapping.as_deref()) {
+                Some("@id") => Value::String(compact_iri(active, id, IriUse::Id)),
+                Some("@vocab") => Value::String(compact_iri(active, id, IriUse::Vocab)),
+                _ => json!({ "@id": compact_iri(active, id, IriUse::Id) }),
+            }
+        }
+        Value::Object(map) => {
+            let mut result = Map::new();
+            for (key, value) in map {
+                match key.as_str() {
+                    "@id" => {
+                        let id = value.as_str().unwrap_or_default();
+                        result.insert(key.clone(), compact_iri(active, id, IriUse::Id).into());
+                    }
+                    "@type" => {
+                        let mut types = value
+                            .as_array()
+                            .into_iter()
+                            .flatten()
+                            .filter_map(Value::as_str)
+                            .map(|t| Value::String(compact_iri(active, t, IriUse::Vocab)))
+                            .collect::<Vec<_>>();
+                        let types = match types.len() {
+                            1 => types.remove(0),
+                            _ => Value::Array(types),
+                        };
+                        result.insert(key.clone(), types);
+                    }
+                    "@graph" | "@list" | "@included" => {
+                        result.insert(key.clone(), compact(active, value, None));
+                    }
+                    keyword if keyword.starts_with('@') => {
+                        result.insert(key.clone(), value.clone());
+                    }
+                    iri => {
+                        let term = compact_iri(active, iri, IriUse::Property);
+                        let compacted = compact(active, value, Some(&term));
+                        result.insert(term, compacted);
+                    }
+                }
+            }
+            Value::Object(result)
+        }
+        scalar => scalar.clone(),
+    }
+}
+
+fn compact_value(
+    active: &ActiveContext,
+    map: &Map<String, Value>,
+    definition: Option<&TermDefinition>,
+) -> Value {
+    let value = map.get("@value").cloned().unwrap_or_default();
+    let type_mapping = definition.and_then(|d| d.type_mapping.as_deref());
+    let language = map
+        .get("@language")
+        .and_then(Value::as_str)
+        .map(str::to_lowercase);
+    match (map.get("@type").and_then(Value::as_str), language) {
+        (Some("@json"), _) => value,
+        (Some(datatype), _) if type_mapping == Some(datatype) => value,
+        (Some(datatype), _) => {
+            json!({ "@value": value, "@type": compact_iri(active, datatype, IriUse::Vocab) })
+        }
+        // Strings in another language than the default one keep their tag
+        (None, Some(language)) if active.language.as_ref() != Some(&language) => {
+            json!({ "@value": value, "@language": language })
+        }
+        (None, _) => value,
+    }
+}
+
+/// Shortest compact form of an IRI in the active context
+fn compact_iri(active: &ActiveContext, iri: &str, usage: IriUse) -> String {
+    let shortest = |candidates: Vec<String>| {
+        candidates
+            .into_iter()
+            .min_by(|a, b| a.len().cmp(&b.len()).then_with(|| a.cmp(b)))
+    };
+
+    let term = || {
+        shortest(
+            active
+                .terms
+                .iter()
+                .filter(|(_, d)| d.as_ref().is_some_and(|d| d.iri == iri))
+                .map(|(term, _)| term.clone())
+                .collect(),
+        )
+    };
+    let vocab_relative = || {
+        let suffix = iri.strip_prefix(active.vocab.as_deref()?)?;
+        let free =
+            !suffix.is_empty() && !suffix.contains(':') && !active.terms.contains_key(suffix);
+        free.then(|| suffix.to_string())
+    };
+    let prefixed = || {
+        shortest(
+            active
+                .terms
+                .iter()
+                .filter_map(|(term, d)| {
+                    let d = d.as_ref().filter(|d| d.prefix)?;
+                    let suffix = iri.strip_prefix(d.iri.as_str())?;
+                    let compact = format!("{term}:{suffix}");
+                    (!suffix.is_empty() && !active.terms.contains_key(&compact)).then_some(compact)
+                })
+                .collect(),
+        )
+    };
+
+    let compacted = match usage {
+        IriUse::Property => term().or_else(vocab_relative).or_else(prefixed),
+        IriUse::Vocab => prefixed().or_else(term).or_else(vocab_relative),
+        IriUse::Id => prefixed(),
+    };
+    compacted.unwrap_or_else(|| iri.to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::croissant::core::LangString;
+    use crate::croissant::validate::{ValidateOptions, validate_str_with_options};
+
+    fn document(context: Value, properties: Value) -> Value {
+        let mut document = json!({
+            "@context": context,
+            "@type": "sc:Dataset",
+            "name": "mydataset",
+        });
+        if let (Some(document), Value::Object(properties)) = (document.as_object_mut(), properties)
+        {
+            document.extend(properties);
+        }
+        document
+    }
+
+    #[test]
+    fn aliases_and_full_iris_compact_to_canonical_terms() {
+        let context = json!({
+            "@vocab": "https://schema.org/",
+            "sc": "https://schema.org/",
+            "cr": "http://mlcommons.org/croissant/",
+            "sets": { "@id": "cr:recordSet" },
+            "title": "sc:name",
+        });
+        let document = document(
+            context,
+            json!({
+                "sets": [{
+                    "@type": "cr:RecordSet",
+                    "@id": "main",
+                    "title": "main",
+                    "http://mlcommons.org/croissant/field": {
+                        "@type": "http://mlcommons.org/croissant/Field",
+                        "@id": "main/a",
+                        "http://mlcommons.org/croissant/dataType": { "@id": "https://schema.org/Text" },
+                    },
+                }],
+            }),
+        );
+        let normalized = normalize(document).unwrap();
+        assert_eq!(
+            normalized["recordSet"],
+            json!({
+                "@type": "cr:RecordSet",
+                "@id": "main",
+                "name": "main",
+                "field": { "@type": "cr:Field", "@id": "main/a", "dataType": ["sc:Text"] },
+            })
+        );
+        assert!(normalized["@context"].get("sets").is_none());
+    }
+
+    #[test]
+    fn array_contexts_apply_in_order() {
+        let context = json!(["http://mlcommons.org/croissant/1.0", { "title": "sc:description" }]);
+        let normalized = normalize(document(context, json!({ "title": "About" }))).unwrap();
+        assert_eq!(normalized["description"], "About");
+        assert_eq!(normalized["name"], "mydataset");
+    }
+
+    #[test]
+    fn terms_of_croissant_1_1_are_kept_when_declared() {
+        let properties = json!({ "samplingRate": 0.5 });
+        let normalized = normalize(document(croissant_1_1_context(), properties.clone())).unwrap();
+        assert_eq!(normalized["samplingRate"], 0.5);
+        assert_eq!(normalized["@context"]["samplingRate"], "cr:samplingRate");
+
+        // Left to @vocab by the 1.0 context, then moved to the Croissant namespace
+        let normalized = normalize(document(croissant_1_0_context(), properties)).unwrap();
+        assert_eq!(normalized["cr:samplingRate"], 0.5);
+        assert!(normalized["@context"].get("samplingRate").is_none());
+    }
+
+    #[test]
+    fn strings_keep_their_language_unless_it_is_the_default_one() {
+        let properties = json!({
+            "description": { "@value": "Un jeu de données", "@language": "fr" },
+            "keywords": [{ "@value": "data", "@language": "EN" }],
+        });
+        let normalized = normalize(document(croissant_1_0_context(), properties)).unwrap();
+        assert_eq!(
+            normalized["description"],
+            json!({ "@value": "Un jeu de données", "@language": "fr" })
+        );
+        assert_eq!(normalized["keywords"], json!(["data"]));
+
+        let mut context = croissant_1_0_context();
+        context["@language"] = "fr".into();
+        let normalized = normalize(document(context, json!({ "description": "Données" }))).unwrap();
+        assert_eq!(normalized["@context"]["@language"], "fr");
+        assert_eq!(normalized["description"], "Données");
+    }
+
+    #[test]
+    fn tagged_strings_reach_the_typed_model() {
+        let document = document(
+            json!({ "@vocab": "https://schema.org/", "cr": "http://mlcommons.org/croissant/" }),
+            json!({
+                "description": { "@value": "Passagers du Titanic", "@language": "fr" },
+                "version": "1.0.0",
+                "distribution": [],
+                "recordSet": [{
+                    "@type": "cr:RecordSet",
+                    "@id": "main",
+                    "name": "main",
+                    "description": "main",
+                    "field": [{
+                        "@type": "cr:Field",
+                        "@id": "main/a",
+                        "name": "a",
+                        "description": "A",
+                        "dataType": "sc:Text",
+                    }],
+                    "data": [{"main/a": "x"}],
+                }],
+            }),
+        );
+        let options = ValidateOptions { lenient: true };
+        let report = validate_str_with_options(&document.to_string(), &options);
+        assert!(!report.has_errors(), "{report}");
+
+        let metadata = parse_metadata(document).unwrap();
+        assert_eq!(
+            metadata.description,
+            LangString::new("Passagers du Titanic").with_language("fr")
+        );
+        let written = serde_json::to_value(&metadata).unwrap();
+        assert_eq!(
+            written["description"],
+            json!({ "@value": "Passagers du Titanic", "@language": "fr" })
+        );
+    }
+
+    #[test]
+    fn unsupported_keywords_are_errors() {
+        let cases = [
+            (
+                json!({ "@vocab": "https://schema.org/" }),
+                json!({ "@reverse": {} }),
+            ),
+            (
+                json!({ "@vocab": "https://schema.org/" }),
+                json!({ "@nest": {} }),
+            ),
+            (
+                json!({ "@vocab": "https://schema.org/", "about": { "@reverse": "sc:subjectOf" } }),
+                json!({}),
+            ),
+            (
+                json!({ "@vocab": "https://schema.org/", "about": { "@id": "sc:about", "@nest": "n" } }),
+                json!({}),
+            ),
+            (
+                json!({ "@vocab": "https://schema.org/", "about": { "@id": "sc:about", "@context": {} } }),
+                json!({}),
+            ),
+            (
+                json!({ "@vocab": "https://schema.org/", "label": { "@id": "sc:name", "@container": "@language" } }),
+                json!({}),
+            ),
+            (
+                json!({ "@import": "https://example.org/context.jsonld" }),
+                json!({}),
+            ),
+            (json!({ "@version": 1.0 }), json!({})),
+        ];
+        for (context, properties) in cases {
+            let result = normalize(document(context.clone(), properties));
+            assert!(
+                matches!(result, Err(Error::Unsupported(_))),
+                "{context}: {result:?}"
+            );
+        }
+    }
+}
@@ -0,0 +1,315 @@
+//! Minimal JSONPath support used by `jsonPath` extractions
+//!
+//! Supports the root `$`, child access (`.key`, `['key']`, with `\'` and `\\`
+//! escapes in quoted keys), array indices (`[0]`, `[-1]`) and wildcards
+//! (`.*`, `[*]`).
+use crate::croissant::errors::{Error, Result};
+use serde_json::Value;
+
+#[derive(Debug, Clone, PartialEq)]
+enum Segment {
+    Key(String),
+    Index(i64),
+    Wildcard,
+}
+
+fn parse(expr: &str) -> Result<Vec<Segment>> {
+    let invalid =
+        |reason: &str| Error::invalid_format(format!("Invalid JSONPath '{expr}': {reason}"));
+    let rest = expr
+        .trim()
+        .strip_prefix('$')
+        .ok_or_else(|| invalid("must start with '$'"))?;
+
+    let chars = rest.chars().collect::<Vec<_>>();
+    let mut segments = Vec::new();
+    let mut i = 0;
+    while i < chars.len() {
+        match chars[i] {
+            '.' => {
+                if chars.get(i + 1) == Some(&'.') {
+                    return Err(invalid("recursive descent is not supported"));
+                }
+                let start = i + 1;
+                let mut end = start;
+                while end < chars.len() && chars[end] != '.' && chars[end] != '[' {
+                    end += 1;
+                }
+                let key = chars[start..end].iter().collect::<String>();
+                match key.as_str() {
+                    "" => return Err(invalid("empty key")),
+                    "*" => segments.push(Segment::Wildcard),
+                    _ => segments.push(Segment::Key(key)),
+                }
+                i = end;
+            }
+            '[' if matches!(next_non_blank(&chars, i + 1), Some((_, '\'' | '"'))) => {
+                let (start, quote) = next_non_blank(&chars, i + 1).unwrap_or_default();
+                let mut key = String::new();
+                let mut end = start + 1;
+                loop {
+                    match chars.get(end) {
+                        None => return Err(invalid("unclosed quoted key")),
+                        Some('\\') => {
+                            let escaped = chars
+                                .get(end + 1)
+                                .ok_or_else(|| invalid("unclosed quoted key"))?;
+                            key.push(*escaped);
+                            end += 2;
+                        }
+                        Some(c) if *c == quote => break,
+                        Some(c) => {
+                            key.push(*c);
+                            end += 1;
+                        }
+                    }
+                }
+                match next_non_blank(&chars, end + 1) {
+                    Some((close, ']')) => i = close + 1,
+                    _ => return Err(invalid("expected ']' after quoted key")),
+                }
+                segments.push(Segment::Key(key));
+            }
+            '[' => {
+                let end = chars[i..]
+                    .iter()
+                    .position(|c| *c == ']')
+                    .map(|p| i + p)
+                    .ok_or_else(|| invalid("unclosed '['"))?;
+                let inner = chars[i + 1..end].iter().collect::<String>();
+                let inner = inner.trim();
+                if inner == "*" {
+                    segments.push(Segment::Wildcard);
+                } else {
+                    let index = inner
+                        .parse::<i64>()
+                        .map_err(|_| invalid("unsupported bracket expression"))?;
+                    segments.push(Segment::Index(index));
+                }
+                i = end + 1;
+            }
+            _ => return Err(invalid("expected '.' or '['")),
+        }
+    }
+    Ok(segments)
+}
+
+/// Position and value of the first non-whitespace character from `start`
+fn next_non_blank(chars: &[char], start: usize) -> Option<(usize, char)> {
+    chars
+        .iter()
+        .enumerate()
+        .skip(start)
+        .find(|(_, c)| !c.is_whitespace())
+        .map(|(i, c)| (i, *c))
+}
+
+/// Expression of the child `key` of `parent`: `parent.key` for keys made of
+/// letters, digits and `_` (or JSON-LD keywords such as `@type`),
+/// `parent['key']` with `'` and `\` escaped otherwise
+pub fn child(parent: &str, key: &str) -> String {
+    let mut chars = key.chars();
+    let shorthand = chars
+        .next()
+        .is_some_and(|c| c == '_' || c == '@' || c.is_alphabetic())
+        && chars.all(|c| c == '_' || c.is_alphanumeric());
+    if shorthand {
+        return format!("{parent}.{key}");
+    }
+    let escaped = key.replace('\\', "\\\\").replace('\'', "\\'");
+    format!("{parent}['{escaped}']")
+}
+
+/// Evaluate a JSONPath expression and return every matching value
+pub fn select<'a>(value: &'a Value, expr: &str) -> Result<Vec<&'a Value>> {
+    let mut current = vec![value];
+    for segment in parse(expr)? {
+        let mut next = Vec::new();
+        for value in current {
+            match (&segment, value) {
+                (Segment::Key(key), Value::Object(map)) => next.extend(map.get(key)),
+                (Segment::Index(index), Value::Array(items)) => {
+                    let index = if *index < 0 {
+                        items.len() as i64 + index
+                    } else {
+                        *index
+                    };
+                    if index >= 0 {
+                        next.extend(items.get(index as usize));
+                    }
+                }
+                (Segment::Wildcard, Value::Array(items)) => next.extend(items.iter()),
+                (Segment::Wildcard, Value::Object(map)) => next.extend(map.values()),
+                _ => {}
+            }
+        }
+        current = next;
+    }
+    Ok(current)
+}
+
+/// `true` when the expression may match more than one value
+pub fn is_multi(expr: &str) -> Result<bool> {
+    Ok(parse(expr)?.contains(&Segment::Wildcard))
+}
+
+/// Expression relative to each element of a root array (`$[*].a` → `$.a`)
+pub fn element_path(expr: &str) -> Option<String> {
+    let expr = expr.trim();
+    expr.strip_prefix("$[*]")
+        .or_else(|| expr.strip_prefix("$.*"))
+        .map(|rest| format!("${rest}"))
+}
+
+/// 1-based line and column of the node designated by a definite JSONPath in a
+/// JSON text
+///
+/// When a segment cannot be followed, the position of the deepest node
+/// reached is returned. Only keys and non-negative indices are supported; a
+/// value that is not a list is its own element `[0]`.
+pub fn locate(source: &str, expr: &str) -> Option<(usize, usize)> {
+    let segments = parse(expr).ok()?;
+    let bytes = source.as_bytes();
+    let mut pos = skip_whitespace(bytes, 0);
+    'segments: for segment in segments {
+        match (segment, bytes.get(pos)) {
+            (Segment::Key(key), Some(b'{')) => {
+                let mut i = skip_whitespace(bytes, pos + 1);
+                while bytes.get(i) == Some(&b'"') {
+                    let end = skip_value(bytes, i)?;
+                    let name = serde_json::from_str::<String>(&source[i..end]).ok()?;
+                    i = skip_whitespace(bytes, end);
+                    if bytes.get(i) != Some(&b':') {
+                        return None;
+                    }
+                    let value = skip_whitespace(bytes, i + 1);
+                    if name == key {
+                        pos = value;
+                        continue 'segments;
+                    }
+                    i = skip_whitespace(bytes, skip_value(bytes, value)?);
+                    if bytes.get(i) == Some(&b',') {
+                        i = skip_whitespace(bytes, i + 1);
+                    }
+                }
+                break;
+            }
+            (Segment::Index(index), Some(b'[')) if index >= 0 => {
+                let mut i = skip_whitespace(bytes, pos + 1);
+                for _ in 0..index {
+                    if bytes.get(i) == Some(&b']') {
+                        break 'segments;
+                    }
+                    i = skip_whitespace(bytes, skip_value(bytes, i)?);
+                    if bytes.get(i) == Some(&b',') {
+                        i = skip_whitespace(bytes, i + 1);
+                    }
+                }
+                if bytes.get(i) == Some(&b']') {
+                    break;
+                }
+                pos = i;
+            }
+            // A single value standing for a one-element list
+            (Segment::Index(0), Some(_)) => {}
+            _ => break,
+        }
+    }
+
+    let before = &source[..pos];
+    let line = before.matches('\n').count() + 1;
+    let column = before
+        .rsplit('\n')
+        .next()
+        .unwrap_or_default()
+        .chars()
+        .count()
+        + 1;
+    Some((line, column))
+}
+
+fn skip_whitespace(bytes: &[u8], mut i: usize) -> usize {
+    while bytes.get(i).is_some_and(|b| b.is_ascii_whitespace()) {
+        i += 1;
+    }
+    i
+}
+
+/// Position right after the JSON value starting at `i`
+fn skip_value(bytes: &[u8], mut i: usize) -> Option<usize> {
+    match bytes.get(i)? {
+        b'"' => {
+            i += 1;
+            loop {
+                match bytes.get(i)? {
+                    b'\\' => i += 2,
+                    b'"' => return Some(i + 1),
+                    _ => i += 1,
+                }
+            }
+        }
+        b'{' | b'[' => {
+            let mut depth = 0;
+            loop {
+                match bytes.get(i)? {
+                    b'"' => {
+                        i = skip_value(bytes, i)?;
+                        continue;
+                    }
+                    b'{' | b'[' => depth += 1,
+                    b'}' | b']' => {
+                        depth -= 1;
+                        if depth == 0 {
+                            return Some(i + 1);
+                        }
+                    }
+                    _ => {}
+                }
+                i += 1;
+            }
+        }
+        _ => {
+            while bytes
+                .get(i)
+                .is_some_and(|b| !matches!(b, b',' | b'}' | b']') && !b.is_ascii_whitespace())
+            {
+                i += 1;
+            }
+            Some(i)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn quoted_keys_may_contain_brackets_and_escaped_quotes() {
+        let value = json!({"x]y": 1, "it's": {"a.b": [2, 3]}, "back\\slash": 4});
+        assert_eq!(select(&value, "$['x]y']").unwrap(), vec![&json!(1)]);
+        assert_eq!(select(&value, "$[ \"x]y\" ]").unwrap(), vec![&json!(1)]);
+        assert_eq!(
+            select(&value, "$['it\\'s']['a.b'][-1]").unwrap(),
+            vec![&json!(3)]
+        );
+        assert_eq!(
+            select(&value, "$['back\\\\slash']").unwrap(),
+            vec![&json!(4)]
+        );
+        assert!(select(&value, "$['x]y'").is_err());
+        assert!(select(&value, "$['x]y' z]").is_err());
+    }
+
+    #[test]
+    fn child_expressions_parse_back_to_their_key() {
+        let value = json!({"plain": 1, "@type": 2, "x]y": 3, "it's": 4, "a\\b": 5, "a.b": 6});
+        for (key, expected) in value.as_object().unwrap() {
+            let expr = child("$", key);
+            assert_eq!(select(&value, &expr).unwrap(), vec![expected], "{expr}");
+        }
+        assert_eq!(child("$", "plain"), "$.plain");
+        assert_eq!(child("$", "it's"), "$['it\\'s']");
+    }
+}
@@ -0,0 +1,677 @@
+//! Migration of Croissant documents between versions of the specification
+//!
+//! Documents are first rewritten against the canonical context (see
+//! [`jsonld::normalize`]), then every construct that changed between the
+//! source and the target version is rewritten. Each rewrite is recorded as a
+//! [`Change`] so that migrations can be reviewed.
+//!
+//! From 0.8 to 1.0, the `ml:` vocabulary moved to `cr:`, `FileObject` and
+//! `FileSet` moved from schema.org to `cr:`, and nodes gained an `@id`
+//! referenced as `{"@id": ...}` where 0.8 used plain names (field sources,
+//! `references`, `key`, `containedIn`, inline `data`). `cr:isEnumeration` and
+//! the `sc:Url` spelling are deprecated in every version.
+use crate::croissant::core::{Metadata, SpecVersion};
+use crate::croissant::errors::{Error, Result};
+use crate::croissant::jsonld::{self, LEGACY_CROISSANT_NS};
+use serde::Serialize;
+use serde_json::{Map, Value, json};
+use std::collections::HashMap;
+use std::path::Path;
+
+/// A rewrite made while migrating a document
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct Change {
+    /// JSON path of the rewritten node in the migrated document
+    pub path: String,
+    pub message: String,
+}
+
+/// A migrated document with the changes made to it
+#[derive(Debug, Clone, PartialEq)]
+pub struct Migration {
+    pub from: SpecVersion,
+    pub to: SpecVersion,
+    pub document: Value,
+    pub changes: Vec<Change>,
+}
+
+/// Migrate a Croissant metadata file to a version of the specification
+pub fn migrate_file(path: &Path, to: SpecVersion) -> Result<Migration> {
+    let content = std::fs::read_to_string(path).map_err(|_| Error::file_not_found(path))?;
+    migrate(serde_json::from_str(&content)?, to)
+}
+
+/// Migrate a Croissant document to a version of the specification
+///
+/// Only upgrades are supported; migrating a document to an older version is
+/// an error.
+pub fn migrate(document: Value, to: SpecVersion) -> Result<Migration> {
+    let from = detect_version(&document);
+    if from > to {
+        return Err(Error::unsupported(format!(
+            "Migrating a Croissant {from} document to {to}"
+        )));
+    }
+    let mut document = jsonld::normalize(document)?;
+    let changes = upgrade(&mut document, from, to);
+    // Written in the property order of the typed model when the document fits it
+    if let Ok(metadata) = serde_json::from_value::<Metadata>(document.clone()) {
+        document = serde_json::to_value(&metadata)?;
+    }
+    Ok(Migration {
+        from,
+        to,
+        document,
+        changes,
+    })
+}
+
+/// Parse a document into the typed model, migrating documents older than
+/// 1.0 to 1.0 first
+///
+/// See [`crate::croissant::parse_metadata`] to read a document as it is.
+pub fn load_metadata(document: Value) -> Result<Metadata> {
+    let from = detect_version(&document);
+    let mut document = jsonld::normalize(document)?;
+    if from < SpecVersion::V1_0 {
+        upgrade(&mut document, from, SpecVersion::V1_0);
+    }
+    Ok(serde_json::from_value(document)?)
+}
+
+/// Version of a document, read from `conformsTo` or else its `@context`
+///
+/// Documents declaring neither are taken for 1.0.
+pub fn detect_version(document: &Value) -> SpecVersion {
+    let conforms_to = [
+        "conformsTo",
+        "dct:conformsTo",
+        "http://purl.org/dc/terms/conformsTo",
+    ]
+    .iter()
+    .find_map(|key| document.get(*key));
+    let declared = match conforms_to {
+        Some(Value::String(iri)) => SpecVersion::from_iri(iri),
+        Some(Value::Array(iris)) => iris
+            .iter()
+            .filter_map(Value::as_str)
+            .filter_map(SpecVersion::from_iri)
+            .max(),
+        _ => None,
+    };
+    declared
+        .or_else(|| document.get("@context").and_then(context_version))
+        .unwrap_or_default()
+}
+
+/// Version implied by a `@context`: its URL, or the 0.8 `ml:` namespace
+fn context_version(context: &Value) -> Option<SpecVersion> {
+    match context {
+        Value::String(url) => SpecVersion::from_iri(url),
+        Value::Array(contexts) => contexts.iter().filter_map(context_version).max(),
+        Value::Object(terms) => terms
+            .values()
+            .any(|definition| definition.as_str() == Some(LEGACY_CROISSANT_NS))
+            .then_some(SpecVersion::V0_8),
+        _ => None,
+    }
+}
+
+/// Rewrite a normalized document from one version to another
+///
+/// The document must already be compacted against the canonical context
+/// (see [`jsonld::normalize`]).
+pub(crate) fn upgrade(document: &mut Value, from: SpecVersion, to: SpecVersion) -> Vec<Change> {
+    let mut migrator = Migrator::default();
+    if let Value::Object(dataset) = document {
+        if from < SpecVersion::V1_0 {
+            migrator.upgrade_0_8(dataset);
+        }
+        migrator.replace_deprecated(dataset);
+        migrator.set_version(dataset, to);
+    }
+    migrator.changes
+}
+
+#[derive(Default)]
+struct Migrator {
+    changes: Vec<Change>,
+}
+
+impl Migrator {
+    fn change(&mut self, path: &str, message: impl Into<String>) {
+        self.changes.push(Change {
+            path: path.to_string(),
+            message: message.into(),
+        });
+    }
+
+    fn upgrade_0_8(&mut self, dataset: &mut Map<String, Value>) {
+        // Property of field sources referencing each distribution, by @id
+        let mut source_keys = HashMap::new();
+        for (path, node) in children(dataset, "distribution", "$") {
+            self.rename_type(node, &path);
+            let id = self.add_id(node, &path, None);
+            let key = match node.get("@type").and_then(Value::as_str) {
+                Some("cr:FileSet") => "fileSet",
+                _ => "fileObject",
+            };
+            if let Some(id) = id {
+                source_keys.insert(id, key);
+            }
+            self.reference_list(node, "containedIn", &path);
+        }
+
+        for (path, record_set) in children(dataset, "recordSet", "$") {
+            self.rename_type(record_set, &path);
+            let Some(record_set_id) = self.add_id(record_set, &path, None) else {
+                continue;
+            };
+            let mut field_ids = HashMap::new();
+            for (field_path, field) in children(record_set, "field", &path) {
+                let name = field.get("name").and_then(Value::as_str).map(String::from);
+                let id = self.upgrade_field_0_8(field, &field_path, &record_set_id, &source_keys);
+                if let (Some(name), Some(id)) = (name, id) {
+                    field_ids.insert(name, id);
+                }
+            }
+            self.upgrade_key_0_8(record_set, &path, &record_set_id);
+            self.upgrade_data_0_8(record_set, &path, &field_ids);
+        }
+    }
+
+    /// Upgrade a field and its sub-fields, returning its `@id`
+    fn upgrade_field_0_8(
+        &mut self,
+        field: &mut Map<String, Value>,
+        path: &str,
+        parent_id: &str,
+        source_keys: &HashMap<String, &'static str>,
+    ) -> Option<String> {
+        self.rename_type(field, path);
+        let id = self.add_id(field, path, Some(parent_id));
+        if let Some(Value::Object(source)) = field.get_mut("source") {
+            self.upgrade_source_0_8(source, &format!("{path}.source"), source_keys);
+        }
+        for (reference_path, reference) in children(field, "references", path) {
+            self.reference(reference, "field", &reference_path);
+        }
+        self.reference_list(field, "parentField", path);
+        if let Some(id) = &id {
+            for (sub_path, sub_field) in children(field, "subField", path) {
+                self.upgrade_field_0_8(sub_field, &sub_path, id, source_keys);
+            }
+        }
+        id
+    }
+
+    fn upgrade_source_0_8(
+        &mut self,
+        source: &mut Map<String, Value>,
+        path: &str,
+        source_keys: &HashMap<String, &'static str>,
+    ) {
+        if let Some(distribution) = source.remove("distribution") {
+            let id = match &distribution {
+                Value::Array(ids) => ids.first().and_then(Value::as_str),
+                id => id.as_str(),
+            };
+            if let Some(id) = id {
+                let key = source_keys.get(id).copied().unwrap_or("fileObject");
+                self.change(
+                    path,
+                    format!("Replaced the `distribution` \"{id}\" by a `{key}` reference"),
+                );
+                source.insert(key.to_string(), json!({ "@id": id }));
+            } else {
+                source.insert("distribution".to_string(), distribution);
+            }
+        }
+        for key in ["field", "recordSet", "fileObject", "fileSet"] {
+            self.reference(source, key, path);
+        }
+    }
+
+    /// Turn `key` field names into references to field `@id`s
+    fn upgrade_key_0_8(&mut self, record_set: &mut Map<String, Value>, path: &str, id: &str) {
+        let names = match record_set.get("key") {
+            Some(Value::String(name)) => vec![name.clone()],
+            Some(Value::Array(names)) if names.iter().all(Value::is_string) => names
+                .iter()
+                .filter_map(Value::as_str)
+                .map(String::from)
+                .collect(),
+            _ => return,
+        };
+        let keys = names
+            .iter()
+            .map(|name| match name.contains('/') {
+                true => json!({ "@id": name }),
+                false => json!({ "@id": format!("{id}/{name}") }),
+            })
+            .collect();
+        record_set.insert("key".to_string(), Value::Array(keys));
+        self.change(
+            &format!("{path}.key"),
+            format!(
+                "Turned the key names {} into field `@id` references",
+                names.join(", ")
+            ),
+        );
+    }
+
+    /// Key inline records by field `@id` rather than field name
+    fn upgrade_data_0_8(
+        &mut self,
+        record_set: &mut Map<String, Value>,
+        path: &str,
+        field_ids: &HashMap<String, String>,
+    ) {
+        let Some(Value::Array(records)) = record_set.get_mut("data") else {
+            return;
+        };
+        let mut renamed = false;
+        for record in records.iter_mut().filter_map(Value::as_object_mut) {
+            *record = std::mem::take(record)
+                .into_iter()
+                .map(|(key, value)| match field_ids.get(&key) {
+                    Some(id) if *id != key => {
+                        renamed = true;
+                        (id.clone(), value)
+                    }
+                    _ => (key, value),
+                })
+                .collect();
+        }
+        if renamed {
+            self.change(
+                &format!("{path}.data"),
+                "Keyed the inline records by field `@id` instead of field name",
+            );
+        }
+    }
+
+    /// Move node types of the 0.8 vocabulary and schema.org to `cr:`
+    fn rename_type(&mut self, node: &mut Map<String, Value>, path: &str) {
+        let Some(Value::String(kind)) = node.get_mut("@type") else {
+            return;
+        };
+        let renamed = match kind.as_str() {
+            "sc:FileObject" => "cr:FileObject".to_string(),
+            "sc:FileSet" => "cr:FileSet".to_string(),
+            other => match other.strip_prefix("ml:") {
+                Some(name) => format!("cr:{name}"),
+                None => return,
+            },
+        };
+        let message = format!("Renamed the type `{kind}` to `{renamed}`");
+        *kind = renamed;
+        self.change(path, message);
+    }
+
+    /// Name a node by its 0.8 `name`, prefixed by its parent `@id`
+    fn add_id(
+        &mut self,
+        node: &mut Map<String, Value>,
+        path: &str,
+        parent_id: Option<&str>,
+    ) -> Option<String> {
+        if let Some(id) = node.get("@id").and_then(Value::as_str) {
+            return Some(id.to_string());
+        }
+        let name = node.get("name").and_then(Value::as_str)?;
+        let id = match parent_id {
+            Some(parent) => format!("{parent}/{name}"),
+            None => name.to_string(),
+        };
+        let rest = std::mem::take(node);
+        node.insert("@id".to_string(), Value::String(id.clone()));
+        node.extend(rest);
+        self.change(path, format!("Added the `@id` \"{id}\""));
+        Some(id)
+    }
+
+    /// Turn a name into an `{"@id": ...}` reference
+    fn reference(&mut self, node: &mut Map<String, Value>, key: &str, path: &str) {
+        if let Some(value) = node.get_mut(key)
+            && let Value::String(id) = value
+        {
+            self.change(
+                path,
+                format!("Turned the `{key}` name \"{id}\" into an `@id` reference"),
+            );
+            *value = json!({ "@id": id });
+        }
+    }
+
+    /// Turn one or many names into `{"@id": ...}` references
+    fn reference_list(&mut self, node: &mut Map<String, Value>, key: &str, path: &str) {
+        let Some(value) = node.get_mut(key) else {
+            return;
+        };
+        let items = match value {
+            Value::Array(items) => items.iter_mut().collect::<Vec<_>>(),
+            value => vec![value],
+        };
+        let mut names = Vec::new();
+        for item in items {
+            if let Value::String(id) = item {
+                names.push(id.clone());
+                *item = json!({ "@id": id });
+            }
+        }
+        if !names.is_empty() {
+            self.change(
+                path,
+                format!(
+                    "Turned the `{key}` names {} into `@id` references",
+                    names.join(", ")
+                ),
+            );
+        }
+    }
+
+    fn replace_deprecated(&mut self, dataset: &mut Map<String, Value>) {
+        for (path, record_set) in children(dataset, "recordSet", "$") {
+            self.replace_is_enumeration(record_set, &path);
+            self.replace_url_type(record_set, &path);
+            for (field_path, field) in children(record_set, "field", &path) {
+                self.replace_deprecated_field(field, &field_path);
+            }
+        }
+    }
+
+    fn replace_deprecated_field(&mut self, field: &mut Map<String, Value>, path: &str) {
+        self.replace_url_type(field, path);
+        for (sub_path, sub_field) in children(field, "subField", path) {
+            self.replace_deprecated_field(sub_field, &sub_path);
+        }
+    }
+
+    /// `isEnumeration: true` became the `sc:Enumeration` data type in 1.0
+    fn replace_is_enumeration(&mut self, record_set: &mut Map<String, Value>, path: &str) {
+        let Some(flag) = ["cr:isEnumeration", "isEnumeration"]
+            .into_iter()
+            .find_map(|key| record_set.remove(key))
+        else {
+            return;
+        };
+        if flag.as_bool() != Some(true) {
+            self.change(path, "Removed the deprecated `isEnumeration`");
+            return;
+        }
+        let data_types = record_set
+            .entry("dataType")
+            .or_insert_with(|| Value::Array(Vec::new()));
+        if let Value::String(data_type) = data_types {
+            *data_types = json!([data_type]);
+        }
+        if let Value::Array(data_types) = data_types
+            && !data_types.iter().any(|t| t == "sc:Enumeration")
+        {
+            data_types.push(Value::String("sc:Enumeration".to_string()));
+        }
+        self.change(
+            path,
+            "Replaced the deprecated `isEnumeration` by the `sc:Enumeration` data type",
+        );
+    }
+
+    /// The schema.org type is spelled `URL`
+    fn replace_url_type(&mut self, node: &mut Map<String, Value>, path: &str) {
+        let Some(data_types) = node.get_mut("dataType") else {
+            return;
+        };
+        let items = match data_types {
+            Value::Array(items) => items.iter_mut().collect::<Vec<_>>(),
+            value => vec![value],
+        };
+        for item in items {
+            if let Value::String(data_type) = item
+                && matches!(data_type.as_str(), "sc:Url" | "https://schema.org/Url")
+            {
+                self.change(
+                    &format!("{path}.dataType"),
+                    format!("Replaced the data type `{data_type}` by `sc:URL`"),
+                );
+                *item = Value::String("sc:URL".to_string());
+            }
+        }
+    }
+
+    /// Declare the target version in `conformsTo` and `@context`
+    fn set_version(&mut self, dataset: &mut Map<String, Value>, to: SpecVersion) {
+        if let Some(Value::Object(context)) = dataset.get_mut("@context") {
+            let legacy = context
+                .iter()
+                .filter(|(_, definition)| definition.as_str() == Some(LEGACY_CROISSANT_NS))
+                .map(|(term, _)| term.clone())
+                .collect::<Vec<_>>();
+            for term in legacy {
+                context.remove(&term);
+                self.change(
+                    "$.@context",
+                    format!("Removed the `{term}` prefix of the 0.8 vocabulary"),
+                );
+            }
+            if to >= SpecVersion::V1_1 {
+                let mut added = Vec::new();
+                if let Value::Object(terms) = jsonld::croissant_1_1_context() {
+                    for (term, definition) in terms {
+                        if !context.contains_key(&term) {
+                            added.push(term.clone());
+                            context.insert(term, definition);
+                        }
+                    }
+                }
+                if !added.is_empty() {
+                    self.change(
+                        "$.@context",
+                        format!("Added the Croissant 1.1 terms {}", added.join(", ")),
+                    );
+                }
+            }
+        }
+
+        let iri = to.conforms_to();
+        let previous = dataset.get("conformsTo").cloned();
+        if previous.as_ref().and_then(Value::as_str) == Some(iri) {
+            return;
+        }
+        let message = match &previous {
+            Some(previous) => format!("Set `conformsTo` to \"{iri}\" instead of {previous}"),
+            None => format!("Set `conformsTo` to \"{iri}\""),
+        };
+        if previous.is_some() {
+            dataset.insert("conformsTo".to_string(), Value::String(iri.to_string()));
+        } else {
+            // Declared right after the description, as in generated documents
+            let rest = std::mem::take(dataset);
+            for (key, value) in rest {
+                let after = key == "description";
+                dataset.insert(key, value);
+                if after {
+                    dataset.insert("conformsTo".to_string(), Value::String(iri.to_string()));
+                }
+            }
+            dataset
+                .entry("conformsTo")
+                .or_insert_with(|| Value::String(iri.to_string()));
+        }
+        self.change("$.conformsTo", message);
+    }
+}
+
+/// Nodes held by a property, one or many, with their JSON paths
+fn children<'a>(
+    node: &'a mut Map<String, Value>,
+    key: &str,
+    path: &str,
+) -> Vec<(String, &'a mut Map<String, Value>)> {
+    match node.get_mut(key) {
+        Some(Value::Array(items)) => items
+            .iter_mut()
+            .enumerate()
+            .filter_map(|(i, item)| Some((format!("{path}.{key}[{i}]"), item.as_object_mut()?)))
+            .collect(),
+        Some(Value::Object(item)) => vec![(format!("{path}.{key}"), item)],
+        _ => Vec::new(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// A small Croissant 0.8 document, with name-based references
+    fn document_0_8() -> Value {
+        json!({
+            "@context": {
+                "@language": "en",
+                "@vocab": "https://schema.org/",
+                "sc": "https://schema.org/",
+                "ml": "http://mlcommons.org/schema/",
+                "column": "ml:column",
+                "data": { "@id": "ml:data", "@type": "@json" },
+                "dataType": { "@id": "ml:dataType", "@type": "@vocab" },
+                "extract": "ml:extract",
+                "field": "ml:field",
+                "recordSet": "ml:recordSet",
+                "source": "ml:source",
+            },
+            "@type": "sc:Dataset",
+            "name": "legacy",
+            "description": "A dataset described with Croissant 0.8",
+            "url": "https://example.org/legacy",
+            "license": "https://creativecommons.org/licenses/by/4.0/",
+            "creator": { "@type": "sc:Person", "name": "Ada" },
+            "datePublished": "2023-01-01",
+            "version": "1.0.0",
+            "distribution": [{
+                "@type": "sc:FileObject",
+                "name": "rows.csv",
+                "contentUrl": "rows.csv",
+                "encodingFormat": "text/csv",
+                "sha256": "0000",
+            }],
+            "recordSet": [
+                {
+                    "@type": "ml:RecordSet",
+                    "name": "rows",
+                    "description": "Rows",
+                    "ml:key": "id",
+                    "field": [
+                        {
+                            "@type": "ml:Field",
+                            "name": "id",
+                            "description": "Identifier",
+                            "dataType": "sc:Integer",
+                            "source": { "distribution": "rows.csv", "extract": { "column": "id" } },
+                        },
+                        {
+                            "@type": "ml:Field",
+                            "name": "link",
+                            "description": "Link",
+                            "dataType": "sc:Url",
+                            "source": { "distribution": "rows.csv", "extract": { "column": "link" } },
+                        },
+                    ],
+                },
+                {
+                    "@type": "ml:RecordSet",
+                    "name": "splits",
+                    "description": "Splits",
+                    "isEnumeration": true,
+                    "field": [{
+                        "@type": "ml:Field",
+                        "name": "name",
+                        "description": "Split name",
+                        "dataType": "sc:Text",
+                    }],
+                    "data": [{ "name": "train" }, { "name": "test" }],
+                },
+            ],
+        })
+    }
+
+    #[test]
+    fn versions_are_detected_from_conforms_to_or_the_context() {
+        assert_eq!(detect_version(&document_0_8()), SpecVersion::V0_8);
+        let document = json!({
+            "@context": "https://mlcommons.org/croissant/1.0/context.jsonld",
+            "conformsTo": ["http://mlcommons.org/croissant/1.0", "http://mlcommons.org/croissant/1.1"],
+        });
+        assert_eq!(detect_version(&document), SpecVersion::V1_1);
+        let document = json!({ "@context": ["http://mlcommons.org/croissant/1.1", {}] });
+        assert_eq!(detect_version(&document), SpecVersion::V1_1);
+        assert_eq!(detect_version(&json!({})), SpecVersion::V1_0);
+    }
+
+    #[test]
+    fn croissant_0_8_documents_are_upgraded() {
+        let migration = migrate(document_0_8(), SpecVersion::V1_0).unwrap();
+        assert_eq!(migration.from, SpecVersion::V0_8);
+        let document = &migration.document;
+        assert_eq!(document["conformsTo"], "http://mlcommons.org/croissant/1.0");
+        assert!(document["@context"].get("ml").is_none());
+        assert_eq!(document["distribution"][0]["@type"], "cr:FileObject");
+        assert_eq!(document["distribution"][0]["@id"], "rows.csv");
+
+        let rows = &document["recordSet"][0];
+        assert_eq!(rows["@type"], "cr:RecordSet");
+        assert_eq!(rows["@id"], "rows");
+        assert_eq!(rows["key"], json!({ "@id": "rows/id" }));
+        assert_eq!(rows["field"][0]["@id"], "rows/id");
+        assert_eq!(
+            rows["field"][0]["source"],
+            json!({ "fileObject": { "@id": "rows.csv" }, "extract": { "column": "id" } })
+        );
+        assert_eq!(rows["field"][1]["dataType"], "sc:URL");
+
+        let splits = &document["recordSet"][1];
+        assert_eq!(splits["dataType"], "sc:Enumeration");
+        assert!(splits.get("isEnumeration").is_none());
+        assert_eq!(
+            splits["data"],
+            json!([{ "splits/name": "train" }, { "splits/name": "test" }])
+        );
+
+        let change = |path: &str| {
+            migration
+                .changes
+                .iter()
+                .filter(|c| c.path == path)
+                .map(|c| c.message.as_str())
+                .collect::<Vec<_>>()
+        };
+        assert_eq!(
+            change("$.recordSet[0].key"),
+            vec!["Turned the key names id into field `@id` references"]
+        );
+        assert_eq!(
+            change("$.recordSet[0].field[1].dataType"),
+            vec!["Replaced the data type `sc:Url` by `sc:URL`"]
+        );
+    }
+
+    #[test]
+    fn documents_are_loaded_migrated_but_parsed_as_they_are() {
+        let metadata = load_metadata(document_0_8()).unwrap();
+        assert_eq!(metadata.record_sets[0].fields[0].id.0, "rows/id");
+        assert!(jsonld::parse_metadata(document_0_8()).is_err());
+    }
+
+    #[test]
+    fn upgrades_to_1_1_declare_its_terms_and_downgrades_fail() {
+        let migration = migrate(document_0_8(), SpecVersion::V1_1).unwrap();
+        assert_eq!(
+            migration.document["@context"]["samplingRate"],
+            "cr:samplingRate"
+        );
+        assert_eq!(
+            migration.document["conformsTo"],
+            "http://mlcommons.org/croissant/1.1"
+        );
+        assert!(migrate(migration.document, SpecVersion::V1_0).is_err());
+    }
+}
@@ -1,5 +1,74 @@
-mod core;
-mod errors;
-pub mod generate;
-pub mod utils;
-pub mod validate;
+//! Croissant metadata model, generation and validation
+//!
+//! The data model lives in [`core`] and is re-exported here so downstream
+//! crates can build, inspect and match on Croissant documents directly.
+//! Most users will want `use rustcroissant::prelude::*;`.
+mod archive;
+mod compression;
+mod config;
+pub mod core;
+mod data;
+mod dialect;
+pub mod errors;
+mod format;
+mod generate;
+mod infer;
+mod jsonld;
+mod jsonpath;
+mod migrate;
+pub mod prelude;
+mod rdf;
+mod records;
+mod references;
+mod relations;
+mod report;
+mod schema;
+mod utils;
+mod validate;
+mod verify;
+
+pub use self::archive::{ArchiveFormat, ArchiveMember, Location, list_members};
+pub use self::compression::{Compression, decompressed_name, open_decompressed};
+pub use self::config::{ColumnConfig, CreatorConfig, CreatorKind, GenerateConfig};
+pub use self::core::{
+    Agent, BoundingBoxFormat, Context, CrType, CreativeWork, CreativeWorkBuilder, CroissantType,
+    CsvDialect, CsvDialectBuilder, DataType, Distribution, DistributionBuilder, Extract, Field,
+    FieldBuilder, FieldRef, FieldSource, FieldSourceBuilder, FileObject, FileObjectBuilder,
+    FileProperty, FileSet, FileSetBuilder, Id, LangString, License, Metadata, MetadataBuilder,
+    MetadataContext, Organization, OrganizationBuilder, Person, PersonBuilder, RecordSet,
+    RecordSetBuilder, Ref, Resource, ResponsibleAi, ResponsibleAiBuilder, SourceRef, SpecVersion,
+    Text, Transform, ValueFormat, default_context,
+};
+pub use self::data::{check_inline_data, validate_data, validate_data_file};
+pub use self::dialect::{open_csv, sniff_dialect};
+pub use self::errors::{Error, Result};
+pub use self::format::{format_file, format_metadata, format_str, is_formatted};
+pub use self::generate::{
+    ChecksumMode, GenerateOptions, generate_metadata, generate_metadata_from_archive,
+    generate_metadata_from_csv, generate_metadata_from_csv_files,
+    generate_metadata_from_csv_with_options, generate_metadata_from_directory,
+    generate_metadata_from_parquet, read_rai_file,
+};
+pub use self::infer::{
+    ColumnProfile, InferenceOptions, JsonLayout, JsonProfile, SchemaColumn, infer_csv_columns,
+    infer_csv_files, infer_json_columns, infer_parquet_columns,
+};
+pub use self::jsonld::parse_metadata;
+pub use self::migrate::{Change, Migration, detect_version, load_metadata, migrate, migrate_file};
+pub use self::rdf::{RdfFormat, RdfOptions, Term, Triple, convert_file, to_rdf, to_triples};
+pub use self::records::{Dataset, Record, Records, Value};
+pub use self::relations::{
+    ColumnValues, InclusionDependency, TableValues, inclusion_dependencies, primary_key,
+    read_csv_values,
+};
+pub use self::report::{Issue, ReportFormat, Severity, ValidationReport, codes};
+pub use self::utils::{
+    calculate_md5, calculate_sha256, format_file_size, get_csv_columns, get_file_extension,
+    is_file_readable, md5_reader, normalize_path, sha256_reader, validate_csv_format,
+    validate_output_path,
+};
+pub use self::validate::{
+    ValidateOptions, validate_file, validate_file_with_options, validate_metadata,
+    validate_metadata_with_options, validate_str, validate_str_with_options,
+};
+pub use self::verify::{verify_file, verify_metadata};
@@ -0,0 +1,45 @@
+//! Convenience re-exports of the most commonly used types
+//!
+//! ```
+//! use rustcroissant::prelude::*;
+//!
+//! let field = Field::builder()
+//!     .id(Id::new("main/id"))
+//!     .kind(CrType::Field)
+//!     .name(Text::new("id"))
+//!     .description(Text::new("Row identifier"))
+//!     .data_types(vec![DataType::Integer])
+//!     .source(Some(
+//!         FieldSource::builder()
+//!             .source(SourceRef::FileObject {
+//!                 file_object: Ref { id: Id::new("data.csv") },
+//!             })
+//!             .extract(Some(Extract::Column { name: Text::new("id") }))
+//!             .build()
+//!             .unwrap(),
+//!     ))
+//!     .build()
+//!     .unwrap();
+//! assert_eq!(field.name, Text::new("id"));
+//! ```
+pub use crate::croissant::config::GenerateConfig;
+pub use crate::croissant::core::{
+    Agent, CrType, CroissantType, DataType, Distribution, Extract, Field, FieldRef, FieldSource,
+    FileObject, FileProperty, FileSet, Id, LangString, License, Metadata, Organization, Person,
+    RecordSet, Ref, Resource, ResponsibleAi, SourceRef, SpecVersion, Text, Transform, ValueFormat,
+    default_context,
+};
+pub use crate::croissant::errors::{Error, Result};
+pub use crate::croissant::generate::{
+    ChecksumMode, GenerateOptions, generate_metadata, generate_metadata_from_csv,
+    generate_metadata_from_csv_files, generate_metadata_from_csv_with_options,
+    generate_metadata_from_directory,
+};
+pub use crate::croissant::infer::InferenceOptions;
+pub use crate::croissant::rdf::{RdfFormat, RdfOptions, to_rdf};
+pub use crate::croissant::records::{Dataset, Record, Value};
+pub use crate::croissant::report::{Issue, ReportFormat, Severity, ValidationReport};
+pub use crate::croissant::validate::{
+    ValidateOptions, validate_file, validate_file_with_options, validate_metadata,
+    validate_metadata_with_options, validate_str, validate_str_with_options,
+};
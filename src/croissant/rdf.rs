@@ -0,0 +1,560 @@
+//! Export of Croissant documents as RDF
+//!
+//! The document is expanded as JSON-LD and every node becomes a set of
+//! triples, which can be written as Turtle, N-Triples or N-Quads.
+use crate::croissant::core::Metadata;
+use crate::croissant::errors::{Error, Result};
+use crate::croissant::{jsonld, migrate};
+use serde_json::{Map, Value};
+use std::collections::HashMap;
+use std::fmt::Write;
+use std::path::Path;
+use std::str::FromStr;
+use url::Url;
+
+const RDF_NS: &str = "http://www.w3.org/1999/02/22-rdf-syntax-ns#";
+const XSD_NS: &str = "http://www.w3.org/2001/XMLSchema#";
+
+/// Subject or object of a triple
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub enum Term {
+    Iri(String),
+    BlankNode(String),
+    Literal {
+        value: String,
+        datatype: String,
+        language: Option<String>,
+    },
+}
+
+/// A single RDF statement
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct Triple {
+    pub subject: Term,
+    pub predicate: String,
+    pub object: Term,
+}
+
+/// Serialization of the exported triples
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum RdfFormat {
+    #[default]
+    Turtle,
+    NTriples,
+    /// N-Triples with every statement in the graph named by [`RdfOptions::graph`]
+    NQuads,
+}
+
+impl FromStr for RdfFormat {
+    type Err = Error;
+
+    fn from_str(format: &str) -> Result<Self> {
+        match format.to_ascii_lowercase().as_str() {
+            "turtle" | "ttl" => Ok(Self::Turtle),
+            "ntriples" | "n-triples" | "nt" => Ok(Self::NTriples),
+            "nquads" | "n-quads" | "nq" => Ok(Self::NQuads),
+            other => Err(Error::unsupported(format!("RDF format '{other}'"))),
+        }
+    }
+}
+
+/// Options of the RDF export
+#[derive(Debug, Clone, Default)]
+pub struct RdfOptions {
+    /// IRI relative `@id`s are resolved against, by default the dataset `url`
+    /// followed by a slash
+    pub base: Option<String>,
+    /// Name of the graph of N-Quads statements, by default the dataset IRI
+    pub graph: Option<String>,
+}
+
+/// Export a Croissant metadata file as RDF
+pub fn convert_file(path: &Path, format: RdfFormat, options: &RdfOptions) -> Result<String> {
+    let content = std::fs::read_to_string(path).map_err(|_| Error::file_not_found(path))?;
+    let metadata = migrate::load_metadata(serde_json::from_str(&content)?)?;
+    to_rdf(&metadata, format, options)
+}
+
+/// Serialize a document as RDF
+pub fn to_rdf(metadata: &Metadata, format: RdfFormat, options: &RdfOptions) -> Result<String> {
+    let (dataset, triples) = export(metadata, options)?;
+    Ok(match format {
+        RdfFormat::Turtle => write_turtle(&triples, &prefixes(metadata)),
+        RdfFormat::NTriples => write_lines(&triples, None),
+        RdfFormat::NQuads => {
+            let graph = options.graph.as_deref().unwrap_or(&dataset);
+            write_lines(&triples, Some(graph))
+        }
+    })
+}
+
+/// Triples describing a document
+///
+/// The dataset is named by its `@id`, or by the base IRI when it has none.
+/// Other nodes without `@id`, such as sources and extractions, are blank
+/// nodes.
+pub fn to_triples(metadata: &Metadata, options: &RdfOptions) -> Result<Vec<Triple>> {
+    Ok(export(metadata, options)?.1)
+}
+
+fn export(metadata: &Metadata, options: &RdfOptions) -> Result<(String, Vec<Triple>)> {
+    let base = match (&options.base, &metadata.url) {
+        (Some(base), _) => base.clone(),
+        (None, Some(url)) if url.ends_with(['/', '#']) => url.clone(),
+        (None, Some(url)) => format!("{url}/"),
+        (None, None) => {
+            return Err(Error::invalid_format(
+                "The dataset has no url, a base IRI is required to name its nodes",
+            ));
+        }
+    };
+    let base = Url::parse(&base)
+        .map_err(|e| Error::invalid_format(format!("Invalid base IRI '{base}': {e}")))?;
+
+    let nodes = jsonld::expand(&serde_json::to_value(metadata)?)?;
+    let mut exporter = Exporter {
+        base,
+        triples: Vec::new(),
+        blank_nodes: 0,
+    };
+    let mut dataset = None;
+    for node in &nodes {
+        if let Value::Object(node) = node {
+            let subject = exporter.node(node, dataset.is_none())?;
+            if dataset.is_none()
+                && let Term::Iri(iri) = subject
+            {
+                dataset = Some(iri);
+            }
+        }
+    }
+    let dataset = dataset.unwrap_or_else(|| exporter.base.to_string());
+    Ok((dataset, exporter.triples))
+}
+
+struct Exporter {
+    base: Url,
+    triples: Vec<Triple>,
+    blank_nodes: usize,
+}
+
+impl Exporter {
+    fn blank_node(&mut self) -> Term {
+        self.blank_nodes += 1;
+        Term::BlankNode(format!("b{}", self.blank_nodes - 1))
+    }
+
+    fn resolve(&self, id: &str) -> Result<Term> {
+        if let Some(label) = id.strip_prefix("_:") {
+            return Ok(Term::BlankNode(label.to_string()));
+        }
+        match Url::parse(id) {
+            Ok(_) => Ok(Term::Iri(id.to_string())),
+            Err(_) => self
+                .base
+                .join(id)
+                .map(|iri| Term::Iri(iri.to_string()))
+                .map_err(|e| Error::invalid_format(format!("Cannot resolve @id '{id}': {e}"))),
+        }
+    }
+
+    fn push(&mut self, subject: &Term, predicate: &str, object: Term) {
+        self.triples.push(Triple {
+            subject: subject.clone(),
+            predicate: predicate.to_string(),
+            object,
+        });
+    }
+
+    fn node(&mut self, node: &Map<String, Value>, root: bool) -> Result<Term> {
+        let subject = match node.get("@id").and_then(Value::as_str) {
+            Some(id) => self.resolve(id)?,
+            None if root => Term::Iri(self.base.to_string()),
+            None => self.blank_node(),
+        };
+        let types = node.get("@type").and_then(Value::as_array);
+        for kind in types.into_iter().flatten().filter_map(Value::as_str) {
+            let kind = self.resolve(kind)?;
+            self.push(&subject, &format!("{RDF_NS}type"), kind);
+        }
+        for (property, values) in node {
+            if property.starts_with('@') || !property.contains(':') {
+                continue;
+            }
+            for value in values.as_array().into_iter().flatten() {
+                let object = self.object(value)?;
+                self.push(&subject, property, object);
+            }
+        }
+        Ok(subject)
+    }
+
+    fn object(&mut self, value: &Value) -> Result<Term> {
+        let Value::Object(map) = value else {
+            return Ok(literal(value, None, None));
+        };
+        if let Some(value) = map.get("@value") {
+            let datatype = map.get("@type").and_then(Value::as_str);
+            let language = map.get("@language").and_then(Value::as_str);
+            return Ok(literal(value, datatype, language));
+        }
+        if let Some(items) = map.get("@list") {
+            return self.list(items.as_array().map(Vec::as_slice).unwrap_or_default());
+        }
+        match map.get("@id").and_then(Value::as_str) {
+            Some(id) if map.len() == 1 => self.resolve(id),
+            _ => self.node(map, false),
+        }
+    }
+
+    fn list(&mut self, items: &[Value]) -> Result<Term> {
+        let Some((first, rest)) = items.split_first() else {
+            return Ok(Term::Iri(format!("{RDF_NS}nil")));
+        };
+        let head = self.blank_node();
+        let first = self.object(first)?;
+        self.push(&head, &format!("{RDF_NS}first"), first);
+        let rest = self.list(rest)?;
+        self.push(&head, &format!("{RDF_NS}rest"), rest);
+        Ok(head)
+    }
+}
+
+/// Literal of a JSON-LD value, typed after its JSON type when not explicitly
+fn literal(value: &Value, datatype: Option<&str>, language: Option<&str>) -> Term {
+    if datatype == Some("@json") {
+        return Term::Literal {
+            value: value.to_string(),
+            datatype: format!("{RDF_NS}JSON"),
+            language: None,
+        };
+    }
+    let (lexical, natural) = match value {
+        Value::String(s) => (s.clone(), "string"),
+        Value::Bool(b) => (b.to_string(), "boolean"),
+        Value::Number(n) if n.is_f64() => {
+            let f = n.as_f64().unwrap_or_default();
+            (format!("{f:E}"), "double")
+        }
+        Value::Number(n) => (n.to_string(), "integer"),
+        other => (other.to_string(), "string"),
+    };
+    match (language, datatype) {
+        (Some(language), _) => Term::Literal {
+            value: lexical,
+            datatype: format!("{RDF_NS}langString"),
+            language: Some(language.to_lowercase()),
+        },
+        (None, Some(datatype)) => Term::Literal {
+            value: lexical,
+            datatype: datatype.to_string(),
+            language: None,
+        },
+        (None, None) => Term::Literal {
+            value: lexical,
+            datatype: format!("{XSD_NS}{natural}"),
+            language: None,
+        },
+    }
+}
+
+/// Prefixes declared by the document context, and those of RDF and XSD
+fn prefixes(metadata: &Metadata) -> Vec<(String, String)> {
+    let mut prefixes = vec![
+        ("rdf".to_string(), RDF_NS.to_string()),
+        ("xsd".to_string(), XSD_NS.to_string()),
+    ];
+    let contexts = match &metadata.context.0 {
+        Value::Array(contexts) => contexts.iter().collect(),
+        context => vec![context],
+    };
+    for (term, iri) in contexts.into_iter().filter_map(Value::as_object).flatten() {
+        if let Value::String(iri) = iri
+            && !term.starts_with('@')
+            && iri.ends_with(['/', '#'])
+            && !prefixes.iter().any(|(prefix, _)| prefix == term)
+        {
+            prefixes.push((term.clone(), iri.clone()));
+        }
+    }
+    prefixes
+}
+
+fn write_lines(triples: &[Triple], graph: Option<&str>) -> String {
+    let mut output = String::new();
+    for triple in triples {
+        let _ = write!(
+            output,
+            "{} <{}> {}",
+            ntriples_term(&triple.subject),
+            escape_iri(&triple.predicate),
+            ntriples_term(&triple.object)
+        );
+        if let Some(graph) = graph {
+            let _ = write!(output, " <{}>", escape_iri(graph));
+        }
+        output.push_str(" .\n");
+    }
+    output
+}
+
+fn ntriples_term(term: &Term) -> String {
+    match term {
+        Term::Iri(iri) => format!("<{}>", escape_iri(iri)),
+        Term::BlankNode(label) => format!("_:{label}"),
+        Term::Literal {
+            value,
+            datatype,
+            language,
+        } => match language {
+            Some(language) => format!("\"{}\"@{language}", escape_string(value)),
+            None if *datatype == format!("{XSD_NS}string") => {
+                format!("\"{}\"", escape_string(value))
+            }
+            None => format!("\"{}\"^^<{}>", escape_string(value), escape_iri(datatype)),
+        },
+    }
+}
+
+/// A predicate and its objects for one subject
+type PredicateObjects<'a> = (&'a str, Vec<&'a Term>);
+
+fn write_turtle(triples: &[Triple], prefixes: &[(String, String)]) -> String {
+    let mut used = vec![false; prefixes.len()];
+    let mut name = |iri: &str| {
+        let best = prefixes
+            .iter()
+            .enumerate()
+            .filter(|(_, (_, ns))| iri.starts_with(ns.as_str()))
+            .filter(|(_, (_, ns))| is_local_name(&iri[ns.len()..]))
+            .max_by_key(|(_, (_, ns))| ns.len());
+        match best {
+            Some((i, (prefix, ns))) => {
+                used[i] = true;
+                format!("{prefix}:{}", &iri[ns.len()..])
+            }
+            None => format!("<{}>", escape_iri(iri)),
+        }
+    };
+
+    // Statements grouped by subject then predicate, in document order
+    let mut subjects: Vec<(&Term, Vec<PredicateObjects>)> = Vec::new();
+    let mut index = HashMap::new();
+    for triple in triples {
+        let i = *index.entry(&triple.subject).or_insert_with(|| {
+            subjects.push((&triple.subject, Vec::new()));
+            subjects.len() - 1
+        });
+        let predicates = &mut subjects[i].1;
+        match predicates.iter_mut().find(|(p, _)| *p == triple.predicate) {
+            Some((_, objects)) => objects.push(&triple.object),
+            None => predicates.push((&triple.predicate, vec![&triple.object])),
+        }
+    }
+
+    let mut body = String::new();
+    for (subject, predicates) in subjects {
+        let mut term = |term: &Term| match term {
+            Term::Iri(iri) => name(iri),
+            Term::Literal {
+                value,
+                datatype,
+                language: None,
+            } if *datatype != format!("{XSD_NS}string") => {
+                format!("\"{}\"^^{}", escape_string(value), name(datatype))
+            }
+            term => ntriples_term(term),
+        };
+        let _ = write!(body, "\n{}", term(subject));
+        for (i, (predicate, objects)) in predicates.iter().enumerate() {
+            let separator = if i == 0 { " " } else { " ;\n    " };
+            let predicate = match *predicate == format!("{RDF_NS}type") {
+                true => "a".to_string(),
+                false => term(&Term::Iri(predicate.to_string())),
+            };
+            let objects = objects.iter().map(|o| term(o)).collect::<Vec<_>>();
+            let _ = write!(body, "{separator}{predicate} {}", objects.join(", "));
+        }
+        body.push_str(" .\n");
+    }
+
+    let mut output = String::new();
+    for ((prefix, ns), used) in prefixes.iter().zip(used) {
+        if used {
+            let _ = writeln!(output, "@prefix {prefix}: <{}> .", escape_iri(ns));
+        }
+    }
+    output + &body
+}
+
+/// Whether a string can follow a prefix in Turtle without escaping
+fn is_local_name(local: &str) -> bool {
+    let mut chars = local.chars();
+    chars
+        .next()
+        .is_some_and(|c| c.is_ascii_alphanumeric() || c == '_')
+        && chars.all(|c| c.is_ascii_alphanumeric() || matches!(c, '_' | '-' | '.'))
+        && !local.ends_with('.')
+}
+
+fn escape_string(value: &str) -> String {
+    let mut escaped = String::with_capacity(value.len());
+    for c in value.chars() {
+        match c {
+            '"' => escaped.push_str("\\\""),
+            '\\' => escaped.push_str("\\\\"),
+            '\n' => escaped.push_str("\\n"),
+            '\r' => escaped.push_str("\\r"),
+            '\t' => escaped.push_str("\\t"),
+            c => escaped.push(c),
+        }
+    }
+    escaped
+}
+
+fn escape_iri(iri: &str) -> String {
+    let mut escaped = String::with_capacity(iri.len());
+    for c in iri.chars() {
+        match c {
+            '<' | '>' | '"' | '{' | '}' | '|' | '^' | '`' | '\\' | '\0'..=' ' => {
+                let _ = write!(escaped, "\\u{:04X}", c as u32);
+            }
+            c => escaped.push(c),
+        }
+    }
+    escaped
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::croissant::core::LangString;
+
+    fn titanic() -> Metadata {
+        jsonld::parse_metadata(serde_json::json!({
+            "@context": {
+                "@language": "en",
+                "@vocab": "https://schema.org/",
+                "cr": "http://mlcommons.org/croissant/",
+            },
+            "@type": "sc:Dataset",
+            "name": "Titanic",
+            "description": "Passengers of the Titanic",
+            "url": "https://www.openml.org/d/40945",
+            "version": "1.0.0",
+            "distribution": [{
+                "@type": "cr:FileObject",
+                "@id": "passengers.csv",
+                "name": "passengers.csv",
+                "contentUrl": "data/titanic.csv",
+                "encodingFormat": "text/csv",
+            }],
+            "recordSet": [],
+        }))
+        .unwrap()
+    }
+
+    fn iri(iri: &str) -> Term {
+        Term::Iri(iri.to_string())
+    }
+
+    #[test]
+    fn nodes_are_named_against_the_dataset_url() {
+        let triples = to_triples(&titanic(), &RdfOptions::default()).unwrap();
+        let dataset = iri("https://www.openml.org/d/40945/");
+        assert!(triples.contains(&Triple {
+            subject: dataset.clone(),
+            predicate: format!("{RDF_NS}type"),
+            object: iri("https://schema.org/Dataset"),
+        }));
+        assert!(triples.contains(&Triple {
+            subject: dataset.clone(),
+            predicate: "https://schema.org/name".to_string(),
+            object: Term::Literal {
+                value: "Titanic".to_string(),
+                datatype: format!("{RDF_NS}langString"),
+                language: Some("en".to_string()),
+            },
+        }));
+        assert!(triples.contains(&Triple {
+            subject: dataset,
+            predicate: "https://schema.org/distribution".to_string(),
+            object: iri("https://www.openml.org/d/40945/passengers.csv"),
+        }));
+
+        let options = RdfOptions {
+            base: Some("https://example.org/titanic#".to_string()),
+            graph: None,
+        };
+        let triples = to_triples(&titanic(), &options).unwrap();
+        assert_eq!(triples[0].subject, iri("https://example.org/titanic#"));
+
+        let mut metadata = titanic();
+        metadata.url = None;
+        assert!(to_triples(&metadata, &RdfOptions::default()).is_err());
+    }
+
+    #[test]
+    fn language_tags_are_kept() {
+        let mut metadata = titanic();
+        metadata.description = LangString::new("Passagers").with_language("FR");
+        let triples = to_triples(&metadata, &RdfOptions::default()).unwrap();
+        let description = triples
+            .iter()
+            .find(|t| t.predicate == "https://schema.org/description")
+            .unwrap();
+        assert_eq!(
+            description.object,
+            Term::Literal {
+                value: "Passagers".to_string(),
+                datatype: format!("{RDF_NS}langString"),
+                language: Some("fr".to_string()),
+            }
+        );
+    }
+
+    #[test]
+    fn triples_are_written_in_every_format() {
+        let metadata = titanic();
+        let options = RdfOptions {
+            base: None,
+            graph: Some("https://example.org/graph".to_string()),
+        };
+        let ntriples = to_rdf(&metadata, RdfFormat::NTriples, &options).unwrap();
+        assert!(ntriples.lines().all(|line| line.ends_with(" .")));
+        assert!(ntriples.contains(
+            "<https://www.openml.org/d/40945/> <https://schema.org/name> \"Titanic\"@en .\n"
+        ));
+        let nquads = to_rdf(&metadata, RdfFormat::NQuads, &options).unwrap();
+        assert!(
+            nquads
+                .lines()
+                .all(|line| line.ends_with(" <https://example.org/graph> ."))
+        );
+        assert_eq!(nquads.lines().count(), ntriples.lines().count());
+
+        let turtle = to_rdf(&metadata, RdfFormat::Turtle, &options).unwrap();
+        assert!(turtle.contains("@prefix sc: <https://schema.org/> .\n"));
+        assert!(turtle.contains("\n<https://www.openml.org/d/40945/> a sc:Dataset ;\n"));
+        assert!(!turtle.contains("@prefix rdf:"));
+
+        assert_eq!("n-quads".parse::<RdfFormat>().unwrap(), RdfFormat::NQuads);
+        assert!("rdfxml".parse::<RdfFormat>().is_err());
+    }
+
+    #[test]
+    fn literals_and_iris_are_escaped() {
+        assert_eq!(escape_string("a \"b\"\n"), "a \\\"b\\\"\\n");
+        assert_eq!(escape_iri("https://x.org/a b"), "https://x.org/a\\u0020b");
+        assert!(is_local_name("passengers.csv"));
+        assert!(!is_local_name("data/"));
+        assert_eq!(
+            literal(&serde_json::json!(1.5), None, None),
+            Term::Literal {
+                value: "1.5E0".to_string(),
+                datatype: format!("{XSD_NS}double"),
+                language: None,
+            }
+        );
+    }
+}
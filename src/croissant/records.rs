@@ -0,0 +1,1448 @@
+//! Read records from the data described by a Croissant document
+//!
+//! [`Dataset::records`] opens the files referenced by a record set, applies
+//! each field's `source` (extraction, transforms and format) and yields typed
+//! [`Record`]s, like `mlcroissant.Dataset.records()`.
+use crate::croissant::archive::{ArchiveFormat, Location, list_members};
+use crate::croissant::compression::Compression;
+use crate::croissant::core::{
+    CsvDialect, DataType, Extract, Field, FileObject, FileProperty, FileSet, Id, Metadata,
+    RecordSet, Resource, SourceRef, Text, Transform, ValueFormat,
+};
+use crate::croissant::errors::{Error, Result};
+use crate::croissant::{jsonpath, migrate};
+use chrono::{DateTime, NaiveDate, NaiveDateTime};
+use parquet::file::reader::{FileReader, SerializedFileReader};
+use parquet::record::Field as ParquetField;
+use parquet::record::reader::RowIter;
+use regex::Regex;
+use serde::ser::{SerializeMap, SerializeSeq};
+use serde::{Serialize, Serializer};
+use std::cell::RefCell;
+use std::collections::HashMap;
+
+use std::io::{BufRead, BufReader, Lines, Read};
+use std::iter::Enumerate;
+use std::path::{Component, Path, PathBuf};
+
+/// Maximum depth of record sets reading from other record sets
+const MAX_DEPTH: usize = 16;
+
+/// A typed value of a record
+#[derive(Debug, Clone, PartialEq)]
+pub enum Value {
+    Null,
+    Boolean(bool),
+    Integer(i64),
+    Float(f64),
+    Text(String),
+    Date(NaiveDate),
+    DateTime(NaiveDateTime),
+    Bytes(Vec<u8>),
+    List(Vec<Value>),
+    Record(Record),
+}
+
+impl Serialize for Value {
+    fn serialize<S: Serializer>(&self, serializer: S) -> std::result::Result<S::Ok, S::Error> {
+        match self {
+            Value::Null => serializer.serialize_none(),
+            Value::Boolean(b) => serializer.serialize_bool(*b),
+            Value::Integer(i) => serializer.serialize_i64(*i),
+            Value::Float(f) => serializer.serialize_f64(*f),
+            Value::Text(s) => serializer.serialize_str(s),
+            Value::Date(d) => serializer.collect_str(d),
+            Value::DateTime(d) => serializer.collect_str(&d.format("%Y-%m-%dT%H:%M:%S%.f")),
+            Value::Bytes(b) => serializer.serialize_str(&hex::encode(b)),
+            Value::List(items) => {
+                let mut seq = serializer.serialize_seq(Some(items.len()))?;
+                for item in items {
+                    seq.serialize_element(item)?;
+                }
+                seq.end()
+            }
+            Value::Record(record) => record.serialize(serializer),
+        }
+    }
+}
+
+/// A record, mapping field ids to values in field order
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Record {
+    pub values: Vec<(Id, Value)>,
+}
+
+impl Record {
+    /// Value of the field with the given id
+    pub fn get(&self, field_id: &str) -> Option<&Value> {
+        self.values
+            .iter()
+            .find(|(id, _)| id.0 == field_id)
+            .map(|(_, v)| v)
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = &(Id, Value)> {
+        self.values.iter()
+    }
+}
+
+impl Serialize for Record {
+    fn serialize<S: Serializer>(&self, serializer: S) -> std::result::Result<S::Ok, S::Error> {
+        let mut map = serializer.serialize_map(Some(self.values.len()))?;
+        for (id, value) in &self.values {
+            map.serialize_entry(&id.0, value)?;
+        }
+        map.end()
+    }
+}
+
+/// A Croissant document bound to the local directory holding its data
+#[derive(Debug, Clone)]
+pub struct Dataset {
+    metadata: Metadata,
+    base_dir: PathBuf,
+}
+
+impl Dataset {
+    /// Bind a document to the directory `contentUrl`s are resolved against
+    pub fn new(metadata: Metadata, base_dir: impl Into<PathBuf>) -> Self {
+        Self {
+            metadata,
+            base_dir: base_dir.into(),
+        }
+    }
+
+    /// Load a metadata file, resolving data relative to its directory
+    pub fn from_file(path: &Path) -> Result<Self> {
+        let content = std::fs::read_to_string(path).map_err(|_| Error::file_not_found(path))?;
+        let metadata = migrate::load_metadata(serde_json::from_str(&content)?)?;
+        let base_dir = path
+            .parent()
+            .filter(|p| !p.as_os_str().is_empty())
+            .map(Path::to_path_buf)
+            .unwrap_or_else(|| PathBuf::from("."));
+        Ok(Self::new(metadata, base_dir))
+    }
+
+    pub fn metadata(&self) -> &Metadata {
+        &self.metadata
+    }
+
+    pub fn base_dir(&self) -> &Path {
+        &self.base_dir
+    }
+
+    /// Find a record set by `@id`
+    pub fn record_set(&self, id: &str) -> Result<&RecordSet> {
+        self.metadata
+            .record_sets
+            .iter()
+            .find(|rs| rs.id.0 == id)
+            .ok_or_else(|| Error::unknown_node(id))
+    }
+
+    /// Iterate over the records of a record set
+    pub fn records(&self, record_set_id: &str) -> Result<Records<'_>> {
+        self.records_at_depth(record_set_id, 0)
+    }
+
+    fn records_at_depth(&self, record_set_id: &str, depth: usize) -> Result<Records<'_>> {
+        if depth > MAX_DEPTH {
+            return Err(Error::unsupported(format!(
+                "record set '{record_set_id}' is nested more than {MAX_DEPTH} levels deep"
+            )));
+        }
+        let record_set = self.record_set(record_set_id)?;
+        let rows = self.rows(record_set, depth)?;
+        Ok(Records {
+            fields: &record_set.fields,
+            rows,
+            headers: Vec::new(),
+            regexes: RefCell::new(HashMap::new()),
+        })
+    }
+
+    fn file_object(&self, id: &Id) -> Result<&FileObject> {
+        self.metadata
+            .distribution
+            .iter()
+            .find_map(|d| match &d.resource {
+                Resource::FileObject(o) if o.id == *id => Some(o),
+                _ => None,
+            })
+            .ok_or_else(|| Error::unknown_node(id.0.to_string()))
+    }
+
+    fn file_set(&self, id: &Id) -> Result<&FileSet> {
+        self.metadata
+            .distribution
+            .iter()
+            .find_map(|d| match &d.resource {
+                Resource::FileSet(s) if s.id == *id => Some(s),
+                _ => None,
+            })
+            .ok_or_else(|| Error::unknown_node(id.0.to_string()))
+    }
+
+    /// Local path of a `contentUrl`
+    ///
+    /// Only paths relative to the base directory and staying under it are
+    /// read, so that a document cannot point at arbitrary local files.
+    pub(crate) fn resolve(&self, content_url: &str) -> Result<PathBuf> {
+        if content_url.contains("://") {
+            return Err(Error::unsupported(format!(
+                "remote content url '{content_url}'"
+            )));
+        }
+        let mut depth = 0usize;
+        for component in Path::new(content_url).components() {
+            depth = match component {
+                Component::Normal(_) => depth + 1,
+                Component::CurDir => depth,
+                Component::ParentDir if depth > 0 => depth - 1,
+                Component::ParentDir | Component::RootDir | Component::Prefix(_) => {
+                    return Err(Error::unsupported(format!(
+                        "content url '{content_url}' outside of the dataset directory"
+                    )));
+                }
+            };
+        }
+        Ok(self.base_dir.join(content_url))
+    }
+
+    /// Files of a file set, sorted by path
+    ///
+    /// File sets contained in a zip, tar or tar.gz archive list its members,
+    /// matched against `includes` as if the archive was extracted.
+    pub(crate) fn list_files(&self, file_set: &FileSet) -> Result<Vec<(Location, String)>> {
+        let mut roots = Vec::new();
+        let mut archives = Vec::new();
+        for id in &file_set.sources {
+            let container = self.file_object(id)?;
+            let path = self.resolve(&container.content_url.0)?;
+            if path.is_dir() {
+                roots.push(path);
+            } else if let Some(format) = ArchiveFormat::from_name(&container.content_url.0) {
+                archives.push((path, format));
+            } else {
+                return Err(Error::unsupported(format!(
+                    "file set '{}' contained in '{}', which is neither a directory nor an archive",
+                    file_set.id.0, id.0
+                )));
+            }
+        }
+        if file_set.sources.is_empty() {
+            roots.push(self.base_dir.clone());
+        }
+
+        let patterns = |patterns: &[Text]| {
+            patterns
+                .iter()
+                .map(|p| glob::Pattern::new(&p.0))
+                .collect::<std::result::Result<Vec<_>, _>>()
+                .map_err(|e| Error::invalid_format(format!("Invalid glob pattern: {e}")))
+        };
+        let includes = patterns(&file_set.includes)?;
+        let excludes = patterns(&file_set.excludes)?;
+        let options = glob::MatchOptions {
+            require_literal_separator: true,
+            ..Default::default()
+        };
+
+        let mut files = Vec::new();
+        for (archive, format) in archives {
+            for member in list_members(&archive, format)? {
+                if includes
+                    .iter()
+                    .any(|p| p.matches_with(&member.path, options))
+                    && !excludes
+                        .iter()
+                        .any(|p| p.matches_with(&member.path, options))
+                {
+                    let location = Location::Member {
+                        archive: archive.clone(),
+                        format,
+                        path: member.path.clone(),
+                    };
+                    files.push((location, member.path));
+                }
+            }
+        }
+        for root in roots {
+            for include in &file_set.includes {
+                let pattern = format!(
+                    "{}/{}",
+                    glob::Pattern::escape(&root.to_string_lossy()),
+                    include.0
+                );
+                let paths = glob::glob(&pattern)
+                    .map_err(|e| Error::invalid_format(format!("Invalid glob pattern: {e}")))?;
+                for path in paths {
+                    let path = path.map_err(|e| Error::Io(e.into()))?;
+                    if !path.is_file() {
+                        continue;
+                    }
+                    let relative = path
+                        .strip_prefix(&root)
+                        .unwrap_or(&path)
+                        .to_string_lossy()
+                        .to_string();
+                    if excludes.iter().any(|p| p.matches_with(&relative, options)) {
+                        continue;
+                    }
+                    let location = file_location(path, &file_set.encoding_format.0, None);
+                    files.push((location, relative));
+                }
+            }
+        }
+        files.sort();
+        files.dedup();
+        Ok(files)
+    }
+
+    fn rows<'a>(&'a self, record_set: &'a RecordSet, depth: usize) -> Result<Rows<'a>> {
+        if !record_set.data.is_empty() {
+            return Ok(Rows::Inline(record_set.data.iter()));
+        }
+        let source = common_source(record_set)?;
+        let extracts = all_fields(&record_set.fields)
+            .filter_map(|f| f.source.as_ref()?.extract.as_ref())
+            .collect::<Vec<_>>();
+        let by_file = extracts
+            .iter()
+            .any(|e| matches!(e, Extract::FileProperty { .. }));
+        let by_line = extracts.iter().any(|e| {
+            matches!(
+                e,
+                Extract::FileProperty {
+                    property: FileProperty::Lines | FileProperty::LineNumbers
+                }
+            )
+        });
+
+        let (files, encoding_format, dialect) = match source {
+            SourceRef::FileObject { file_object } => {
+                let object = self.file_object(&file_object.id)?;
+                let path = self.resolve(&object.content_url.0)?;
+                let name = object.content_url.0.to_string();
+                let location = file_location(
+                    path,
+                    &object.encoding_format.0,
+                    object.compress_format.as_ref(),
+                );
+                let encoding_format = object.encoding_format.0.as_ref();
+                (
+                    vec![(location, name)],
+                    encoding_format,
+                    object.dialect.as_ref(),
+                )
+            }
+            SourceRef::FileSet { file_set } => {
+                let set = self.file_set(&file_set.id)?;
+                let encoding_format = set.encoding_format.0.as_ref();
+                (self.list_files(set)?, encoding_format, set.dialect.as_ref())
+            }
+            SourceRef::RecordSet { record_set: parent } => {
+                if parent.id == record_set.id {
+                    return Err(Error::unsupported(format!(
+                        "record set '{}' reads from itself",
+                        record_set.id.0
+                    )));
+                }
+                let records = self.records_at_depth(&parent.id.0, depth + 1)?;
+                return Ok(Rows::Records(Box::new(records)));
+            }
+        };
+
+        if by_file {
+            return Ok(Rows::Files {
+                files: files.into_iter(),
+                by_line,
+                current: None,
+            });
+        }
+
+        let format = Format::from_encoding(encoding_format)?;
+        if format == Format::Json {
+            return Ok(Rows::Json {
+                files: files.into_iter(),
+                record_set,
+                current: Vec::new().into_iter(),
+            });
+        }
+        Ok(Rows::Stream {
+            format,
+            dialect: Box::new(dialect.cloned().unwrap_or_default()),
+            files: files.into_iter(),
+            current: None,
+        })
+    }
+}
+
+/// Location of a file on disk, read decompressed when it is compressed
+///
+/// Files are compressed when `compress_format` says so, or when their
+/// extension is that of a compression format while `encoding_format` is a
+/// format records are read from (`data.csv.gz` described as `text/csv`).
+fn file_location(path: PathBuf, encoding_format: &str, compress_format: Option<&Text>) -> Location {
+    let compression = match compress_format {
+        Some(format) => Compression::from_encoding_format(&format.0),
+        None if Format::from_encoding(encoding_format).is_ok() => Compression::from_path(&path),
+        None => None,
+    };
+    match compression {
+        Some(compression) => Location::Compressed { path, compression },
+        None => Location::File(path),
+    }
+}
+
+/// Every field and sub-field, depth first
+fn all_fields(fields: &[Field]) -> Box<dyn Iterator<Item = &Field> + '_> {
+    Box::new(fields.iter().flat_map(|f| {
+        std::iter::once(f).chain(all_fields(f.sub_fields.as_deref().unwrap_or_default()))
+    }))
+}
+
+/// How values of a field are extracted from its source
+fn field_extract(field: &Field) -> Option<&Extract> {
+    field.source.as_ref()?.extract.as_ref()
+}
+
+/// The single node all fields of a record set read from
+fn common_source(record_set: &RecordSet) -> Result<&SourceRef> {
+    let mut sources = all_fields(&record_set.fields)
+        .filter(|f| f.sub_fields.as_ref().is_none_or(|s| s.is_empty()))
+        .map(|f| {
+            f.source
+                .as_ref()
+                .map(|s| &s.source)
+                .ok_or_else(|| Error::missing_field(format!("source of field '{}'", f.id.0)))
+        });
+    let first = sources.next().ok_or_else(|| {
+        Error::invalid_format(format!("Record set '{}' has no field", record_set.id.0))
+    })??;
+    for source in sources {
+        if source? != first {
+            return Err(Error::unsupported(format!(
+                "record set '{}' joins several sources",
+                record_set.id.0
+            )));
+        }
+    }
+    Ok(first)
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Format {
+    Csv,
+    Json,
+    JsonLines,
+    Parquet,
+}
+
+impl Format {
+    fn from_encoding(encoding_format: &str) -> Result<Self> {
+        match encoding_format {
+            "text/csv" => Ok(Self::Csv),
+            "application/json" => Ok(Self::Json),
+            "application/jsonlines" | "application/x-jsonlines" | "application/jsonl" => {
+                Ok(Self::JsonLines)
+            }
+            "application/x-parquet" | "application/vnd.apache.parquet" => Ok(Self::Parquet),
+            other => Err(Error::unsupported(format!("encoding format '{other}'"))),
+        }
+    }
+}
+
+enum Reader {
+    Csv(csv::StringRecordsIntoIter<Box<dyn Read>>),
+    Lines(Lines<BufReader<Box<dyn Read>>>),
+    Parquet(RowIter<'static>),
+}
+
+/// Lines of a file, numbered from 0
+type FileLines = Enumerate<Lines<BufReader<Box<dyn Read>>>>;
+
+/// Rows of a record set, read lazily
+enum Rows<'a> {
+    /// Rows of CSV, JSON Lines or Parquet files, one file after the other
+    Stream {
+        format: Format,
+        /// Dialect of CSV files
+        dialect: Box<CsvDialect>,
+        files: std::vec::IntoIter<(Location, String)>,
+        current: Option<Reader>,
+    },
+    /// One row per file, or per line of each file
+    Files {
+        files: std::vec::IntoIter<(Location, String)>,
+        by_line: bool,
+        current: Option<(Location, String, FileLines)>,
+    },
+    /// Rows of whole JSON documents, parsed one document at a time
+    Json {
+        files: std::vec::IntoIter<(Location, String)>,
+        record_set: &'a RecordSet,
+        current: std::vec::IntoIter<Row>,
+    },
+    /// Records of the record set read from
+    Records(Box<Records<'a>>),
+    /// Inline `data` of the record set
+    Inline(std::slice::Iter<'a, serde_json::Map<String, serde_json::Value>>),
+}
+
+enum Row {
+    Csv(csv::StringRecord),
+    Json(serde_json::Value),
+    /// Element of a root array, read by `$[*]` expressions
+    Element(serde_json::Value),
+    /// Values of each field, keyed by field id
+    Columns(HashMap<String, serde_json::Value>),
+    File {
+        location: Location,
+        relative: String,
+        line: Option<(usize, String)>,
+    },
+    Record(Record),
+}
+
+/// Evaluate the `jsonPath` of every field against a whole JSON document
+fn json_rows(location: &Location, record_set: &RecordSet) -> Result<Vec<Row>> {
+    let document: serde_json::Value = serde_json::from_reader(location.open()?)?;
+
+    // Arrays of records are read element by element so that missing or
+    // repeated values stay within their record
+    let per_element = all_fields(&record_set.fields).all(|field| match field_extract(field) {
+        Some(Extract::JsonPath { expr }) => jsonpath::element_path(&expr.0).is_some(),
+        _ => true,
+    });
+    if per_element && let serde_json::Value::Array(items) = document {
+        return Ok(items.into_iter().map(Row::Element).collect());
+    }
+
+    let mut columns = Vec::new();
+    for field in all_fields(&record_set.fields) {
+        if let Some(Extract::JsonPath { expr }) = field_extract(field) {
+            let values = jsonpath::select(&document, &expr.0)?;
+            columns.push((field.id.0.to_string(), values));
+        }
+    }
+    let len = columns.iter().map(|(_, v)| v.len()).max().unwrap_or(0);
+    for (id, values) in &columns {
+        if values.len() != len && values.len() != 1 {
+            return Err(Error::invalid_format(format!(
+                "Field '{id}' yields {} values where {len} were expected",
+                values.len()
+            )));
+        }
+    }
+    Ok((0..len)
+        .map(|i| {
+            let row = columns
+                .iter()
+                .map(|(id, values)| {
+                    let value = values.get(i).or(values.first()).copied();
+                    (id.clone(), value.cloned().unwrap_or_default())
+                })
+                .collect();
+            Row::Columns(row)
+        })
+        .collect())
+}
+
+fn open_reader(
+    format: Format,
+    dialect: &CsvDialect,
+    location: &Location,
+) -> Result<(Reader, Vec<String>)> {
+    let file = location.open()?;
+    match format {
+        Format::Csv => {
+            let (reader, headers) = dialect.csv_reader(file)?;
+            Ok((Reader::Csv(reader.into_records()), headers))
+        }
+        Format::JsonLines => Ok((Reader::Lines(BufReader::new(file).lines()), Vec::new())),
+        Format::Parquet => {
+            let reader = match location {
+                Location::File(path) => {
+                    let file =
+                        std::fs::File::open(path).map_err(|_| Error::file_not_found(path))?;
+                    Box::new(SerializedFileReader::new(file)?) as Box<dyn FileReader>
+                }
+                location => Box::new(SerializedFileReader::new(bytes::Bytes::from(
+                    location.read()?,
+                ))?),
+            };
+            Ok((Reader::Parquet(RowIter::from_file_into(reader)), Vec::new()))
+        }
+        Format::Json => Err(Error::unsupported("streaming whole JSON documents")),
+    }
+}
+
+fn next_row(rows: &mut Rows, headers: &mut Vec<String>) -> Option<Result<Row>> {
+    match rows {
+        Rows::Inline(records) => records
+            .next()
+            .map(|record| Ok(Row::Columns(record.clone().into_iter().collect()))),
+        Rows::Records(records) => records.next().map(|r| r.map(Row::Record)),
+        Rows::Json {
+            files,
+            record_set,
+            current,
+        } => loop {
+            if let Some(row) = current.next() {
+                return Some(Ok(row));
+            }
+            let (location, _) = files.next()?;
+            match json_rows(&location, record_set) {
+                Ok(rows) => *current = rows.into_iter(),
+                Err(e) => return Some(Err(e)),
+            }
+        },
+        Rows::Files {
+            files,
+            by_line,
+            current,
+        } => loop {
+            if let Some((location, relative, lines)) = current {
+                match lines.next() {
+                    Some((n, Ok(line))) => {
+                        return Some(Ok(Row::File {
+                            location: location.clone(),
+                            relative: relative.clone(),
+                            line: Some((n + 1, line)),
+                        }));
+                    }
+                    Some((_, Err(e))) => return Some(Err(e.into())),
+                    None => *current = None,
+                }
+                continue;
+            }
+            let (location, relative) = files.next()?;
+            if !*by_line {
+                return Some(Ok(Row::File {
+                    location,
+                    relative,
+                    line: None,
+                }));
+            }
+            match location.open() {
+                Ok(file) => {
+                    let lines = BufReader::new(file).lines().enumerate();
+                    *current = Some((location, relative, lines));
+                }
+                Err(e) => return Some(Err(e)),
+            }
+        },
+        Rows::Stream {
+            format,
+            dialect,
+            files,
+            current,
+        } => loop {
+            match current {
+                Some(Reader::Csv(records)) => match records.next() {
+                    Some(record) => return Some(record.map(Row::Csv).map_err(Error::from)),
+                    None => *current = None,
+                },
+                Some(Reader::Lines(lines)) => match lines.next() {
+                    Some(Ok(line)) if line.trim().is_empty() => continue,
+                    Some(Ok(line)) => {
+                        return Some(
+                            serde_json::from_str(&line)
+                                .map(Row::Json)
+                                .map_err(Error::from),
+                        );
+                    }
+                    Some(Err(e)) => return Some(Err(e.into())),
+                    None => *current = None,
+                },
+                Some(Reader::Parquet(rows)) => match rows.next() {
+                    Some(row) => {
+                        return Some(
+                            row.map(|row| Row::Json(parquet_value(&ParquetField::Group(row))))
+                                .map_err(Error::from),
+                        );
+                    }
+                    None => *current = None,
+                },
+                None => {
+                    let (location, _) = files.next()?;
+                    match open_reader(*format, dialect, &location) {
+                        Ok((reader, file_headers)) => {
+                            *headers = file_headers;
+                            *current = Some(reader);
+                        }
+                        Err(e) => return Some(Err(e)),
+                    }
+                }
+            }
+        },
+    }
+}
+
+/// Iterator over the records of a record set
+pub struct Records<'a> {
+    fields: &'a [Field],
+    rows: Rows<'a>,
+    headers: Vec<String>,
+    regexes: RefCell<HashMap<String, Regex>>,
+}
+
+impl Iterator for Records<'_> {
+    type Item = Result<Record>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let row = match next_row(&mut self.rows, &mut self.headers)? {
+            Ok(row) => row,
+            Err(e) => return Some(Err(e)),
+        };
+        Some(self.record(self.fields, &row))
+    }
+}
+
+impl<'a> Records<'a> {
+    /// Fields of the record set, in record order
+    pub(crate) fn fields(&self) -> &'a [Field] {
+        self.fields
+    }
+
+    /// Values of the next record, each field converted on its own so that a
+    /// value not matching its data type does not hide the others
+    pub(crate) fn next_values(&mut self) -> Option<Result<Vec<Result<Value>>>> {
+        let row = match next_row(&mut self.rows, &mut self.headers)? {
+            Ok(row) => row,
+            Err(e) => return Some(Err(e)),
+        };
+        Some(Ok(self
+            .fields
+            .iter()
+            .map(|field| self.value(field, &row))
+            .collect()))
+    }
+}
+
+/// A value before it is converted to the field's data type
+enum Raw {
+    Json(serde_json::Value),
+    Bytes(Vec<u8>),
+    Typed(Value),
+}
+
+impl Records<'_> {
+    fn record(&self, fields: &[Field], row: &Row) -> Result<Record> {
+        let mut record = Record::default();
+        for field in fields {
+            record
+                .values
+                .push((field.id.clone(), self.value(field, row)?));
+        }
+        Ok(record)
+    }
+
+    fn value(&self, field: &Field, row: &Row) -> Result<Value> {
+        if let Some(sub_fields) = &field.sub_fields
+            && !sub_fields.is_empty()
+        {
+            // Inline records nest the values of sub-fields under their parent
+            if field.source.is_none()
+                && let Row::Columns(columns) = row
+                && let Some(serde_json::Value::Object(nested)) = columns.get(field.id.0.as_ref())
+            {
+                let nested = Row::Columns(nested.clone().into_iter().collect());
+                return Ok(Value::Record(self.record(sub_fields, &nested)?));
+            }
+            return Ok(Value::Record(self.record(sub_fields, row)?));
+        }
+        let mut raw = self.extract(field, row)?;
+        let transforms = field
+            .source
+            .iter()
+            .flat_map(|s| s.transform.iter().flatten());
+        for transform in transforms {
+            raw = self.transform(field, transform, raw)?;
+        }
+        convert(raw, field)
+    }
+
+    fn extract(&self, field: &Field, row: &Row) -> Result<Raw> {
+        match (field_extract(field), row) {
+            (Some(Extract::Column { name }), Row::Csv(record)) => {
+                let index = self
+                    .headers
+                    .iter()
+                    .position(|h| *h == name.0)
+                    .ok_or_else(|| Error::missing_field(format!("column '{}'", name.0)))?;
+                let value = record.get(index).unwrap_or_default().to_string();
+                Ok(Raw::Json(serde_json::Value::String(value)))
+            }
+            (Some(Extract::Column { name }), Row::Json(document)) => {
+                Ok(Raw::Json(column_value(document, &name.0)))
+            }
+            (Some(Extract::Column { name }), Row::Record(record)) => {
+                Ok(Raw::Typed(record_value(record, &name.0)))
+            }
+            (None, Row::Record(record)) => Ok(Raw::Typed(record_value(record, &field.name.0))),
+            (Some(Extract::JsonPath { expr }), Row::Json(document)) => {
+                json_path_value(document, &expr.0)
+            }
+            (Some(Extract::Column { name }), Row::Element(element)) => Ok(Raw::Json(
+                element.get(name.0.as_ref()).cloned().unwrap_or_default(),
+            )),
+            (Some(Extract::JsonPath { expr }), Row::Element(element)) => {
+                let relative = jsonpath::element_path(&expr.0).ok_or_else(|| {
+                    Error::unsupported(format!("JSONPath '{}' outside of the record", expr.0))
+                })?;
+                json_path_value(element, &relative)
+            }
+            (Some(Extract::JsonPath { .. }), Row::Columns(columns)) => Ok(Raw::Json(
+                columns
+                    .get(field.id.0.as_ref())
+                    .cloned()
+                    .unwrap_or_default(),
+            )),
+            (
+                Some(Extract::FileProperty { property }),
+                Row::File {
+                    location,
+                    relative,
+                    line,
+                },
+            ) => match property {
+                FileProperty::FullPath => Ok(Raw::Json(relative.clone().into())),
+                FileProperty::FileName => Ok(Raw::Json(location.file_name().into())),
+                FileProperty::Content => Ok(Raw::Bytes(location.read()?)),
+                FileProperty::Lines => Ok(Raw::Json(
+                    line.as_ref()
+                        .map(|(_, l)| l.clone().into())
+                        .unwrap_or_default(),
+                )),
+                FileProperty::LineNumbers => Ok(Raw::Typed(
+                    line.as_ref()
+                        .map(|(n, _)| Value::Integer(*n as i64))
+                        .unwrap_or(Value::Null),
+                )),
+            },
+            (None, Row::Columns(columns)) if field.source.is_none() => Ok(Raw::Json(
+                columns
+                    .get(field.id.0.as_ref())
+                    .cloned()
+                    .unwrap_or_default(),
+            )),
+            (None, _) => Err(Error::missing_field(format!(
+                "extract of field '{}'",
+                field.id.0
+            ))),
+            (Some(extract), _) => Err(Error::unsupported(format!(
+                "extracting {extract:?} for field '{}' from this source",
+                field.id.0
+            ))),
+        }
+    }
+
+    fn transform(&self, field: &Field, transform: &Transform, raw: Raw) -> Result<Raw> {
+        let value = match raw {
+            Raw::Json(value) => value,
+            Raw::Typed(value) => serde_json::to_value(value)?,
+            Raw::Bytes(_) => {
+                return Err(Error::unsupported(format!(
+                    "transforming the binary content of field '{}'",
+                    field.id.0
+                )));
+            }
+        };
+        let text = match &value {
+            serde_json::Value::String(s) => s.clone(),
+            serde_json::Value::Null => return Ok(Raw::Json(value)),
+            other => other.to_string(),
+        };
+        match transform {
+            Transform::Regex { pattern } => {
+                let mut regexes = self.regexes.borrow_mut();
+                let regex = match regexes.get(pattern.0.as_ref()) {
+                    Some(regex) => regex,
+                    None => {
+                        let regex = Regex::new(&pattern.0).map_err(|e| {
+                            Error::invalid_format(format!("Invalid regex '{}': {e}", pattern.0))
+                        })?;
+                        regexes.entry(pattern.0.to_string()).or_insert(regex)
+                    }
+                };
+                let matched = regex
+                    .captures(&text)
+                    .and_then(|c| c.get(1).or_else(|| c.get(0)))
+                    .map(|m| serde_json::Value::String(m.as_str().to_string()))
+                    .unwrap_or_default();
+                Ok(Raw::Json(matched))
+            }
+            Transform::Delimiter { char } => Ok(Raw::Json(serde_json::Value::Array(
+                text.split(*char)
+                    .map(|s| serde_json::Value::String(s.to_string()))
+                    .collect(),
+            ))),
+            Transform::JsonQuery { query } => {
+                let document = match value {
+                    serde_json::Value::String(s) => serde_json::from_str(&s)?,
+                    other => other,
+                };
+                json_path_value(&document, &query.0)
+            }
+        }
+    }
+}
+
+/// Values matched by a JSONPath, as a list when it may match several
+fn json_path_value(document: &serde_json::Value, expr: &str) -> Result<Raw> {
+    let values = jsonpath::select(document, expr)?;
+    if jsonpath::is_multi(expr)? {
+        Ok(Raw::Json(serde_json::Value::Array(
+            values.into_iter().cloned().collect(),
+        )))
+    } else {
+        Ok(Raw::Json(
+            values.first().copied().cloned().unwrap_or_default(),
+        ))
+    }
+}
+
+/// JSON value of a Parquet value: groups become objects, lists arrays and maps
+/// arrays of `key`/`value` objects, as their columns are described (see
+/// [`infer_parquet_columns`](crate::croissant::infer::infer_parquet_columns))
+fn parquet_value(field: &ParquetField) -> serde_json::Value {
+    use serde_json::Value as Json;
+    match field {
+        ParquetField::Null => Json::Null,
+        ParquetField::Bool(b) => Json::Bool(*b),
+        ParquetField::Byte(n) => (*n).into(),
+        ParquetField::Short(n) => (*n).into(),
+        ParquetField::Int(n) => (*n).into(),
+        ParquetField::Long(n) => (*n).into(),
+        ParquetField::UByte(n) => (*n).into(),
+        ParquetField::UShort(n) => (*n).into(),
+        ParquetField::UInt(n) => (*n).into(),
+        ParquetField::ULong(n) => (*n).into(),
+        ParquetField::Float16(n) => f64::from(*n).into(),
+        ParquetField::Float(n) => f64::from(*n).into(),
+        ParquetField::Double(n) => (*n).into(),
+        ParquetField::Decimal(decimal) => decimal_string(decimal.data(), decimal.scale())
+            .map(Json::String)
+            .unwrap_or_default(),
+        ParquetField::Str(s) => Json::String(s.clone()),
+        ParquetField::Bytes(bytes) => Json::String(String::from_utf8_lossy(bytes.data()).into()),
+        ParquetField::Date(days) => DateTime::from_timestamp(i64::from(*days) * 86_400, 0)
+            .map(|d| Json::String(d.date_naive().to_string()))
+            .unwrap_or_default(),
+        ParquetField::TimestampMillis(millis) => DateTime::from_timestamp_millis(*millis)
+            .map(|d| Json::String(d.naive_utc().format("%Y-%m-%dT%H:%M:%S%.f").to_string()))
+            .unwrap_or_default(),
+        ParquetField::TimestampMicros(micros) => DateTime::from_timestamp_micros(*micros)
+            .map(|d| Json::String(d.naive_utc().format("%Y-%m-%dT%H:%M:%S%.f").to_string()))
+            .unwrap_or_default(),
+        ParquetField::Group(row) => Json::Object(
+            row.get_column_iter()
+                .map(|(name, value)| (name.clone(), parquet_value(value)))
+                .collect(),
+        ),
+        ParquetField::ListInternal(list) => {
+            Json::Array(list.elements().iter().map(parquet_value).collect())
+        }
+        ParquetField::MapInternal(map) => Json::Array(
+            map.entries()
+                .iter()
+                .map(|(key, value)| {
+                    serde_json::json!({"key": parquet_value(key), "value": parquet_value(value)})
+                })
+                .collect(),
+        ),
+    }
+}
+
+/// Decimal number written from its unscaled big-endian two's complement bytes
+fn decimal_string(bytes: &[u8], scale: i32) -> Option<String> {
+    if bytes.is_empty() || bytes.len() > 16 || scale < 0 {
+        return None;
+    }
+    let fill = if bytes[0] & 0x80 != 0 { 0xff } else { 0 };
+    let mut buffer = [fill; 16];
+    buffer[16 - bytes.len()..].copy_from_slice(bytes);
+    let unscaled = i128::from_be_bytes(buffer);
+    let scale = scale as usize;
+    let digits = format!("{:0>width$}", unscaled.unsigned_abs(), width = scale + 1);
+    let (integer, fraction) = digits.split_at(digits.len() - scale);
+    let sign = if unscaled < 0 { "-" } else { "" };
+    Some(match fraction {
+        "" => format!("{sign}{integer}"),
+        fraction => format!("{sign}{integer}.{fraction}"),
+    })
+}
+
+/// Value of a column by its dotted path (`point.x`), lists being traversed
+/// element by element
+fn column_value(value: &serde_json::Value, path: &str) -> serde_json::Value {
+    use serde_json::Value as Json;
+    match value {
+        Json::Object(map) => {
+            if let Some(value) = map.get(path) {
+                return value.clone();
+            }
+            path.match_indices('.')
+                .find_map(|(i, _)| Some(column_value(map.get(&path[..i])?, &path[i + 1..])))
+                .unwrap_or_default()
+        }
+        Json::Array(items) => Json::Array(items.iter().map(|i| column_value(i, path)).collect()),
+        _ => Json::Null,
+    }
+}
+
+/// Value of a field of another record set, by id or by name
+fn record_value(record: &Record, name: &str) -> Value {
+    record
+        .get(name)
+        .or_else(|| {
+            record
+                .values
+                .iter()
+                .find(|(id, _)| id.0.rsplit('/').next() == Some(name))
+                .map(|(_, v)| v)
+        })
+        .cloned()
+        .unwrap_or(Value::Null)
+}
+
+/// First data type of a field that values can be converted to
+pub(crate) fn primitive_type(field: &Field) -> &DataType {
+    field
+        .data_types
+        .iter()
+        .find(|t| {
+            matches!(
+                t,
+                DataType::Boolean
+                    | DataType::Integer
+                    | DataType::Float
+                    | DataType::Text
+                    | DataType::Date
+                    | DataType::DateTime
+                    | DataType::Url
+                    | DataType::ImageObject
+            )
+        })
+        .unwrap_or(&DataType::Text)
+}
+
+fn convert(raw: Raw, field: &Field) -> Result<Value> {
+    let data_type = primitive_type(field);
+    let format = field.source.as_ref().and_then(|s| s.format.as_ref());
+    let pattern = match (format, data_type) {
+        (Some(ValueFormat::Date { pattern }), DataType::Date | DataType::DateTime)
+        | (Some(ValueFormat::Number { pattern }), DataType::Integer | DataType::Float) => {
+            Some(pattern.0.as_ref())
+        }
+        _ => None,
+    };
+    match raw {
+        Raw::Typed(value) => Ok(value),
+        Raw::Bytes(bytes) => match data_type {
+            DataType::Text => Ok(Value::Text(String::from_utf8_lossy(&bytes).to_string())),
+            _ => Ok(Value::Bytes(bytes)),
+        },
+        Raw::Json(value) => {
+            let nulls = field.source.as_ref().map(|s| s.nulls.as_slice());
+            convert_json(
+                without_nulls(value, nulls.unwrap_or_default()),
+                data_type,
+                pattern,
+            )
+        }
+    }
+}
+
+/// Replace the strings standing for a missing value (`csvw:null`) by nulls,
+/// in lists too
+fn without_nulls(value: serde_json::Value, nulls: &[String]) -> serde_json::Value {
+    use serde_json::Value as Json;
+    match value {
+        Json::String(s) if nulls.iter().any(|null| null == s.trim()) => Json::Null,
+        Json::Array(items) if !nulls.is_empty() => Json::Array(
+            items
+                .into_iter()
+                .map(|item| without_nulls(item, nulls))
+                .collect(),
+        ),
+        value => value,
+    }
+}
+
+/// Convert a raw value to the given data type
+///
+/// Booleans are only read as `sc:Boolean` (or text), and numbers neither as
+/// booleans nor as dates. Inline data is checked with the same conversion,
+/// see [`check_inline_data`](crate::croissant::data::check_inline_data).
+pub(crate) fn convert_json(
+    value: serde_json::Value,
+    data_type: &DataType,
+    pattern: Option<&str>,
+) -> Result<Value> {
+    use serde_json::Value as Json;
+    match value {
+        Json::Null => Ok(Value::Null),
+        Json::Array(items) => Ok(Value::List(
+            items
+                .into_iter()
+                .map(|v| convert_json(v, data_type, pattern))
+                .collect::<Result<_>>()?,
+        )),
+        Json::Object(_) => Ok(Value::Text(value.to_string())),
+        Json::Bool(b) => match data_type {
+            DataType::Text => Ok(Value::Text(b.to_string())),
+            DataType::Integer
+            | DataType::Float
+            | DataType::Date
+            | DataType::DateTime
+            | DataType::Url => Err(Error::invalid_data_type(
+                b.to_string(),
+                data_type.to_string(),
+            )),
+            _ => Ok(Value::Boolean(b)),
+        },
+        Json::Number(n) => match data_type {
+            DataType::Integer => n
+                .as_i64()
+                .map(Value::Integer)
+                .ok_or_else(|| Error::invalid_data_type(n.to_string(), data_type.to_string())),
+            DataType::Float => Ok(Value::Float(n.as_f64().unwrap_or(f64::NAN))),
+            DataType::Text | DataType::Url => Ok(Value::Text(n.to_string())),
+            DataType::Boolean | DataType::Date | DataType::DateTime => Err(
+                Error::invalid_data_type(n.to_string(), data_type.to_string()),
+            ),
+            _ => Ok(n
+                .as_i64()
+                .map(Value::Integer)
+                .unwrap_or_else(|| Value::Float(n.as_f64().unwrap_or(f64::NAN)))),
+        },
+        Json::String(s) => parse_str(s, data_type, pattern),
+    }
+}
+
+fn parse_str(value: String, data_type: &DataType, pattern: Option<&str>) -> Result<Value> {
+    let trimmed = value.trim();
+    let invalid = || Error::invalid_data_type(value.as_str(), data_type.to_string());
+    if trimmed.is_empty() && !matches!(data_type, DataType::Text) {
+        return Ok(Value::Null);
+    }
+    let number = match (data_type, pattern) {
+        (DataType::Integer | DataType::Float, Some(pattern)) => normalize_number(trimmed, pattern),
+        _ => trimmed.to_string(),
+    };
+    match data_type {
+        DataType::Integer => number
+            .parse::<i64>()
+            .map(Value::Integer)
+            .map_err(|_| invalid()),
+        DataType::Float => number
+            .parse::<f64>()
+            .map(Value::Float)
+            .map_err(|_| invalid()),
+        DataType::Boolean => match trimmed.to_lowercase().as_str() {
+            "true" | "1" | "yes" | "y" | "t" => Ok(Value::Boolean(true)),
+            "false" | "0" | "no" | "n" | "f" => Ok(Value::Boolean(false)),
+            _ => Err(invalid()),
+        },
+        DataType::Date => match pattern {
+            Some(pattern) => NaiveDate::parse_from_str(trimmed, pattern),
+            None => NaiveDate::parse_from_str(trimmed, "%Y-%m-%d")
+                .or_else(|_| parse_datetime(trimmed).map(|d| d.date())),
+        }
+        .map(Value::Date)
+        .map_err(|_| invalid()),
+        DataType::DateTime => match pattern {
+            Some(pattern) => NaiveDateTime::parse_from_str(trimmed, pattern),
+            None => parse_datetime(trimmed),
+        }
+        .map(Value::DateTime)
+        .map_err(|_| invalid()),
+        _ => Ok(Value::Text(value)),
+    }
+}
+
+/// Rewrite a number written after a decimal pattern such as `#,##0.00` or
+/// `#.##0,00` with a `.` decimal separator and no grouping
+fn normalize_number(value: &str, pattern: &str) -> String {
+    let (grouping, decimal) = match (pattern.rfind('.'), pattern.rfind(',')) {
+        (Some(dot), Some(comma)) if comma > dot => (Some('.'), ','),
+        (Some(_), Some(_)) => (Some(','), '.'),
+        (None, Some(comma)) => {
+            let digits = pattern[comma + 1..]
+                .chars()
+                .take_while(|c| matches!(c, '#' | '0'))
+                .count();
+            if digits == 3 {
+                (Some(','), '.')
+            } else {
+                (None, ',')
+            }
+        }
+        _ => (None, '.'),
+    };
+    value
+        .chars()
+        .filter(|c| Some(*c) != grouping)
+        .map(|c| if c == decimal { '.' } else { c })
+        .collect()
+}
+
+fn parse_datetime(value: &str) -> std::result::Result<NaiveDateTime, chrono::ParseError> {
+    DateTime::parse_from_rfc3339(value)
+        .map(|d| d.naive_utc())
+        .or_else(|_| NaiveDateTime::parse_from_str(value, "%Y-%m-%d %H:%M:%S"))
+        .or_else(|_| NaiveDateTime::parse_from_str(value, "%Y-%m-%dT%H:%M:%S%.f"))
+        .or_else(|e| {
+            NaiveDate::parse_from_str(value, "%Y-%m-%d")
+                .map(|d| d.and_time(chrono::NaiveTime::MIN))
+                .map_err(|_| e)
+        })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::croissant::generate::generate_metadata_from_parquet;
+    use parquet::data_type::{ByteArray, ByteArrayType, DoubleType, Int64Type};
+    use parquet::file::properties::WriterProperties;
+    use parquet::file::writer::SerializedFileWriter;
+    use parquet::schema::parser::parse_message_type;
+    use std::sync::Arc;
+
+    fn write_parquet(path: &Path) {
+        let schema = "message points {
+            required int64 id;
+            optional binary name (STRING);
+            required group point { required double x; }
+        }";
+        let schema = Arc::new(parse_message_type(schema).unwrap());
+        let file = std::fs::File::create(path).unwrap();
+        let properties = Arc::new(WriterProperties::builder().build());
+        let mut writer = SerializedFileWriter::new(file, schema, properties).unwrap();
+        let mut row_group = writer.next_row_group().unwrap();
+        let mut column = row_group.next_column().unwrap().unwrap();
+        column
+            .typed::<Int64Type>()
+            .write_batch(&[1, 2], None, None)
+            .unwrap();
+        column.close().unwrap();
+        let mut column = row_group.next_column().unwrap().unwrap();
+        column
+            .typed::<ByteArrayType>()
+            .write_batch(&[ByteArray::from("a")], Some(&[1, 0]), None)
+            .unwrap();
+        column.close().unwrap();
+        let mut column = row_group.next_column().unwrap().unwrap();
+        column
+            .typed::<DoubleType>()
+            .write_batch(&[0.5, 1.5], None, None)
+            .unwrap();
+        column.close().unwrap();
+        row_group.close().unwrap();
+        writer.close().unwrap();
+    }
+
+    fn dataset(dir: &Path, files: &[(&str, &str)], metadata: serde_json::Value) -> Dataset {
+        for (name, content) in files {
+            std::fs::write(dir.join(name), content).unwrap();
+        }
+        let metadata = migrate::load_metadata(metadata).unwrap();
+        Dataset::new(metadata, dir)
+    }
+
+    fn document(
+        distribution: serde_json::Value,
+        record_sets: serde_json::Value,
+    ) -> serde_json::Value {
+        serde_json::json!({
+            "@context": {"@vocab": "https://schema.org/", "cr": "http://mlcommons.org/croissant/"},
+            "@type": "sc:Dataset",
+            "name": "test",
+            "description": "test",
+            "version": "1.0.0",
+            "distribution": distribution,
+            "recordSet": record_sets,
+        })
+    }
+
+    #[test]
+    fn parquet_files_are_read_by_column_path() {
+        let dir = tempfile::tempdir().unwrap();
+        let parquet_path = dir.path().join("points.parquet");
+        write_parquet(&parquet_path);
+        let metadata_path = dir.path().join("metadata.json");
+        generate_metadata_from_parquet(&parquet_path, Some(&metadata_path)).unwrap();
+
+        let dataset = Dataset::from_file(&metadata_path).unwrap();
+        let records = dataset
+            .records("main")
+            .unwrap()
+            .map(|r| serde_json::to_value(r.unwrap()).unwrap())
+            .collect::<Vec<_>>();
+        assert_eq!(
+            records,
+            vec![
+                serde_json::json!({"main/id": 1, "main/name": "a", "main/point": {"main/point/x": 0.5}}),
+                serde_json::json!({"main/id": 2, "main/name": null, "main/point": {"main/point/x": 1.5}}),
+            ]
+        );
+    }
+
+    #[test]
+    fn file_lines_and_derived_record_sets_are_streamed() {
+        let dir = tempfile::tempdir().unwrap();
+        let metadata = document(
+            serde_json::json!([{
+                "@type": "cr:FileSet",
+                "@id": "texts",
+                "encodingFormat": "text/plain",
+                "includes": "*.txt",
+            }]),
+            serde_json::json!([
+                {
+                    "@type": "cr:RecordSet",
+                    "@id": "lines",
+                    "field": [
+                        {
+                            "@type": "cr:Field",
+                            "@id": "lines/file",
+                            "name": "file",
+                            "description": "File name",
+                            "dataType": "sc:Text",
+                            "source": {"fileSet": {"@id": "texts"}, "extract": {"fileProperty": "filename"}},
+                        },
+                        {
+                            "@type": "cr:Field",
+                            "@id": "lines/line",
+                            "name": "line",
+                            "description": "Line",
+                            "dataType": "sc:Text",
+                            "source": {"fileSet": {"@id": "texts"}, "extract": {"fileProperty": "lines"}},
+                        },
+                    ],
+                },
+                {
+                    "@type": "cr:RecordSet",
+                    "@id": "words",
+                    "field": {
+                        "@type": "cr:Field",
+                        "@id": "words/first",
+                        "name": "first",
+                        "description": "First word of a line",
+                        "dataType": "sc:Text",
+                        "source": {
+                            "recordSet": {"@id": "lines"},
+                            "extract": {"column": "line"},
+                            "transform": {"regex": "^(\\w+)"},
+                        },
+                    },
+                },
+            ]),
+        );
+        let dataset = dataset(
+            dir.path(),
+            &[("a.txt", "hello world\nbye now\n"), ("b.txt", "again\n")],
+            metadata,
+        );
+        let mut lines = dataset.records("lines").unwrap();
+        let first = lines.next().unwrap().unwrap();
+        assert_eq!(first.get("lines/file"), Some(&Value::Text("a.txt".into())));
+        assert_eq!(lines.count(), 2);
+
+        let words = dataset
+            .records("words")
+            .unwrap()
+            .map(|r| r.unwrap().get("words/first").cloned().unwrap())
+            .collect::<Vec<_>>();
+        let expected = ["hello", "bye", "again"].map(|w| Value::Text(w.into()));
+        assert_eq!(words, expected);
+    }
+
+    #[test]
+    fn transforms_of_binary_content_are_errors() {
+        let dir = tempfile::tempdir().unwrap();
+        let metadata = document(
+            serde_json::json!([{
+                "@type": "cr:FileSet",
+                "@id": "blobs",
+                "encodingFormat": "application/octet-stream",
+                "includes": "*.bin",
+            }]),
+            serde_json::json!([{
+                "@type": "cr:RecordSet",
+                "@id": "blobs",
+                "field": {
+                    "@type": "cr:Field",
+                    "@id": "blobs/content",
+                    "name": "content",
+                    "description": "Content",
+                    "dataType": "sc:Text",
+                    "source": {
+                        "fileSet": {"@id": "blobs"},
+                        "extract": {"fileProperty": "content"},
+                        "transform": {"regex": "(.*)"},
+                    },
+                },
+            }]),
+        );
+        let dataset = dataset(dir.path(), &[("a.bin", "data")], metadata);
+        let error = dataset
+            .records("blobs")
+            .unwrap()
+            .next()
+            .unwrap()
+            .unwrap_err();
+        assert!(error.to_string().contains("binary content"), "{error}");
+    }
+
+    #[test]
+    fn excludes_do_not_match_across_directories() {
+        let dir = tempfile::tempdir().unwrap();
+        std::fs::create_dir(dir.path().join("sub")).unwrap();
+        let metadata = document(
+            serde_json::json!([{
+                "@type": "cr:FileSet",
+                "@id": "texts",
+                "encodingFormat": "text/plain",
+                "includes": ["*.txt", "*/*.txt"],
+                "excludes": ["*.txt", "sub/b*"],
+            }]),
+            serde_json::json!([]),
+        );
+        let dataset = dataset(
+            dir.path(),
+            &[("a.txt", ""), ("sub/a.txt", ""), ("sub/b.txt", "")],
+            metadata,
+        );
+        let file_set = dataset.file_set(&Id::new("texts")).unwrap();
+        let files = dataset
+            .list_files(file_set)
+            .unwrap()
+            .into_iter()
+            .map(|(_, relative)| relative)
+            .collect::<Vec<_>>();
+        assert_eq!(files, vec!["sub/a.txt"]);
+    }
+
+    #[test]
+    fn content_urls_outside_the_dataset_are_rejected() {
+        let dir = tempfile::tempdir().unwrap();
+        let dataset = dataset(
+            dir.path(),
+            &[],
+            document(serde_json::json!([]), serde_json::json!([])),
+        );
+        assert!(dataset.resolve("data/../rows.csv").is_ok());
+        for content_url in ["/etc/passwd", "../secret.csv", "data/../../secret.csv"] {
+            let error = dataset.resolve(content_url).unwrap_err();
+            assert!(
+                matches!(error, Error::Unsupported(_)),
+                "{content_url}: {error}"
+            );
+        }
+    }
+}
@@ -0,0 +1,510 @@
+//! Resolution of the references between the nodes of a document
+//!
+//! Every `@id` reference (field sources, `references`, `key`, `parentField`
+//! and `containedIn`) is resolved to the node it designates, which must exist,
+//! be unique and be of the expected kind. References between record sets, file
+//! sets and fields must not form cycles.
+use crate::croissant::core::{
+    Field, FileObject, FileSet, Metadata, RecordSet, Resource, SourceRef,
+};
+use crate::croissant::report::{Severity, ValidationReport, codes};
+use std::collections::HashMap;
+use std::fmt;
+
+/// Encoding formats of archives a file set can be extracted from
+const ARCHIVE_FORMATS: &[&str] = &[
+    "application/zip",
+    "application/x-tar",
+    "application/gzip",
+    "application/x-gzip",
+    "application/x-bzip2",
+    "application/x-xz",
+    "application/zstd",
+    "application/x-7z-compressed",
+    "application/vnd.rar",
+];
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Kind {
+    FileObject,
+    FileSet,
+    RecordSet,
+    Field,
+}
+
+impl fmt::Display for Kind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let name = match self {
+            Self::FileObject => "FileObject",
+            Self::FileSet => "FileSet",
+            Self::RecordSet => "RecordSet",
+            Self::Field => "Field",
+        };
+        write!(f, "{name}")
+    }
+}
+
+#[derive(Debug, Clone, Copy)]
+enum NodeRef<'a> {
+    FileObject(&'a FileObject),
+    FileSet(&'a FileSet),
+    RecordSet(&'a RecordSet),
+    Field(&'a Field),
+}
+
+impl NodeRef<'_> {
+    fn kind(&self) -> Kind {
+        match self {
+            Self::FileObject(_) => Kind::FileObject,
+            Self::FileSet(_) => Kind::FileSet,
+            Self::RecordSet(_) => Kind::RecordSet,
+            Self::Field(_) => Kind::Field,
+        }
+    }
+}
+
+/// A node of the document with the position it was found at
+#[derive(Debug, Clone)]
+struct Node<'a> {
+    id: &'a str,
+    node: NodeRef<'a>,
+    location: String,
+    json_path: String,
+    /// Record set a field belongs to
+    record_set: Option<&'a str>,
+}
+
+/// A resolved reference, an edge of the reference graph
+struct Edge<'a> {
+    from: &'a str,
+    to: &'a str,
+    location: String,
+    json_path: String,
+}
+
+/// Check every reference of a document
+pub fn check_references(metadata: &Metadata) -> ValidationReport {
+    let mut graph = Graph::new(metadata);
+    graph.check_duplicates();
+    for i in 0..graph.nodes.len() {
+        let node = graph.nodes[i].clone();
+        match node.node {
+            NodeRef::FileObject(_) => {}
+            NodeRef::FileSet(set) => graph.check_file_set(&node, set),
+            NodeRef::RecordSet(record_set) => graph.check_record_set(&node, record_set),
+            NodeRef::Field(field) => graph.check_field(&node, field),
+        }
+    }
+    graph.check_cycles();
+    graph.report
+}
+
+struct Graph<'a> {
+    nodes: Vec<Node<'a>>,
+    by_id: HashMap<&'a str, Vec<usize>>,
+    edges: Vec<Edge<'a>>,
+    report: ValidationReport,
+}
+
+impl<'a> Graph<'a> {
+    fn new(metadata: &'a Metadata) -> Self {
+        let mut graph = Self {
+            nodes: Vec::new(),
+            by_id: HashMap::new(),
+            edges: Vec::new(),
+            report: ValidationReport::new(),
+        };
+        let root = format!("Metadata({})", metadata.name.0);
+        for (i, distribution) in metadata.distribution.iter().enumerate() {
+            let (id, node) = match &distribution.resource {
+                Resource::FileObject(object) => (&object.id.0, NodeRef::FileObject(object)),
+                Resource::FileSet(set) => (&set.id.0, NodeRef::FileSet(set)),
+            };
+            graph.add(Node {
+                id,
+                location: format!("{root} > {}({id})", node.kind()),
+                node,
+                json_path: format!("$.distribution[{i}]"),
+                record_set: None,
+            });
+        }
+        for (i, record_set) in metadata.record_sets.iter().enumerate() {
+            let location = format!("{root} > RecordSet({})", record_set.id.0);
+            let json_path = format!("$.recordSet[{i}]");
+            graph.add(Node {
+                id: &record_set.id.0,
+                node: NodeRef::RecordSet(record_set),
+                location: location.clone(),
+                json_path: json_path.clone(),
+                record_set: None,
+            });
+            graph.add_fields(
+                &record_set.fields,
+                &record_set.id.0,
+                &location,
+                &format!("{json_path}.field"),
+            );
+        }
+        graph
+    }
+
+    fn add(&mut self, node: Node<'a>) {
+        self.by_id
+            .entry(node.id)
+            .or_default()
+            .push(self.nodes.len());
+        self.nodes.push(node);
+    }
+
+    fn add_fields(&mut self, fields: &'a [Field], record_set: &'a str, parent: &str, path: &str) {
+        for (i, field) in fields.iter().enumerate() {
+            let location = format!("{parent} > Field({})", field.id.0);
+            let json_path = format!("{path}[{i}]");
+            self.add(Node {
+                id: &field.id.0,
+                node: NodeRef::Field(field),
+                location: location.clone(),
+                json_path: json_path.clone(),
+                record_set: Some(record_set),
+            });
+            if let Some(sub_fields) = &field.sub_fields {
+                self.add_fields(
+                    sub_fields,
+                    record_set,
+                    &location,
+                    &format!("{json_path}.subField"),
+                );
+            }
+        }
+    }
+
+    fn check_duplicates(&mut self) {
+        for (i, node) in self.nodes.iter().enumerate() {
+            let first = self.by_id[node.id][0];
+            if first != i {
+                self.report.push(
+                    Severity::Error,
+                    codes::DUPLICATE_ID,
+                    // Locations are built from the @id, the path tells nodes apart
+                    format!(
+                        "@id \"{}\" is already used by the node at {}.",
+                        node.id, self.nodes[first].json_path
+                    ),
+                    node.location.as_str(),
+                    format!("{}.@id", node.json_path),
+                );
+            }
+        }
+    }
+
+    /// Resolve a reference, reporting it when it is dangling, ambiguous or of
+    /// the wrong kind
+    fn resolve(
+        &mut self,
+        id: &'a str,
+        expected: &[Kind],
+        what: &str,
+        location: &str,
+        json_path: &str,
+    ) -> Option<Node<'a>> {
+        let matches = self.by_id.get(id).map(Vec::as_slice).unwrap_or_default();
+        let node = match matches {
+            [] => {
+                self.report.push(
+                    Severity::Error,
+                    codes::REFERENCE,
+                    format!("{what} \"{id}\" does not exist."),
+                    location,
+                    json_path,
+                );
+                return None;
+            }
+            [node] => self.nodes[*node].clone(),
+            nodes => {
+                let candidates = nodes
+                    .iter()
+                    .map(|n| self.nodes[*n].location.as_str())
+                    .collect::<Vec<_>>();
+                self.report.push(
+                    Severity::Error,
+                    codes::AMBIGUOUS_REFERENCE,
+                    format!(
+                        "{what} \"{id}\" is ambiguous, it matches {}.",
+                        candidates.join(" and ")
+                    ),
+                    location,
+                    json_path,
+                );
+                return None;
+            }
+        };
+        let kind = node.node.kind();
+        if !expected.contains(&kind) {
+            let expected = expected
+                .iter()
+                .map(Kind::to_string)
+                .collect::<Vec<_>>()
+                .join(" or ");
+            self.report.push(
+                Severity::Error,
+                codes::REFERENCE_KIND,
+                format!("{what} \"{id}\" is a {kind}, expected a {expected}."),
+                location,
+                json_path,
+            );
+            return None;
+        }
+        Some(node)
+    }
+
+    fn check_file_set(&mut self, node: &Node<'a>, set: &'a FileSet) {
+        for (i, container) in set.sources.iter().enumerate() {
+            let json_path = format!("{}.containedIn[{i}]", node.json_path);
+            let expected = [Kind::FileObject, Kind::FileSet];
+            let what = "Container";
+            let Some(target) =
+                self.resolve(&container.0, &expected, what, &node.location, &json_path)
+            else {
+                continue;
+            };
+            if let NodeRef::FileObject(object) = target.node {
+                let archive = ARCHIVE_FORMATS.contains(&&*object.encoding_format.0);
+                if !archive && !object.content_url.0.ends_with('/') {
+                    self.report.push(
+                        Severity::Warning,
+                        codes::REFERENCE_KIND,
+                        format!(
+                            "Container \"{}\" is neither an archive nor a directory (encodingFormat \"{}\").",
+                            container.0, object.encoding_format.0
+                        ),
+                        node.location.as_str(),
+                        json_path.as_str(),
+                    );
+                }
+            }
+            self.edges.push(Edge {
+                from: node.id,
+                to: target.id,
+                location: node.location.clone(),
+                json_path,
+            });
+        }
+    }
+
+    fn check_record_set(&mut self, node: &Node<'a>, record_set: &'a RecordSet) {
+        for (i, key) in record_set.keys.iter().enumerate() {
+            let json_path = format!("{}.key[{i}]", node.json_path);
+            let Some(target) =
+                self.resolve(&key.id.0, &[Kind::Field], "Key", &node.location, &json_path)
+            else {
+                continue;
+            };
+            if target.record_set != Some(node.id) {
+                self.report.push(
+                    Severity::Error,
+                    codes::REFERENCE,
+                    format!(
+                        "Key \"{}\" is not a field of record set \"{}\".",
+                        key.id.0, node.id
+                    ),
+                    node.location.as_str(),
+                    json_path,
+                );
+            }
+        }
+    }
+
+    fn check_field(&mut self, node: &Node<'a>, field: &'a Field) {
+        match &field.source {
+            Some(source) => self.check_source(node, &source.source),
+            // Fields of record sets holding their records inline and fields
+            // described by their sub-fields need no source
+            None if self.has_inline_data(node.record_set)
+                || field.sub_fields.as_ref().is_some_and(|s| !s.is_empty()) => {}
+            None => self.report.push(
+                Severity::Error,
+                codes::REQUIRED_PROPERTY,
+                "Property \"http://mlcommons.org/croissant/source\" is mandatory, but does not exist.",
+                node.location.as_str(),
+                node.json_path.as_str(),
+            ),
+        }
+
+        for (i, reference) in field.references.iter().enumerate() {
+            let json_path = format!("{}.references[{i}].field", node.json_path);
+            let id = &reference.field.id.0;
+            self.resolve(
+                id,
+                &[Kind::Field],
+                "Referenced field",
+                &node.location,
+                &json_path,
+            );
+        }
+
+        for (i, parent) in field.parent_fields.iter().flatten().enumerate() {
+            let json_path = format!("{}.parentField[{i}]", node.json_path);
+            let what = "Parent field";
+            if let Some(target) =
+                self.resolve(&parent.0, &[Kind::Field], what, &node.location, &json_path)
+            {
+                self.edges.push(Edge {
+                    from: node.id,
+                    to: target.id,
+                    location: node.location.clone(),
+                    json_path,
+                });
+            }
+        }
+    }
+
+    fn check_source(&mut self, node: &Node<'a>, source: &'a SourceRef) {
+        let (id, kind, what) = match source {
+            SourceRef::FileObject { file_object } => {
+                (&file_object.id.0, Kind::FileObject, "Source file object")
+            }
+            SourceRef::FileSet { file_set } => (&file_set.id.0, Kind::FileSet, "Source file set"),
+            SourceRef::RecordSet { record_set } => {
+                (&record_set.id.0, Kind::RecordSet, "Source record set")
+            }
+        };
+        let json_path = format!("{}.source", node.json_path);
+        let target = self.resolve(id, &[kind], what, &node.location, &json_path);
+        if let (Some(target), Some(record_set)) = (target, node.record_set)
+            && kind == Kind::RecordSet
+        {
+            self.edges.push(Edge {
+                from: record_set,
+                to: target.id,
+                location: node.location.clone(),
+                json_path,
+            });
+        }
+    }
+
+    /// Whether the record set of a field holds its records inline
+    fn has_inline_data(&self, record_set: Option<&str>) -> bool {
+        let indices = record_set.and_then(|id| self.by_id.get(id));
+        indices
+            .into_iter()
+            .flatten()
+            .any(|&i| matches!(self.nodes[i].node, NodeRef::RecordSet(r) if !r.data.is_empty()))
+    }
+
+    /// Report every cycle of record sets reading from each other, file sets
+    /// contained in each other or fields being their own parent
+    fn check_cycles(&mut self) {
+        let mut adjacency = HashMap::<&str, Vec<usize>>::new();
+        for (i, edge) in self.edges.iter().enumerate() {
+            adjacency.entry(edge.from).or_default().push(i);
+        }
+
+        #[derive(Clone, Copy, PartialEq)]
+        enum State {
+            Visiting,
+            Done,
+        }
+        let mut states = HashMap::<&str, State>::new();
+        let mut cycles = Vec::new();
+        for start in self.edges.iter().map(|e| e.from) {
+            if states.contains_key(start) {
+                continue;
+            }
+            // Depth-first search keeping the path of edges from `start`
+            let mut stack = vec![(start, 0)];
+            let mut path = Vec::<usize>::new();
+            states.insert(start, State::Visiting);
+            while let Some((node, next)) = stack.last_mut() {
+                let node = *node;
+                let Some(&edge) = adjacency.get(node).and_then(|edges| edges.get(*next)) else {
+                    states.insert(node, State::Done);
+                    stack.pop();
+                    path.pop();
+                    continue;
+                };
+                *next += 1;
+                let target = self.edges[edge].to;
+                match states.get(target) {
+                    Some(State::Done) => {}
+                    Some(State::Visiting) => {
+                        let begin = path
+                            .iter()
+                            .position(|e| self.edges[*e].from == target)
+                            .unwrap_or(path.len());
+                        let mut cycle = path[begin..].to_vec();
+                        cycle.push(edge);
+                        cycles.push(cycle);
+                    }
+                    None => {
+                        states.insert(target, State::Visiting);
+                        stack.push((target, 0));
+                        path.push(edge);
+                    }
+                }
+            }
+        }
+
+        for cycle in cycles {
+            let mut ids = cycle
+                .iter()
+                .map(|e| self.edges[*e].from)
+                .collect::<Vec<_>>();
+            ids.push(ids[0]);
+            let last = &self.edges[cycle[cycle.len() - 1]];
+            self.report.push(
+                Severity::Error,
+                codes::REFERENCE_CYCLE,
+                format!("Reference cycle: {}.", ids.join(" -> ")),
+                last.location.as_str(),
+                last.json_path.as_str(),
+            );
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::croissant::jsonld::parse_metadata;
+
+    #[test]
+    fn duplicate_ids_point_at_the_first_node() {
+        let field = serde_json::json!({
+            "@type": "cr:Field",
+            "@id": "main/a",
+            "name": "a",
+            "description": "A",
+            "dataType": "sc:Text",
+        });
+        let document = serde_json::json!({
+            "@context": {"@vocab": "https://schema.org/", "cr": "http://mlcommons.org/croissant/"},
+            "@type": "sc:Dataset",
+            "name": "test",
+            "description": "test",
+            "version": "1.0.0",
+            "distribution": [],
+            "recordSet": [{
+                "@type": "cr:RecordSet",
+                "@id": "main",
+                "name": "main",
+                "description": "main",
+                "field": [field.clone(), field],
+            }],
+        });
+
+        let report = check_references(&parse_metadata(document).unwrap());
+        let duplicates = report
+            .issues
+            .iter()
+            .filter(|issue| issue.code == codes::DUPLICATE_ID)
+            .map(|issue| (issue.message.as_str(), issue.path.as_str()))
+            .collect::<Vec<_>>();
+        assert_eq!(
+            duplicates,
+            vec![(
+                "@id \"main/a\" is already used by the node at $.recordSet[0].field[0].",
+                "$.recordSet[0].field[1].@id"
+            )]
+        );
+    }
+}
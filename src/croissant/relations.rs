@@ -0,0 +1,293 @@
+//! Discovery of keys and foreign keys between related tables
+//!
+//! Every column of every table is read once into the set of its distinct
+//! values. A column is a candidate key when its values are unique and never
+//! missing, and a column references the key of another table when all of its
+//! values appear there (an inclusion dependency).
+use crate::croissant::compression::open_decompressed;
+use crate::croissant::core::{CsvDialect, DataType};
+use crate::croissant::dialect::open_csv;
+use crate::croissant::errors::Result;
+use crate::croissant::infer::{ColumnProfile, InferenceOptions};
+use std::collections::HashSet;
+use std::path::Path;
+
+/// Distinct values of a column of a table
+#[derive(Debug, Clone)]
+pub struct ColumnValues {
+    pub profile: ColumnProfile,
+    pub values: HashSet<String>,
+    /// Whether a value was seen more than once
+    pub duplicates: bool,
+}
+
+impl ColumnValues {
+    fn new(name: &str) -> Self {
+        Self {
+            profile: ColumnProfile::new(name),
+            values: HashSet::new(),
+            duplicates: false,
+        }
+    }
+
+    /// Whether the column identifies the rows of its table
+    pub fn is_unique(&self) -> bool {
+        self.profile.rows > 0 && self.profile.nulls == 0 && !self.duplicates
+    }
+}
+
+/// Columns of a table, in file order
+#[derive(Debug, Clone)]
+pub struct TableValues {
+    pub name: String,
+    pub columns: Vec<ColumnValues>,
+    /// Dialect detected from the file
+    pub dialect: CsvDialect,
+}
+
+/// A column whose values all appear in the key of another table
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct InclusionDependency {
+    pub table: usize,
+    pub column: usize,
+    pub target_table: usize,
+    pub target_column: usize,
+}
+
+/// Read the columns of a CSV file with their types and distinct values
+///
+/// Values are trimmed and the null markers of `options` are skipped, as for
+/// type inference. Only the first `max_rows` rows are read when it is set.
+/// The dialect of the file is detected, see [`open_csv`].
+pub fn read_csv_values(
+    name: &str,
+    csv_path: &Path,
+    options: &InferenceOptions,
+) -> Result<TableValues> {
+    let (dialect, mut reader, headers) = open_csv(open_decompressed(csv_path)?, &options.dialect)?;
+    let mut columns = headers
+        .iter()
+        .map(|header| ColumnValues::new(header))
+        .collect::<Vec<_>>();
+
+    for (row, result) in reader.records().enumerate() {
+        if options.max_rows.is_some_and(|max| row >= max) {
+            break;
+        }
+        let record = result?;
+        for (i, column) in columns.iter_mut().enumerate() {
+            let value = record.get(i).unwrap_or_default();
+            column.profile.observe(value, options);
+            let value = value.trim();
+            if !options.is_null(value) && !column.values.insert(value.to_string()) {
+                column.duplicates = true;
+            }
+        }
+    }
+
+    Ok(TableValues {
+        name: name.to_string(),
+        columns,
+        dialect,
+    })
+}
+
+/// Primary key of a table: the unique column that best looks like an
+/// identifier, or the first unique column
+pub fn primary_key(table: &TableValues) -> Option<usize> {
+    let singular = singular(&normalize(&table.name));
+    let candidates = table
+        .columns
+        .iter()
+        .enumerate()
+        .filter(|(_, c)| c.is_unique() && is_key_type(&c.profile.data_type));
+    candidates
+        .map(|(i, column)| {
+            let name = normalize(&column.profile.name);
+            let score = if name == "id" || name == format!("{singular}id") {
+                2
+            } else if name.ends_with("id") || name.ends_with("key") || name.ends_with("code") {
+                1
+            } else {
+                0
+            };
+            (i, score)
+        })
+        .max_by_key(|(i, score)| (*score, std::cmp::Reverse(*i)))
+        .map(|(i, _)| i)
+}
+
+/// Inclusion dependencies between the columns of the tables and their
+/// primary keys (`keys[t]` being the primary key of `tables[t]`)
+///
+/// A column references the key of another table when its values are
+/// included in that key and both have the same data type. When several keys
+/// include a column, the one whose table and column names match best wins,
+/// and ties are dropped as ambiguous. Integer columns and columns that are
+/// keys themselves also need a matching name (`customer_id` for
+/// `customers.id`), since small integers and identifiers numbered from 1 are
+/// included in most integer keys.
+pub fn inclusion_dependencies(
+    tables: &[TableValues],
+    keys: &[Option<usize>],
+) -> Vec<InclusionDependency> {
+    let mut dependencies = Vec::new();
+    for (t, table) in tables.iter().enumerate() {
+        for (c, column) in table.columns.iter().enumerate() {
+            if column.values.is_empty() || !is_key_type(&column.profile.data_type) {
+                continue;
+            }
+            let mut candidates = Vec::new();
+            for (target_table, target) in tables.iter().enumerate() {
+                let Some(target_column) = keys[target_table] else {
+                    continue;
+                };
+                let key = &target.columns[target_column];
+                if target_table == t
+                    || key.profile.data_type != column.profile.data_type
+                    || !column.values.is_subset(&key.values)
+                {
+                    continue;
+                }
+                let score = name_score(&column.profile.name, &target.name, &key.profile.name);
+                let numeric = column.profile.data_type == DataType::Integer;
+                if score == 0 && (numeric || keys[t] == Some(c)) {
+                    continue;
+                }
+                candidates.push((score, target_table, target_column));
+            }
+            candidates.sort_by_key(|(score, ..)| std::cmp::Reverse(*score));
+            match candidates.as_slice() {
+                [] => {}
+                [(best, ..), (second, ..), ..] if best == second => {}
+                [(_, target_table, target_column), ..] => dependencies.push(InclusionDependency {
+                    table: t,
+                    column: c,
+                    target_table: *target_table,
+                    target_column: *target_column,
+                }),
+            }
+        }
+    }
+    dependencies
+}
+
+/// Types whose values can identify a row
+fn is_key_type(data_type: &DataType) -> bool {
+    matches!(
+        data_type,
+        DataType::Integer | DataType::Text | DataType::Url
+    )
+}
+
+/// How much the name of a column suggests that it references `table.key`
+fn name_score(column: &str, table: &str, key: &str) -> u8 {
+    let column = normalize(column);
+    let table = singular(&normalize(table));
+    let key = normalize(key);
+    if column == format!("{table}{key}") || (column == key && key != "id") {
+        2
+    } else if column.contains(&table) {
+        1
+    } else {
+        0
+    }
+}
+
+/// Lowercase alphanumeric form of a name
+fn normalize(name: &str) -> String {
+    name.chars()
+        .filter(char::is_ascii_alphanumeric)
+        .map(|c| c.to_ascii_lowercase())
+        .collect()
+}
+
+/// Naive singular of a normalized table name (`customers` → `customer`)
+fn singular(name: &str) -> String {
+    if let Some(stem) = name.strip_suffix("ies").filter(|s| !s.is_empty()) {
+        format!("{stem}y")
+    } else if let Some(stem) = name.strip_suffix('s').filter(|s| !s.is_empty()) {
+        stem.to_string()
+    } else {
+        name.to_string()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn read(directory: &Path, name: &str, content: &str) -> TableValues {
+        let path = directory.join(format!("{name}.csv"));
+        std::fs::write(&path, content).unwrap();
+        read_csv_values(name, &path, &InferenceOptions::default()).unwrap()
+    }
+
+    #[test]
+    fn keys_and_references_are_discovered() {
+        let directory = tempfile::tempdir().unwrap();
+        let tables = [
+            read(
+                directory.path(),
+                "customers",
+                "id,email,country\n1,a@x.org,FR\n2,b@x.org,FR\n3,c@x.org,DE\n",
+            ),
+            read(
+                directory.path(),
+                "orders",
+                "order_id,customer_id,amount\n10,1,5\n11,1,7\n12,3,9\n",
+            ),
+            read(
+                directory.path(),
+                "countries",
+                "code,label\nFR,France\nDE,Germany\n",
+            ),
+        ];
+        assert!(tables[1].columns[1].duplicates);
+        assert_eq!(tables[0].columns[2].values.len(), 2);
+
+        let keys = tables.iter().map(primary_key).collect::<Vec<_>>();
+        assert_eq!(keys, [Some(0), Some(0), Some(0)]);
+
+        let dependencies = inclusion_dependencies(&tables, &keys);
+        assert_eq!(
+            dependencies,
+            [
+                InclusionDependency {
+                    table: 0,
+                    column: 2,
+                    target_table: 2,
+                    target_column: 0,
+                },
+                InclusionDependency {
+                    table: 1,
+                    column: 1,
+                    target_table: 0,
+                    target_column: 0,
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn columns_with_missing_values_are_not_keys() {
+        let directory = tempfile::tempdir().unwrap();
+        let table = read(directory.path(), "items", "name,id\na,1\nb,\nc,3\n");
+        assert!(table.columns[0].is_unique());
+        assert!(!table.columns[1].is_unique());
+        assert_eq!(primary_key(&table), Some(0));
+
+        let table = read(directory.path(), "empty", "id\n");
+        assert_eq!(primary_key(&table), None);
+    }
+
+    #[test]
+    fn names_are_compared_in_singular_lowercase() {
+        assert_eq!(singular(&normalize("Categories")), "category");
+        assert_eq!(singular(&normalize("Order_Items")), "orderitem");
+        assert_eq!(name_score("customer_id", "customers", "id"), 2);
+        assert_eq!(name_score("code", "countries", "code"), 2);
+        assert_eq!(name_score("billing_customer", "customers", "id"), 1);
+        assert_eq!(name_score("amount", "customers", "id"), 0);
+    }
+}
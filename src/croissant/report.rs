@@ -0,0 +1,439 @@
+//! Structured validation reports
+use crate::croissant::errors::{Error, Result};
+use serde::Serialize;
+use std::fmt;
+use std::str::FromStr;
+
+/// Stable identifiers of the validation rules
+pub mod codes {
+    /// The input cannot be read
+    pub const INPUT_READ: &str = "input-read";
+    /// The input is not well-formed JSON
+    pub const JSON_SYNTAX: &str = "json-syntax";
+    /// The JSON-LD `@context` cannot be processed
+    pub const JSON_LD: &str = "json-ld";
+    /// The document does not match the Croissant data model
+    pub const SCHEMA: &str = "schema";
+    /// A property value violates a constraint (length, pattern, ...)
+    pub const CONSTRAINT: &str = "constraint";
+    /// A node references an id that does not exist
+    pub const REFERENCE: &str = "reference";
+    /// A reference designates a node of the wrong kind
+    pub const REFERENCE_KIND: &str = "reference-kind";
+    /// A reference matches several nodes
+    pub const AMBIGUOUS_REFERENCE: &str = "ambiguous-reference";
+    /// References lead back to the node they start from
+    pub const REFERENCE_CYCLE: &str = "reference-cycle";
+    /// Several nodes share the same `@id`
+    pub const DUPLICATE_ID: &str = "duplicate-id";
+    /// The document conforms to a superseded version of the specification
+    pub const DEPRECATED_VERSION: &str = "deprecated-version";
+    /// A property required by the specification is missing
+    pub const REQUIRED_PROPERTY: &str = "required-property";
+    /// A recommended property is missing
+    pub const RECOMMENDED_PROPERTY: &str = "recommended-property";
+    /// A file of the distribution cannot be found
+    pub const FILE_MISSING: &str = "file-missing";
+    /// A file does not match its declared checksum
+    pub const CHECKSUM_MISMATCH: &str = "checksum-mismatch";
+    /// A file does not match its declared `contentSize`
+    pub const SIZE_MISMATCH: &str = "size-mismatch";
+    /// A file could not be checked, e.g. because it is remote
+    pub const NOT_VERIFIED: &str = "not-verified";
+    /// The records of a record set cannot be read
+    pub const DATA_READ: &str = "data-read";
+    /// Values do not match the declared `dataType` of their field
+    pub const DATA_TYPE: &str = "data-type";
+    /// Records have a null or duplicate key
+    pub const KEY: &str = "key";
+    /// Values are missing from the field they reference
+    pub const FOREIGN_KEY: &str = "foreign-key";
+    /// Inline records do not match the fields of their record set
+    pub const INLINE_DATA: &str = "inline-data";
+
+    /// Short description of a rule
+    pub fn description(code: &str) -> &'static str {
+        match code {
+            INPUT_READ => "The input cannot be read",
+            JSON_SYNTAX => "The input is not well-formed JSON",
+            JSON_LD => "The JSON-LD @context cannot be processed",
+            SCHEMA => "The document does not match the Croissant data model",
+            CONSTRAINT => "A property value violates a constraint",
+            REFERENCE => "A node references an id that does not exist",
+            REFERENCE_KIND => "A reference designates a node of the wrong kind",
+            AMBIGUOUS_REFERENCE => "A reference matches several nodes",
+            REFERENCE_CYCLE => "References lead back to the node they start from",
+            DUPLICATE_ID => "Several nodes share the same @id",
+            DEPRECATED_VERSION => {
+                "The document conforms to a superseded version of the specification"
+            }
+            REQUIRED_PROPERTY => "A property required by the specification is missing",
+            RECOMMENDED_PROPERTY => "A recommended property is missing",
+            FILE_MISSING => "A file of the distribution cannot be found",
+            CHECKSUM_MISMATCH => "A file does not match its declared checksum",
+            SIZE_MISMATCH => "A file does not match its declared contentSize",
+            NOT_VERIFIED => "A file could not be checked",
+            DATA_READ => "The records of a record set cannot be read",
+            DATA_TYPE => "Values do not match the declared dataType of their field",
+            KEY => "Records have a null or duplicate key",
+            FOREIGN_KEY => "Values are missing from the field they reference",
+            INLINE_DATA => "Inline records do not match the fields of their record set",
+            _ => "Croissant validation rule",
+        }
+    }
+}
+
+/// Severity of a validation issue
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Severity {
+    Error,
+    Warning,
+    Info,
+}
+
+impl fmt::Display for Severity {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let key = match self {
+            Self::Error => "error",
+            Self::Warning => "warning",
+            Self::Info => "info",
+        };
+        write!(f, "{}", key)
+    }
+}
+
+/// A single finding of the validation
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct Issue {
+    pub severity: Severity,
+    /// Stable rule identifier, see [`codes`]
+    pub code: String,
+    pub message: String,
+    /// Human readable location, e.g. `Metadata(mydataset) > RecordSet(a-record-set)`
+    pub location: String,
+    /// JSON path of the offending node or property, e.g. `$.recordSet[0].field[1]`
+    pub path: String,
+    /// 1-based line of the offending node in the validated text, when known
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub line: Option<usize>,
+    /// 1-based column of the offending node in the validated text, when known
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub column: Option<usize>,
+}
+
+impl fmt::Display for Issue {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "[{}] {}", self.location, self.message)
+    }
+}
+
+/// Every issue found while validating a document
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize)]
+pub struct ValidationReport {
+    pub issues: Vec<Issue>,
+}
+
+impl ValidationReport {
+    /// Create an empty report
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Add an issue to the report
+    pub fn push(
+        &mut self,
+        severity: Severity,
+        code: impl Into<String>,
+        message: impl Into<String>,
+        location: impl Into<String>,
+        path: impl Into<String>,
+    ) {
+        self.issues.push(Issue {
+            severity,
+            code: code.into(),
+            message: message.into(),
+            location: location.into(),
+            path: path.into(),
+            line: None,
+            column: None,
+        });
+    }
+
+    /// Append all issues of another report
+    pub fn extend(&mut self, other: ValidationReport) {
+        self.issues.extend(other.issues);
+    }
+
+    /// Issues with the given severity
+    pub fn with_severity(&self, severity: Severity) -> impl Iterator<Item = &Issue> {
+        self.issues.iter().filter(move |i| i.severity == severity)
+    }
+
+    pub fn errors(&self) -> impl Iterator<Item = &Issue> {
+        self.with_severity(Severity::Error)
+    }
+
+    pub fn warnings(&self) -> impl Iterator<Item = &Issue> {
+        self.with_severity(Severity::Warning)
+    }
+
+    pub fn has_errors(&self) -> bool {
+        self.errors().next().is_some()
+    }
+
+    pub fn has_warnings(&self) -> bool {
+        self.warnings().next().is_some()
+    }
+
+    /// `true` when the report contains no errors
+    pub fn is_valid(&self) -> bool {
+        !self.has_errors()
+    }
+
+    /// `true` when the input could not be read or parsed as JSON, so that
+    /// nothing else could be checked
+    pub fn is_unreadable(&self) -> bool {
+        self.errors()
+            .any(|i| i.code == codes::INPUT_READ || i.code == codes::JSON_SYNTAX)
+    }
+
+    /// Render the report in the given format
+    ///
+    /// `artifact` names the validated file in SARIF and JUnit outputs.
+    pub fn render(&self, format: ReportFormat, artifact: &str) -> Result<String> {
+        match format {
+            ReportFormat::Text => Ok(self.to_string()),
+            ReportFormat::Json => Ok(self.to_json()? + "\n"),
+            ReportFormat::Sarif => Ok(self.to_sarif(artifact)? + "\n"),
+            ReportFormat::Junit => Ok(self.to_junit(artifact)),
+        }
+    }
+
+    /// Summary and issues as a JSON document
+    pub fn to_json(&self) -> Result<String> {
+        let value = serde_json::json!({
+            "valid": self.is_valid(),
+            "errors": self.errors().count(),
+            "warnings": self.warnings().count(),
+            "infos": self.with_severity(Severity::Info).count(),
+            "issues": self.issues,
+        });
+        Ok(serde_json::to_string_pretty(&value)?)
+    }
+
+    /// SARIF 2.1.0 log with one result per issue
+    pub fn to_sarif(&self, artifact: &str) -> Result<String> {
+        let mut rules = Vec::<&str>::new();
+        for issue in &self.issues {
+            if !rules.contains(&issue.code.as_str()) {
+                rules.push(&issue.code);
+            }
+        }
+        let rules = rules
+            .into_iter()
+            .map(|code| {
+                serde_json::json!({
+                    "id": code,
+                    "shortDescription": { "text": codes::description(code) },
+                })
+            })
+            .collect::<Vec<_>>();
+
+        let results = self
+            .issues
+            .iter()
+            .map(|issue| {
+                let level = match issue.severity {
+                    Severity::Error => "error",
+                    Severity::Warning => "warning",
+                    Severity::Info => "note",
+                };
+                let mut physical = serde_json::json!({
+                    "artifactLocation": { "uri": artifact },
+                });
+                // SARIF positions are 1-based, parsers report column 0 at EOF
+                if let Some(line) = issue.line {
+                    physical["region"] = serde_json::json!({
+                        "startLine": line.max(1),
+                        "startColumn": issue.column.unwrap_or(1).max(1),
+                    });
+                }
+                serde_json::json!({
+                    "ruleId": issue.code,
+                    "level": level,
+                    "message": { "text": issue.message },
+                    "locations": [{
+                        "physicalLocation": physical,
+                        "logicalLocations": [{
+                            "name": issue.location,
+                            "fullyQualifiedName": issue.path,
+                        }],
+                    }],
+                })
+            })
+            .collect::<Vec<_>>();
+
+        let version = crate::version::get_version();
+        let log = serde_json::json!({
+            "$schema": "https://json.schemastore.org/sarif-2.1.0.json",
+            "version": "2.1.0",
+            "runs": [{
+                "tool": {
+                    "driver": {
+                        "name": version.app_name,
+                        "version": version.version,
+                        "informationUri": "https://github.com/aqora-io/rustcroissant",
+                        "rules": rules,
+                    },
+                },
+                "results": results,
+            }],
+        });
+        Ok(serde_json::to_string_pretty(&log)?)
+    }
+
+    /// JUnit XML with one test case per issue, errors being failures
+    pub fn to_junit(&self, artifact: &str) -> String {
+        let mut xml = String::from("<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n");
+        let tests = self.issues.len().max(1);
+        let failures = self.errors().count();
+        xml.push_str(&format!(
+            "<testsuites tests=\"{tests}\" failures=\"{failures}\">\n"
+        ));
+        xml.push_str(&format!(
+            "  <testsuite name=\"{}\" tests=\"{tests}\" failures=\"{failures}\">\n",
+            xml_escape(artifact)
+        ));
+        if self.issues.is_empty() {
+            xml.push_str(&format!(
+                "    <testcase classname=\"{}\" name=\"validation\"/>\n",
+                xml_escape(artifact)
+            ));
+        }
+        for issue in &self.issues {
+            xml.push_str(&format!(
+                "    <testcase classname=\"{}\" name=\"{}: {}\">\n",
+                xml_escape(&issue.location),
+                issue.code,
+                xml_escape(&issue.path)
+            ));
+            match issue.severity {
+                Severity::Error => xml.push_str(&format!(
+                    "      <failure type=\"{}\" message=\"{}\"/>\n",
+                    issue.code,
+                    xml_escape(&issue.message)
+                )),
+                Severity::Warning | Severity::Info => xml.push_str(&format!(
+                    "      <system-out>{}: {}</system-out>\n",
+                    issue.severity,
+                    xml_escape(&issue.message)
+                )),
+            }
+            xml.push_str("    </testcase>\n");
+        }
+        xml.push_str("  </testsuite>\n</testsuites>\n");
+        xml
+    }
+}
+
+/// Output format of a [`ValidationReport`]
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum ReportFormat {
+    /// Human readable summary, the [`fmt::Display`] of the report
+    #[default]
+    Text,
+    Json,
+    /// Static Analysis Results Interchange Format 2.1.0
+    Sarif,
+    /// JUnit XML, as understood by most CI servers
+    Junit,
+}
+
+impl FromStr for ReportFormat {
+    type Err = Error;
+
+    fn from_str(format: &str) -> Result<Self> {
+        match format.to_ascii_lowercase().as_str() {
+            "text" => Ok(Self::Text),
+            "json" => Ok(Self::Json),
+            "sarif" => Ok(Self::Sarif),
+            "junit" => Ok(Self::Junit),
+            other => Err(Error::unsupported(format!("report format '{other}'"))),
+        }
+    }
+}
+
+fn xml_escape(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+        .replace('\'', "&apos;")
+}
+
+impl fmt::Display for ValidationReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.issues.is_empty() {
+            return writeln!(f, "Validation passed with no issues.");
+        }
+
+        let mut first = true;
+        for severity in [Severity::Error, Severity::Warning, Severity::Info] {
+            let issues = self.with_severity(severity).collect::<Vec<_>>();
+            if issues.is_empty() {
+                continue;
+            }
+            if !first {
+                writeln!(f)?;
+            }
+            first = false;
+            writeln!(
+                f,
+                "Found the following {} {}(s) during the validation:",
+                issues.len(),
+                severity
+            )?;
+            for issue in issues {
+                writeln!(f, "  -  {issue}")?;
+            }
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::croissant::validate::validate_str;
+
+    #[test]
+    fn truncated_input_is_unreadable_and_located_from_column_one() {
+        let mut report = validate_str("");
+        assert!(report.is_unreadable());
+        assert_eq!(report.issues[0].column, Some(1));
+        report.issues[0].column = Some(0);
+
+        let sarif: serde_json::Value =
+            serde_json::from_str(&report.render(ReportFormat::Sarif, "m.json").unwrap()).unwrap();
+        let region = &sarif["runs"][0]["results"][0]["locations"][0]["physicalLocation"]["region"];
+        assert_eq!(region["startLine"], 1);
+        assert_eq!(region["startColumn"], 1);
+        assert_eq!(
+            sarif["runs"][0]["tool"]["driver"]["rules"][0]["id"],
+            codes::JSON_SYNTAX
+        );
+    }
+
+    #[test]
+    fn only_reading_errors_make_a_report_unreadable() {
+        let mut report = ValidationReport::new();
+        report.push(Severity::Error, codes::SCHEMA, "bad", "Metadata", "$");
+        assert!(!report.is_unreadable());
+        report.push(Severity::Error, codes::INPUT_READ, "gone", "Metadata", "$");
+        assert!(report.is_unreadable());
+        let json: serde_json::Value = serde_json::from_str(&report.to_json().unwrap()).unwrap();
+        assert_eq!(json["errors"], 2);
+        assert_eq!(json["issues"][1]["code"], "input-read");
+    }
+}
@@ -0,0 +1,642 @@
+//! Structural checks of documents before they are mapped onto the typed model
+//!
+//! Deserialization stops at the first mismatch and cannot tell where it
+//! happened below flattened properties. The normalized document (see
+//! [`jsonld::normalize`]) is therefore walked first: every missing mandatory
+//! property, property holding the wrong kind of JSON value and node of an
+//! unexpected `@type` is reported at its own JSON path.
+use crate::croissant::jsonld;
+use crate::croissant::report::{Severity, ValidationReport, codes};
+use serde_json::{Map, Value};
+
+/// Kind of node a property holds
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Node {
+    Dataset,
+    Agent,
+    CreativeWork,
+    Distribution,
+    FileObject,
+    FileSet,
+    Dialect,
+    RecordSet,
+    Field,
+    Source,
+    Extract,
+    Transform,
+    Format,
+    FieldRef,
+}
+
+/// Expected JSON shape of a property value
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Shape {
+    Text,
+    /// A string or a list of strings
+    Texts,
+    /// A string, possibly tagged with its language
+    LangText,
+    /// A possibly tagged string or a list of them
+    LangTexts,
+    Bool,
+    /// A node object
+    Object(Node),
+    /// A node object or a list of node objects
+    Objects(Node),
+    /// A string, a node object or a list of them
+    TextsOrObjects(Node),
+    /// A `{"@id": ...}` reference
+    Ref,
+    /// One or many `{"@id": ...}` references
+    Refs,
+    /// One or many ids, as strings or references
+    IdRefs,
+    /// One or many records of inline data
+    Records,
+}
+
+impl Shape {
+    fn describe(&self) -> &'static str {
+        match self {
+            Shape::Text => "a string",
+            Shape::Texts => "a string or a list of strings",
+            Shape::LangText => "a string or a language-tagged string",
+            Shape::LangTexts => "a string, a language-tagged string or a list of them",
+            Shape::Bool => "a boolean",
+            Shape::Object(_) => "an object",
+            Shape::Objects(_) | Shape::Records => "an object or a list of objects",
+            Shape::TextsOrObjects(_) => "a string, an object or a list of them",
+            Shape::Ref => "a reference ({\"@id\": ...})",
+            Shape::Refs => "one or more references ({\"@id\": ...})",
+            Shape::IdRefs => "one or more ids or references",
+        }
+    }
+}
+
+/// A property of a node: name in the canonical context, shape and whether
+/// it is mandatory
+type Property = (&'static str, Shape, bool);
+
+const DATASET: &[Property] = &[
+    ("@type", Shape::Text, true),
+    ("name", Shape::LangText, true),
+    ("description", Shape::LangText, true),
+    ("conformsTo", Shape::Text, false),
+    ("citeAs", Shape::LangText, false),
+    ("creator", Shape::TextsOrObjects(Node::Agent), false),
+    ("publisher", Shape::TextsOrObjects(Node::Agent), false),
+    ("datePublished", Shape::Text, false),
+    ("dateCreated", Shape::Text, false),
+    ("dateModified", Shape::Text, false),
+    ("license", Shape::TextsOrObjects(Node::CreativeWork), false),
+    ("url", Shape::Text, false),
+    ("sameAs", Shape::Texts, false),
+    ("keywords", Shape::LangTexts, false),
+    ("inLanguage", Shape::Texts, false),
+    ("isLiveDataset", Shape::Bool, false),
+    ("version", Shape::Text, true),
+    ("distribution", Shape::Objects(Node::Distribution), true),
+    ("recordSet", Shape::Objects(Node::RecordSet), true),
+    ("rai:dataCollection", Shape::Text, false),
+    ("rai:dataCollectionType", Shape::Texts, false),
+    ("rai:dataCollectionMissingData", Shape::Text, false),
+    ("rai:dataCollectionRawData", Shape::Text, false),
+    ("rai:dataCollectionTimeframe", Shape::Texts, false),
+    ("rai:dataImputationProtocol", Shape::Text, false),
+    ("rai:dataManipulationProtocol", Shape::Text, false),
+    ("rai:dataPreprocessingProtocol", Shape::Texts, false),
+    ("rai:dataAnnotationProtocol", Shape::Text, false),
+    ("rai:dataAnnotationPlatform", Shape::Texts, false),
+    ("rai:dataAnnotationAnalysis", Shape::Texts, false),
+    ("rai:annotationsPerItem", Shape::Text, false),
+    ("rai:annotatorDemographics", Shape::Texts, false),
+    ("rai:machineAnnotationTools", Shape::Texts, false),
+    ("rai:personalSensitiveInformation", Shape::Texts, false),
+    ("rai:dataBiases", Shape::Texts, false),
+    ("rai:dataLimitations", Shape::Texts, false),
+    ("rai:dataUseCases", Shape::Texts, false),
+    ("rai:dataSocialImpact", Shape::Text, false),
+    ("rai:dataReleaseMaintenancePlan", Shape::Text, false),
+];
+
+const AGENT: &[Property] = &[
+    ("@type", Shape::Text, false),
+    ("name", Shape::Text, true),
+    ("email", Shape::Text, false),
+    ("url", Shape::Text, false),
+];
+
+const CREATIVE_WORK: &[Property] = &[
+    ("@type", Shape::Text, false),
+    ("name", Shape::Text, false),
+    ("url", Shape::Text, false),
+];
+
+const DISTRIBUTION: &[Property] = &[("@type", Shape::Text, true)];
+
+const FILE_OBJECT: &[Property] = &[
+    ("@id", Shape::Text, true),
+    ("name", Shape::Text, true),
+    ("description", Shape::LangText, false),
+    ("contentUrl", Shape::Text, true),
+    ("contentSize", Shape::Text, false),
+    ("encodingFormat", Shape::Text, true),
+    ("dcat:compressFormat", Shape::Text, false),
+    ("csvw:dialect", Shape::Object(Node::Dialect), false),
+    ("sha256", Shape::Text, false),
+    ("md5", Shape::Text, false),
+];
+
+const FILE_SET: &[Property] = &[
+    ("@id", Shape::Text, true),
+    ("name", Shape::Text, false),
+    ("description", Shape::LangText, false),
+    ("containedIn", Shape::IdRefs, false),
+    ("encodingFormat", Shape::Text, true),
+    ("includes", Shape::Texts, true),
+    ("excludes", Shape::Texts, false),
+    ("csvw:dialect", Shape::Object(Node::Dialect), false),
+    ("sha256", Shape::Text, false),
+];
+
+const DIALECT: &[Property] = &[
+    ("csvw:encoding", Shape::Text, false),
+    ("csvw:delimiter", Shape::Text, false),
+    ("csvw:quoteChar", Shape::Text, false),
+    ("csvw:doubleQuote", Shape::Bool, false),
+    ("csvw:header", Shape::Bool, false),
+    ("csvw:lineTerminators", Shape::Text, false),
+    ("csvw:commentPrefix", Shape::Text, false),
+];
+
+const RECORD_SET: &[Property] = &[
+    ("@type", Shape::Text, true),
+    ("@id", Shape::Text, true),
+    ("name", Shape::Text, false),
+    ("description", Shape::LangText, false),
+    ("dataType", Shape::Texts, false),
+    ("key", Shape::Refs, false),
+    ("field", Shape::Objects(Node::Field), true),
+    ("data", Shape::Records, false),
+];
+
+const FIELD: &[Property] = &[
+    ("@type", Shape::Text, true),
+    ("@id", Shape::Text, true),
+    ("name", Shape::Text, true),
+    ("description", Shape::LangText, true),
+    ("dataType", Shape::Texts, false),
+    ("references", Shape::Objects(Node::FieldRef), false),
+    ("source", Shape::Object(Node::Source), false),
+    ("subField", Shape::Objects(Node::Field), false),
+    ("parentField", Shape::IdRefs, false),
+    ("repeated", Shape::Bool, false),
+    ("equivalentProperty", Shape::Texts, false),
+];
+
+const SOURCE: &[Property] = &[
+    ("fileObject", Shape::Ref, false),
+    ("fileSet", Shape::Ref, false),
+    ("recordSet", Shape::Ref, false),
+    ("extract", Shape::Object(Node::Extract), false),
+    ("transform", Shape::Objects(Node::Transform), false),
+    ("format", Shape::Object(Node::Format), false),
+    ("csvw:null", Shape::Texts, false),
+];
+
+const EXTRACT: &[Property] = &[
+    ("column", Shape::Text, false),
+    ("fileProperty", Shape::Text, false),
+    ("jsonPath", Shape::Text, false),
+];
+
+const TRANSFORM: &[Property] = &[
+    ("regex", Shape::Text, false),
+    ("delimiter", Shape::Text, false),
+    ("jsonQuery", Shape::Text, false),
+];
+
+const FORMAT: &[Property] = &[
+    ("kind", Shape::Text, true),
+    ("pattern", Shape::Text, false),
+    ("format", Shape::Text, false),
+];
+
+const FIELD_REF: &[Property] = &[("field", Shape::Ref, true)];
+
+/// Check the structure of a normalized document
+///
+/// Problems that would prevent reading the document into the typed model,
+/// and fields with neither a `dataType` nor `subField`s, are reported as
+/// errors at the JSON path of the offending node or property.
+pub fn check_structure(document: &Value) -> ValidationReport {
+    let mut checker = Checker {
+        report: ValidationReport::new(),
+    };
+    match document {
+        Value::Object(dataset) => {
+            let location = match dataset.get("name").and_then(Value::as_str) {
+                Some(name) => format!("Metadata({name})"),
+                None => "Metadata".to_string(),
+            };
+            checker.node(Node::Dataset, dataset, &location, "$");
+        }
+        _ => checker.error(
+            codes::SCHEMA,
+            "The document is not a JSON object.",
+            "Metadata",
+            "$",
+        ),
+    }
+    checker.report
+}
+
+struct Checker {
+    report: ValidationReport,
+}
+
+impl Checker {
+    fn error(&mut self, code: &str, message: impl Into<String>, location: &str, path: &str) {
+        self.report
+            .push(Severity::Error, code, message, location, path);
+    }
+
+    fn node(&mut self, kind: Node, node: &Map<String, Value>, parent: &str, path: &str) {
+        let id = node.get("@id").and_then(Value::as_str).unwrap_or_default();
+        let node_type = node.get("@type").and_then(Value::as_str);
+        let (properties, expected_types, location): (_, &[&str], _) = match kind {
+            Node::Dataset => (DATASET, &["sc:Dataset"], parent.to_string()),
+            Node::Agent => (AGENT, &["sc:Person", "sc:Organization"], parent.to_string()),
+            Node::CreativeWork => (CREATIVE_WORK, &[], parent.to_string()),
+            Node::Distribution => match node_type {
+                Some("cr:FileObject") => return self.node(Node::FileObject, node, parent, path),
+                Some("cr:FileSet") => return self.node(Node::FileSet, node, parent, path),
+                _ => (
+                    DISTRIBUTION,
+                    &["cr:FileObject", "cr:FileSet"],
+                    parent.to_string(),
+                ),
+            },
+            Node::FileObject => (FILE_OBJECT, &[], format!("{parent} > FileObject({id})")),
+            Node::FileSet => (FILE_SET, &[], format!("{parent} > FileSet({id})")),
+            Node::Dialect => (DIALECT, &[], parent.to_string()),
+            Node::RecordSet => (
+                RECORD_SET,
+                &["cr:RecordSet"],
+                format!("{parent} > RecordSet({id})"),
+            ),
+            Node::Field => (FIELD, &["cr:Field"], format!("{parent} > Field({id})")),
+            Node::Source => (SOURCE, &[], parent.to_string()),
+            Node::Extract => (EXTRACT, &[], parent.to_string()),
+            Node::Transform => (TRANSFORM, &[], parent.to_string()),
+            Node::Format => (FORMAT, &[], parent.to_string()),
+            Node::FieldRef => (FIELD_REF, &[], parent.to_string()),
+        };
+
+        if let Some(node_type) = node_type
+            && !expected_types.is_empty()
+            && !expected_types.contains(&node_type)
+        {
+            let expected = expected_types
+                .iter()
+                .map(|t| format!("\"{t}\""))
+                .collect::<Vec<_>>()
+                .join(" or ");
+            self.error(
+                codes::SCHEMA,
+                format!("\"{node_type}\" is not a valid @type, expected {expected}."),
+                &location,
+                &format!("{path}.@type"),
+            );
+        }
+
+        for (name, shape, mandatory) in properties {
+            let key = property_key(node, name);
+            let property_path = format!("{path}.{}", key.unwrap_or(name));
+            match key.and_then(|key| node.get(key)) {
+                None | Some(Value::Null) if *mandatory => self.error(
+                    codes::REQUIRED_PROPERTY,
+                    format!(
+                        "Property \"{}\" is mandatory, but does not exist.",
+                        jsonld::property_iri(name)
+                    ),
+                    &location,
+                    path,
+                ),
+                None | Some(Value::Null) => {}
+                Some(value) => self.value(*shape, name, value, &location, &property_path),
+            }
+        }
+
+        self.check_alternatives(kind, node, &location, path);
+    }
+
+    /// Constraints between the properties of a node
+    fn check_alternatives(
+        &mut self,
+        kind: Node,
+        node: &Map<String, Value>,
+        location: &str,
+        path: &str,
+    ) {
+        let alternatives: &[&str] = match kind {
+            Node::Source => &["fileObject", "fileSet", "recordSet"],
+            Node::Extract => &["column", "fileProperty", "jsonPath"],
+            Node::Transform => &["regex", "delimiter", "jsonQuery"],
+            _ => &[],
+        };
+        let present = alternatives
+            .iter()
+            .filter(|name| node.contains_key(**name))
+            .count();
+        if !alternatives.is_empty() && present != 1 {
+            let names = alternatives
+                .iter()
+                .map(|name| format!("\"{}\"", jsonld::property_iri(name)))
+                .collect::<Vec<_>>()
+                .join(", ");
+            let (code, message) = match present {
+                0 => (
+                    codes::REQUIRED_PROPERTY,
+                    format!("One of the properties {names} is mandatory, but none exists."),
+                ),
+                _ => (
+                    codes::SCHEMA,
+                    format!("Only one of the properties {names} can be given."),
+                ),
+            };
+            self.error(code, message, location, path);
+        }
+
+        if kind == Node::Field && !node.contains_key("dataType") && !node.contains_key("subField") {
+            self.error(
+                codes::REQUIRED_PROPERTY,
+                format!(
+                    "Property \"{}\" is mandatory for fields without subField, but does not exist.",
+                    jsonld::property_iri("dataType")
+                ),
+                location,
+                path,
+            );
+        }
+        if kind == Node::Extract
+            && let Some(Value::String(property)) = node.get("fileProperty")
+            && !matches!(
+                property.as_str(),
+                "fullpath" | "filename" | "content" | "lines" | "lineNumbers"
+            )
+        {
+            self.error(
+                codes::SCHEMA,
+                format!(
+                    "\"{property}\" is not a file property, expected \"fullpath\", \
+                     \"filename\", \"content\", \"lines\" or \"lineNumbers\"."
+                ),
+                location,
+                &format!("{path}.fileProperty"),
+            );
+        }
+        if kind == Node::Format
+            && let Some(Value::String(format)) = node.get("kind")
+            && !matches!(format.as_str(), "date" | "number" | "bbox")
+        {
+            self.error(
+                codes::SCHEMA,
+                format!(
+                    "\"{format}\" is not a value format, expected \"date\", \"number\" or \"bbox\"."
+                ),
+                location,
+                &format!("{path}.kind"),
+            );
+        }
+    }
+
+    fn value(&mut self, shape: Shape, name: &str, value: &Value, location: &str, path: &str) {
+        let problem = |value: &Value, verb: &str| {
+            format!(
+                "Property \"{}\" must be {}, but {verb} {}.",
+                jsonld::property_iri(name),
+                shape.describe(),
+                describe(value)
+            )
+        };
+        let valid_item = |item: &Value| match shape {
+            Shape::Texts => item.is_string(),
+            Shape::LangText | Shape::LangTexts => is_lang_string(item),
+            Shape::Objects(_) | Shape::Records => item.is_object(),
+            Shape::TextsOrObjects(_) => item.is_string() || item.is_object(),
+            Shape::Refs => is_ref(item),
+            Shape::IdRefs => item.is_string() || is_ref(item),
+            Shape::Text => item.is_string(),
+            Shape::Bool => item.is_boolean(),
+            Shape::Object(_) => item.is_object(),
+            Shape::Ref => is_ref(item),
+        };
+        match shape {
+            Shape::Text | Shape::LangText | Shape::Bool | Shape::Object(_) | Shape::Ref => {
+                if !valid_item(value) {
+                    self.error(codes::SCHEMA, problem(value, "is"), location, path);
+                } else if let (Shape::Object(node), Value::Object(map)) = (shape, value) {
+                    self.node(node, map, location, path);
+                }
+            }
+            // Lists are indexed in paths even when a single value stands for them
+            _ => {
+                for (i, item) in items(value) {
+                    let item_path = format!("{path}[{i}]");
+                    if !valid_item(item) {
+                        let verb = if value.is_array() { "holds" } else { "is" };
+                        self.error(codes::SCHEMA, problem(item, verb), location, &item_path);
+                    } else if let (
+                        Shape::Objects(node) | Shape::TextsOrObjects(node),
+                        Value::Object(map),
+                    ) = (shape, item)
+                        && !is_lang_string(item)
+                    {
+                        self.node(node, map, location, &item_path);
+                    }
+                }
+            }
+        }
+    }
+}
+
+/// Key under which a node holds a property, prefixed properties of
+/// vocabularies the document does not declare being written as full IRIs
+fn property_key<'a>(node: &'a Map<String, Value>, name: &'a str) -> Option<&'a str> {
+    if node.contains_key(name) || !name.starts_with("csvw:") && !name.starts_with("dcat:") {
+        return Some(name);
+    }
+    node.keys()
+        .map(String::as_str)
+        .find(|key| *key == jsonld::property_iri(name))
+        .or(Some(name))
+}
+
+/// A string, or a string tagged with its language (`{"@value": ..., "@language": ...}`)
+fn is_lang_string(value: &Value) -> bool {
+    match value {
+        Value::String(_) => true,
+        Value::Object(map) => {
+            map.get("@value").is_some_and(Value::is_string)
+                && map.keys().all(|key| key == "@value" || key == "@language")
+        }
+        _ => false,
+    }
+}
+
+/// Values of a property holding one value or a list, with their list index
+fn items(value: &Value) -> Vec<(usize, &Value)> {
+    match value {
+        Value::Array(items) => items.iter().enumerate().collect(),
+        value => vec![(0, value)],
+    }
+}
+
+fn is_ref(value: &Value) -> bool {
+    value.get("@id").is_some_and(Value::is_string)
+}
+
+/// Kind of a JSON value, for messages
+fn describe(value: &Value) -> &'static str {
+    match value {
+        Value::Null => "null",
+        Value::Bool(_) => "a boolean",
+        Value::Number(_) => "a number",
+        Value::String(_) => "a string",
+        Value::Array(_) => "a list",
+        Value::Object(map) if map.contains_key("@value") => "a typed or language-tagged value",
+        Value::Object(_) => "an object",
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn paths(report: &ValidationReport) -> Vec<(&str, &str)> {
+        report
+            .issues
+            .iter()
+            .map(|issue| (issue.code.as_str(), issue.path.as_str()))
+            .collect()
+    }
+
+    fn dataset(record_set: Value) -> Value {
+        json!({
+            "@type": "sc:Dataset",
+            "name": "test",
+            "description": "A test dataset",
+            "version": "1.0.0",
+            "distribution": [{
+                "@type": "cr:FileObject",
+                "@id": "data.csv",
+                "name": "data.csv",
+                "contentUrl": "data.csv",
+                "encodingFormat": "text/csv"
+            }],
+            "recordSet": record_set
+        })
+    }
+
+    #[test]
+    fn valid_document_has_no_issue() {
+        let document = dataset(json!({
+            "@type": "cr:RecordSet",
+            "@id": "main",
+            "field": {
+                "@type": "cr:Field",
+                "@id": "main/a",
+                "name": "a",
+                "description": "A",
+                "dataType": "sc:Integer",
+                "source": {
+                    "fileObject": { "@id": "data.csv" },
+                    "extract": { "column": "a" }
+                }
+            }
+        }));
+        assert!(check_structure(&document).issues.is_empty());
+    }
+
+    #[test]
+    fn every_problem_is_reported_at_its_path() {
+        let mut document = dataset(json!([{
+            "@type": "cr:RecordSet",
+            "@id": "main",
+            "field": [
+                { "@type": "cr:Field", "@id": "main/a", "name": "a", "dataType": "sc:Text" },
+                {
+                    "@type": "cr:Field",
+                    "@id": "main/b",
+                    "name": 2,
+                    "description": "B",
+                    "dataType": "sc:Text",
+                    "source": { "extract": { "fileProperty": "size" } }
+                }
+            ]
+        }]));
+        document["@type"] = json!("sc:Thing");
+        document["rai:dataBiases"] = json!(["none", { "a": 1 }]);
+        document["distribution"][0]
+            .as_object_mut()
+            .unwrap()
+            .remove("contentUrl");
+
+        let report = check_structure(&document);
+        assert_eq!(
+            paths(&report),
+            vec![
+                (codes::SCHEMA, "$.@type"),
+                (codes::REQUIRED_PROPERTY, "$.distribution[0]"),
+                (codes::REQUIRED_PROPERTY, "$.recordSet[0].field[0]"),
+                (codes::SCHEMA, "$.recordSet[0].field[1].name"),
+                (
+                    codes::SCHEMA,
+                    "$.recordSet[0].field[1].source.extract.fileProperty"
+                ),
+                (codes::REQUIRED_PROPERTY, "$.recordSet[0].field[1].source"),
+                (codes::SCHEMA, "$.rai:dataBiases[1]"),
+            ]
+        );
+        assert!(report.has_errors());
+    }
+
+    #[test]
+    fn fields_need_a_data_type_or_sub_fields() {
+        let document = dataset(json!({
+            "@type": "cr:RecordSet",
+            "@id": "main",
+            "field": { "@type": "cr:Field", "@id": "main/a", "name": "a", "description": "A" }
+        }));
+        let report = check_structure(&document);
+        assert_eq!(
+            paths(&report),
+            vec![(codes::REQUIRED_PROPERTY, "$.recordSet[0].field[0]")]
+        );
+        assert!(report.issues[0].message.contains("croissant/dataType"));
+    }
+
+    #[test]
+    fn only_natural_language_properties_take_language_tags() {
+        let tagged = json!({ "@value": "Âge", "@language": "fr" });
+        let mut document = dataset(json!({
+            "@type": "cr:RecordSet",
+            "@id": "main",
+            "field": {
+                "@type": "cr:Field",
+                "@id": "main/a",
+                "name": tagged,
+                "description": tagged,
+                "dataType": "sc:Integer",
+            }
+        }));
+        document["keywords"] = json!(["age", tagged]);
+        let report = check_structure(&document);
+        assert_eq!(
+            paths(&report),
+            vec![(codes::SCHEMA, "$.recordSet[0].field[0].name")]
+        );
+    }
+}
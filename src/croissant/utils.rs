@@ -1,6 +1,10 @@
 //! Utility functions for file operations and CSV processing
 
+use crate::croissant::compression::open_decompressed;
+use crate::croissant::core::CsvDialect;
+use crate::croissant::dialect::open_csv;
 use crate::croissant::errors::{Error, Result};
+use md5::Md5;
 use sha2::{Digest, Sha256};
 use std::fs::File;
 use std::io::{BufReader, Read};
@@ -9,8 +13,28 @@ use std::path::Path;
 /// Calculate the SHA-256 hash of a file
 pub fn calculate_sha256(file_path: &Path) -> Result<String> {
     let file = File::open(file_path).map_err(|_| Error::file_not_found(file_path))?;
-    let mut reader = BufReader::new(file);
-    let mut hasher = Sha256::new();
+    sha256_reader(file)
+}
+
+/// Calculate the SHA-256 hash of everything a reader yields
+pub fn sha256_reader(reader: impl Read) -> Result<String> {
+    digest_reader::<Sha256>(reader)
+}
+
+/// Calculate MD5 hash of a file
+pub fn calculate_md5(file_path: &Path) -> Result<String> {
+    let file = File::open(file_path).map_err(|_| Error::file_not_found(file_path))?;
+    md5_reader(file)
+}
+
+/// Calculate the MD5 hash of everything a reader yields
+pub fn md5_reader(reader: impl Read) -> Result<String> {
+    digest_reader::<Md5>(reader)
+}
+
+fn digest_reader<D: Digest>(reader: impl Read) -> Result<String> {
+    let mut reader = BufReader::new(reader);
+    let mut hasher = D::new();
     let mut buffer = [0; 8192];
 
     loop {
@@ -24,17 +48,29 @@ pub fn calculate_sha256(file_path: &Path) -> Result<String> {
     Ok(hex::encode(hasher.finalize()))
 }
 
+/// SHA-256 of the `sha256sum`-style listing of a set of files
+///
+/// Entries are `(relative path, SHA-256)` pairs, in any order.
+pub fn aggregate_sha256<'a>(entries: impl IntoIterator<Item = (&'a str, &'a str)>) -> String {
+    let mut lines = entries
+        .into_iter()
+        .map(|(path, digest)| format!("{digest}  {path}\n"))
+        .collect::<Vec<_>>();
+    lines.sort();
+
+    let mut hasher = Sha256::new();
+    for line in lines {
+        hasher.update(line.as_bytes());
+    }
+    hex::encode(hasher.finalize())
+}
+
 /// Get CSV column headers and optionally the first data row
+///
+/// Compressed files are decompressed on the fly, see [`open_decompressed`],
+/// and the dialect of the file is detected, see [`open_csv`].
 pub fn get_csv_columns(csv_path: &Path) -> Result<(Vec<String>, Option<Vec<String>>)> {
-    let file = File::open(csv_path).map_err(|_| Error::file_not_found(csv_path))?;
-    let mut reader = csv::Reader::from_reader(file);
-
-    // Read headers
-    let headers = reader
-        .headers()?
-        .iter()
-        .map(|h| h.trim().to_string())
-        .collect::<Vec<String>>();
+    let (_, mut reader, headers) = open_csv(open_decompressed(csv_path)?, &CsvDialect::default())?;
 
     // Try to read the first data row for type inference
     let first_row = if let Some(result) = reader.records().next() {
@@ -55,12 +91,12 @@ pub fn get_csv_columns(csv_path: &Path) -> Result<(Vec<String>, Option<Vec<Strin
 /// Validate if the given path is a valid output file path
 pub fn validate_output_path(output_path: &Path) -> Result<()> {
     // Check if the parent directory exists or can be created
-    if let Some(parent) = output_path.parent() {
-        if !parent.exists() {
-            std::fs::create_dir_all(parent).map_err(|e| {
-                Error::invalid_output_path(output_path, format!("Cannot create directory: {e}"))
-            })?;
-        }
+    if let Some(parent) = output_path.parent()
+        && !parent.exists()
+    {
+        std::fs::create_dir_all(parent).map_err(|e| {
+            Error::invalid_output_path(output_path, format!("Cannot create directory: {e}"))
+        })?;
     }
 
     // Check if we can write to the file by creating a temporary file
@@ -115,20 +151,48 @@ pub fn get_file_extension(path: &Path) -> Option<String> {
         .map(|ext| ext.to_lowercase())
 }
 
+/// Guess the encoding format (MIME type) of a file from its extension
+pub fn encoding_format_for_extension(extension: &str) -> &'static str {
+    match extension.to_lowercase().as_str() {
+        "csv" => "text/csv",
+        "tsv" => "text/tab-separated-values",
+        "txt" => "text/plain",
+        "json" => "application/json",
+        "jsonl" | "ndjson" => "application/jsonlines",
+        "parquet" => "application/x-parquet",
+        "zip" => "application/zip",
+        "tar" => "application/x-tar",
+        "gz" => "application/gzip",
+        "jpg" | "jpeg" => "image/jpeg",
+        "png" => "image/png",
+        "gif" => "image/gif",
+        "bmp" => "image/bmp",
+        "tif" | "tiff" => "image/tiff",
+        "webp" => "image/webp",
+        "wav" => "audio/wav",
+        "mp3" => "audio/mpeg",
+        "flac" => "audio/flac",
+        "ogg" => "audio/ogg",
+        "mp4" => "video/mp4",
+        "webm" => "video/webm",
+        _ => "application/octet-stream",
+    }
+}
+
 /// Validate CSV file format by attempting to read headers
+///
+/// The dialect of the file is detected first; files without a header row
+/// get generated column names and always pass.
 pub fn validate_csv_format(csv_path: &Path) -> Result<()> {
-    let file = File::open(csv_path).map_err(|_| Error::file_not_found(csv_path))?;
-    let mut reader = csv::Reader::from_reader(file);
+    let (_, _, headers) = open_csv(open_decompressed(csv_path)?, &CsvDialect::default())?;
 
-    // Try to read headers
-    let headers = reader.headers()?;
     if headers.is_empty() {
         return Err(Error::invalid_format("CSV file has no headers".to_string()));
     }
 
     // Check for duplicate headers
     let mut seen_headers = std::collections::HashSet::new();
-    for header in headers.iter() {
+    for header in &headers {
         let trimmed = header.trim();
         if trimmed.is_empty() {
             return Err(Error::invalid_format(
@@ -144,3 +208,68 @@ pub fn validate_csv_format(csv_path: &Path) -> Result<()> {
 
     Ok(())
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn digests_of_files_and_file_sets() {
+        let directory = tempfile::tempdir().unwrap();
+        let path = directory.path().join("hello.txt");
+        std::fs::write(&path, "hello\n").unwrap();
+        let sha256 = "5891b5b522d5df086d0ff0b110fbd9d21bb4fc7163af34d08286a2e846f6be03";
+        assert_eq!(calculate_sha256(&path).unwrap(), sha256);
+        assert_eq!(
+            calculate_md5(&path).unwrap(),
+            "b1946ac92492d2347c6235b4d2611184"
+        );
+        assert!(calculate_sha256(&directory.path().join("missing")).is_err());
+
+        // Same as `sha256sum hello.txt | sha256sum`
+        let listing = format!("{sha256}  hello.txt\n");
+        assert_eq!(
+            aggregate_sha256([("hello.txt", sha256)]),
+            sha256_reader(listing.as_bytes()).unwrap()
+        );
+        assert_eq!(
+            aggregate_sha256([("b", "2"), ("a", "1")]),
+            aggregate_sha256([("a", "1"), ("b", "2")])
+        );
+    }
+
+    #[test]
+    fn csv_headers_are_checked() {
+        let directory = tempfile::tempdir().unwrap();
+        let path = directory.path().join("rows.csv");
+        std::fs::write(&path, "id, name\n1, Ada\n").unwrap();
+        let (headers, first_row) = get_csv_columns(&path).unwrap();
+        assert_eq!(headers.len(), 2);
+        assert_eq!(first_row, Some(vec!["1".to_string(), "Ada".to_string()]));
+        assert!(validate_csv_format(&path).is_ok());
+
+        std::fs::write(&path, "id,name,ID\n1,Ada,2\n").unwrap();
+        let error = validate_csv_format(&path).unwrap_err();
+        assert!(error.to_string().contains("duplicate header: ID"));
+    }
+
+    #[test]
+    fn sizes_and_extensions_are_described() {
+        assert_eq!(format_file_size(512), "512 B");
+        assert_eq!(format_file_size(1536), "1.5 KB");
+        assert_eq!(format_file_size(5 * 1024 * 1024), "5.0 MB");
+        assert_eq!(
+            get_file_extension(Path::new("data/Rows.CSV")).as_deref(),
+            Some("csv")
+        );
+        assert_eq!(get_file_extension(Path::new("README")), None);
+        assert_eq!(
+            encoding_format_for_extension("JSONL"),
+            "application/jsonlines"
+        );
+        assert_eq!(
+            encoding_format_for_extension("xyz"),
+            "application/octet-stream"
+        );
+    }
+}
@@ -1,14 +1,420 @@
 //! Validation logic for Croissant metadata
-use crate::croissant::core::Metadata;
+use crate::croissant::core::{Metadata, Resource, SpecVersion};
 use crate::croissant::errors::{Error, Result};
+use crate::croissant::report::{Severity, ValidationReport, codes};
+use crate::croissant::{data, jsonld, jsonpath, migrate, references, schema};
 use std::path::Path;
 
+/// Options controlling validation
+#[derive(Debug, Clone, Default)]
+pub struct ValidateOptions {
+    /// Report missing mandatory dataset properties (`license`, `url` and
+    /// `creator`) as warnings instead of errors, e.g. for drafts
+    pub lenient: bool,
+}
+
 /// Validate a Croissant metadata file
-pub fn validate_file(file_path: &Path) -> Result<()> {
+///
+/// Only I/O failures are returned as errors; problems with the document
+/// itself are collected in the returned [`ValidationReport`].
+pub fn validate_file(file_path: &Path) -> Result<ValidationReport> {
+    validate_file_with_options(file_path, &ValidateOptions::default())
+}
+
+/// Validate a Croissant metadata file with custom options, see [`validate_file`]
+pub fn validate_file_with_options(
+    file_path: &Path,
+    options: &ValidateOptions,
+) -> Result<ValidationReport> {
     let content =
         std::fs::read_to_string(file_path).map_err(|_| Error::file_not_found(file_path))?;
 
-    let metadata: Metadata = serde_json::from_str(&content)?;
-    metadata.check()?;
-    Ok(())
+    Ok(validate_str_with_options(&content, options))
+}
+
+/// Validate a Croissant metadata document held in memory
+pub fn validate_str(content: &str) -> ValidationReport {
+    validate_str_with_options(content, &ValidateOptions::default())
+}
+
+/// Validate a Croissant metadata document held in memory with custom options
+pub fn validate_str_with_options(content: &str, options: &ValidateOptions) -> ValidationReport {
+    let mut report = ValidationReport::new();
+
+    let value: serde_json::Value = match serde_json::from_str(content) {
+        Ok(value) => value,
+        Err(e) => {
+            report.push(
+                Severity::Error,
+                codes::JSON_SYNTAX,
+                e.to_string(),
+                "Metadata",
+                "$",
+            );
+            if let Some(issue) = report.issues.last_mut() {
+                issue.line = Some(e.line());
+                // serde_json reports column 0 at the end of a line
+                issue.column = Some(e.column().max(1));
+            }
+            return report;
+        }
+    };
+
+    let location = match value.get("name").and_then(|n| n.as_str()) {
+        Some(name) => format!("Metadata({name})"),
+        None => "Metadata".to_string(),
+    };
+    let version = migrate::detect_version(&value);
+    let mut document = match jsonld::normalize(value) {
+        Ok(document) => document,
+        Err(e) => {
+            report.push(
+                Severity::Error,
+                codes::JSON_LD,
+                e.to_string(),
+                location,
+                "$.@context",
+            );
+            return locate_issues(report, content);
+        }
+    };
+    if version < SpecVersion::V1_0 {
+        migrate::upgrade(&mut document, version, SpecVersion::V1_0);
+    }
+
+    // Every structural problem is reported before the typed model is built,
+    // which would only report the first one
+    let structure = schema::check_structure(&document);
+    if structure.has_errors() {
+        return locate_issues(structure, content);
+    }
+    let metadata: Metadata = match serde_path_to_error::deserialize(document) {
+        Ok(metadata) => metadata,
+        Err(e) => {
+            let json_path = match e.path().to_string() {
+                path if path == "." => "$".to_string(),
+                path => format!("$.{path}"),
+            };
+            report.push(
+                Severity::Error,
+                codes::SCHEMA,
+                e.inner().to_string(),
+                location,
+                json_path,
+            );
+            return locate_issues(report, content);
+        }
+    };
+
+    let mut report = validate_metadata_with_options(&metadata, options);
+    if version < SpecVersion::V1_0 {
+        report.push(
+            Severity::Warning,
+            codes::DEPRECATED_VERSION,
+            format!(
+                "The document conforms to Croissant {version} and was read by migrating it to 1.0; \
+                 rewrite it with `migrate`"
+            ),
+            location,
+            "$.@context",
+        );
+    }
+    locate_issues(report, content)
+}
+
+/// Set the line and column of issues from their JSON path in the document
+fn locate_issues(mut report: ValidationReport, content: &str) -> ValidationReport {
+    for issue in &mut report.issues {
+        if let Some((line, column)) = jsonpath::locate(content, &issue.path) {
+            issue.line = Some(line);
+            issue.column = Some(column);
+        }
+    }
+    report
+}
+
+/// Validate an already parsed Croissant document
+pub fn validate_metadata(metadata: &Metadata) -> ValidationReport {
+    validate_metadata_with_options(metadata, &ValidateOptions::default())
+}
+
+/// Validate an already parsed Croissant document with custom options
+pub fn validate_metadata_with_options(
+    metadata: &Metadata,
+    options: &ValidateOptions,
+) -> ValidationReport {
+    let mut report = ValidationReport::new();
+
+    if let Err(garde_report) = metadata.check_constraints() {
+        for (path, error) in garde_report.iter() {
+            let components = path_components(path);
+            let (location, json_path, property) = locate(metadata, &components);
+            let (code, message) = match property.as_deref() {
+                None => (codes::CONSTRAINT, error.message().to_string()),
+                Some(property) => (
+                    codes::CONSTRAINT,
+                    format!("Property \"{property}\": {}", error.message()),
+                ),
+            };
+            report.push(Severity::Error, code, message, location, json_path);
+        }
+    }
+
+    report.extend(references::check_references(metadata));
+    report.extend(data::check_inline_data(metadata));
+
+    let root = format!("Metadata({})", metadata.name.0);
+    let required = [
+        ("https://schema.org/license", metadata.license.is_empty()),
+        ("https://schema.org/url", metadata.url.is_none()),
+        ("https://schema.org/creator", metadata.creator.is_empty()),
+    ];
+    for (property, missing) in required {
+        if missing {
+            report.push(
+                match options.lenient {
+                    true => Severity::Warning,
+                    false => Severity::Error,
+                },
+                codes::REQUIRED_PROPERTY,
+                format!("Property \"{property}\" is mandatory, but does not exist."),
+                root.as_str(),
+                "$",
+            );
+        }
+    }
+
+    let recommended = [
+        (
+            Severity::Warning,
+            "http://purl.org/dc/terms/conformsTo",
+            metadata.conforms_to.is_none(),
+        ),
+        (
+            Severity::Info,
+            "https://schema.org/datePublished",
+            metadata.date_published.is_none(),
+        ),
+        (
+            Severity::Info,
+            "http://mlcommons.org/croissant/citeAs",
+            metadata.cite_as.is_none(),
+        ),
+    ];
+    for (severity, property, missing) in recommended {
+        if missing {
+            report.push(
+                severity,
+                codes::RECOMMENDED_PROPERTY,
+                format!("Property \"{property}\" is recommended, but does not exist."),
+                root.as_str(),
+                "$",
+            );
+        }
+    }
+
+    report
+}
+
+/// Components of a garde path, outermost first
+fn path_components(path: &garde::Path) -> Vec<String> {
+    let Ok(serde_json::Value::Array(components)) = serde_json::to_value(path) else {
+        return Vec::new();
+    };
+    components
+        .iter()
+        .filter_map(|c| c.get(1).and_then(|v| v.as_str()).map(str::to_string))
+        .collect()
+}
+
+/// Resolve garde path components into a node location, a JSON path and the
+/// name of the offending property, if any
+fn locate(metadata: &Metadata, components: &[String]) -> (String, String, Option<String>) {
+    let mut location = vec![format!("Metadata({})", metadata.name.0)];
+    let mut json_path = String::from("$");
+    let index = |i: usize| components.get(i).and_then(|c| c.parse::<usize>().ok());
+
+    let mut i = 0;
+    let mut record_set = None;
+    let mut fields = None;
+    while i < components.len() {
+        let key = components[i].as_str();
+        match (key, index(i + 1)) {
+            ("distribution", Some(n)) if record_set.is_none() => {
+                match metadata.distribution.get(n).map(|d| &d.resource) {
+                    Some(Resource::FileObject(o)) => {
+                        location.push(format!("FileObject({})", o.id.0))
+                    }
+                    Some(Resource::FileSet(s)) => location.push(format!("FileSet({})", s.id.0)),
+                    None => {}
+                }
+                json_path.push_str(&format!(".distribution[{n}]"));
+            }
+            // Distribution entries wrap the file object or file set
+            ("resource", Some(_)) => {}
+            ("record_sets", Some(n)) if record_set.is_none() => {
+                if let Some(rs) = metadata.record_sets.get(n) {
+                    location.push(format!("RecordSet({})", rs.id.0));
+                    fields = Some(&rs.fields);
+                    record_set = Some(rs);
+                }
+                json_path.push_str(&format!(".recordSet[{n}]"));
+            }
+            ("fields" | "sub_fields", Some(n)) if fields.is_some() => {
+                let field = fields.and_then(|f| f.get(n));
+                if let Some(field) = field {
+                    location.push(format!("Field({})", field.id.0));
+                }
+                fields = field.and_then(|f| f.sub_fields.as_ref());
+                let json_key = if key == "fields" { "field" } else { "subField" };
+                json_path.push_str(&format!(".{json_key}[{n}]"));
+            }
+            // Responsible AI properties are flattened into the dataset
+            ("rai", _) if i + 1 < components.len() => {
+                let property = format!("rai:{}", camel_case(&components[i + 1]));
+                json_path.push_str(&format!(".{property}"));
+                return (location.join(" > "), json_path, Some(property));
+            }
+            _ => {
+                let property = json_key(key);
+                json_path.push_str(&format!(".{property}"));
+                return (location.join(" > "), json_path, Some(property.to_string()));
+            }
+        }
+        i += 2;
+    }
+
+    (location.join(" > "), json_path, None)
+}
+
+/// `lowerCamelCase` form of a `snake_case` model field name
+fn camel_case(rust_name: &str) -> String {
+    let mut words = rust_name.split('_');
+    let mut name = words.next().unwrap_or_default().to_string();
+    for word in words {
+        let mut chars = word.chars();
+        if let Some(first) = chars.next() {
+            name.extend(first.to_uppercase());
+            name.push_str(chars.as_str());
+        }
+    }
+    name
+}
+
+/// JSON-LD property name of a model field
+fn json_key(rust_name: &str) -> &str {
+    match rust_name {
+        "context" => "@context",
+        "kind" => "@type",
+        "id" => "@id",
+        "conforms_to" => "conformsTo",
+        "cite_as" => "citeAs",
+        "date_published" => "datePublished",
+        "date_created" => "dateCreated",
+        "date_modified" => "dateModified",
+        "same_as" => "sameAs",
+        "in_language" => "inLanguage",
+        "is_live_dataset" => "isLiveDataset",
+        "record_sets" => "recordSet",
+        "content_url" => "contentUrl",
+        "content_size" => "contentSize",
+        "encoding_format" => "encodingFormat",
+        "sources" => "containedIn",
+        "data_types" | "record_types" => "dataType",
+        "keys" => "key",
+        "fields" => "field",
+        "sub_fields" => "subField",
+        "parent_fields" => "parentField",
+        "equivalent_properties" => "equivalentProperty",
+        other => other,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn structural_problems_are_all_reported_with_their_line() {
+        let report = validate_str(include_str!("../../samples_jsonld/missing_fields.jsonld"));
+        let issues = report
+            .issues
+            .iter()
+            .map(|issue| (issue.path.as_str(), issue.line))
+            .collect::<Vec<_>>();
+        assert_eq!(
+            issues,
+            vec![
+                ("$.@type", Some(26)),
+                ("$.distribution[0]", Some(32)),
+                ("$.recordSet[0].field[0]", Some(48)),
+                ("$.recordSet[0].field[0]", Some(48)),
+            ]
+        );
+    }
+
+    #[test]
+    fn missing_dataset_properties_are_errors_unless_lenient() {
+        let content = include_str!("../../samples_jsonld/titanic.jsonld");
+        let required = |report: &ValidationReport| {
+            report
+                .issues
+                .iter()
+                .filter(|issue| issue.code == codes::REQUIRED_PROPERTY)
+                .map(|issue| issue.severity)
+                .collect::<Vec<_>>()
+        };
+        let strict = validate_str(content);
+        assert_eq!(required(&strict), vec![Severity::Error]);
+        let options = ValidateOptions { lenient: true };
+        let lenient = validate_str_with_options(content, &options);
+        assert_eq!(required(&lenient), vec![Severity::Warning]);
+    }
+
+    #[test]
+    fn creators_may_be_untyped_and_licenses_creative_works() {
+        let document = serde_json::json!({
+            "@context": {"@vocab": "https://schema.org/", "cr": "http://mlcommons.org/croissant/"},
+            "@type": "sc:Dataset",
+            "name": "test",
+            "description": "test",
+            "version": "1.0.0",
+            "url": "https://example.org/test",
+            "creator": ["Jane Doe", {"name": "ACME"}],
+            "license": {
+                "@type": "sc:CreativeWork",
+                "name": "Academic Free License 3.0",
+                "url": "https://opensource.org/licenses/AFL-3.0",
+            },
+            "distribution": [],
+            "recordSet": [{
+                "@type": "cr:RecordSet",
+                "@id": "main",
+                "name": "main",
+                "description": "main",
+                "field": [{
+                    "@type": "cr:Field",
+                    "@id": "main/a",
+                    "name": "a",
+                    "description": "A",
+                    "dataType": "sc:Text",
+                }],
+                "data": [{"main/a": "x"}],
+            }],
+        });
+        let report = validate_str(&document.to_string());
+        assert!(!report.has_errors(), "{:?}", report.issues);
+    }
+
+    #[test]
+    fn sample_documents_are_valid() {
+        let options = ValidateOptions { lenient: true };
+        for content in [
+            include_str!("../../samples_jsonld/titanic.jsonld"),
+            include_str!("../../samples_jsonld/test1.jsonld"),
+        ] {
+            let report = validate_str_with_options(content, &options);
+            assert!(!report.has_errors(), "{:?}", report.issues);
+        }
+    }
 }
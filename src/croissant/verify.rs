@@ -0,0 +1,451 @@
+//! Verification of distributions against local copies of their files
+use crate::croissant::archive::sha256_all;
+use crate::croissant::core::{FileObject, FileSet, Metadata, Resource};
+use crate::croissant::errors::{Error, Result};
+use crate::croissant::records::Dataset;
+use crate::croissant::report::{Severity, ValidationReport, codes};
+use crate::croissant::utils::{aggregate_sha256, calculate_md5, calculate_sha256};
+use std::path::{Path, PathBuf};
+use url::Url;
+
+/// Verify the distributions of a Croissant metadata file
+///
+/// Content URLs are resolved against `data_dir`, or the directory of the
+/// metadata file when it is `None`. Remote files are only verified with a
+/// `data_dir`, where they are looked up as `<data_dir>/<host>/<path>`, then
+/// `<data_dir>/<file name>`. Only failures to read the metadata are returned
+/// as errors; missing, modified or truncated files are collected in the
+/// returned [`ValidationReport`].
+pub fn verify_file(metadata_path: &Path, data_dir: Option<&Path>) -> Result<ValidationReport> {
+    let dataset = Dataset::from_file(metadata_path)?;
+    Ok(match data_dir {
+        Some(dir) => verify_dataset(&Dataset::new(dataset.metadata().clone(), dir), true),
+        None => verify_dataset(&dataset, false),
+    })
+}
+
+/// Verify the distributions of a parsed document against the files under `data_dir`
+///
+/// Remote files are looked up under `data_dir` as in [`verify_file`].
+pub fn verify_metadata(metadata: &Metadata, data_dir: impl AsRef<Path>) -> ValidationReport {
+    verify_dataset(&Dataset::new(metadata.clone(), data_dir.as_ref()), true)
+}
+
+fn verify_dataset(dataset: &Dataset, local_copies: bool) -> ValidationReport {
+    let mut report = ValidationReport::new();
+    let root = format!("Metadata({})", dataset.metadata().name.0);
+    for (i, distribution) in dataset.metadata().distribution.iter().enumerate() {
+        let json_path = format!("$.distribution[{i}]");
+        match &distribution.resource {
+            Resource::FileObject(object) => {
+                let location = format!("{root} > FileObject({})", object.id.0);
+                if let Some(path) = local_path(
+                    dataset,
+                    object,
+                    local_copies,
+                    &location,
+                    &json_path,
+                    &mut report,
+                ) {
+                    verify_file_object(object, &path, &location, &json_path, &mut report);
+                }
+            }
+            Resource::FileSet(set) => {
+                let location = format!("{root} > FileSet({})", set.id.0);
+                verify_file_set(dataset, set, &location, &json_path, &mut report);
+            }
+        }
+    }
+    report
+}
+
+/// Path of the local copy of a file object, `None` when it cannot be verified
+fn local_path(
+    dataset: &Dataset,
+    object: &FileObject,
+    local_copies: bool,
+    location: &str,
+    json_path: &str,
+    report: &mut ValidationReport,
+) -> Option<PathBuf> {
+    let content_url = &object.content_url.0;
+    if !content_url.contains("://") {
+        return match dataset.resolve(content_url) {
+            Ok(path) => Some(path),
+            Err(Error::Unsupported(reason)) => {
+                report.push(
+                    Severity::Info,
+                    codes::NOT_VERIFIED,
+                    format!("File was not verified: {reason}."),
+                    location,
+                    json_path,
+                );
+                None
+            }
+            Err(e) => {
+                report.push(
+                    Severity::Error,
+                    codes::FILE_MISSING,
+                    e.to_string(),
+                    location,
+                    json_path,
+                );
+                None
+            }
+        };
+    }
+
+    let candidates = Url::parse(content_url)
+        .ok()
+        .filter(|_| local_copies)
+        .and_then(|url| {
+            let host = url.host_str()?.to_string();
+            let path = url.path().trim_start_matches('/').to_string();
+            let name = path.rsplit('/').next().filter(|name| !name.is_empty())?;
+            Some([format!("{host}/{path}"), name.to_string()])
+        })
+        .into_iter()
+        .flatten()
+        .filter_map(|candidate| dataset.resolve(&candidate).ok())
+        .collect::<Vec<_>>();
+    if let Some(path) = candidates.iter().find(|path| path.exists()) {
+        return Some(path.clone());
+    }
+    if candidates.is_empty() {
+        report.push(
+            Severity::Info,
+            codes::NOT_VERIFIED,
+            format!("Remote file \"{content_url}\" was not verified."),
+            location,
+            json_path,
+        );
+    } else {
+        let tried = candidates
+            .iter()
+            .map(|path| format!("\"{}\"", path.display()))
+            .collect::<Vec<_>>();
+        report.push(
+            Severity::Error,
+            codes::FILE_MISSING,
+            format!(
+                "Remote file \"{content_url}\" has no local copy at {}.",
+                tried.join(" or ")
+            ),
+            location,
+            json_path,
+        );
+    }
+    None
+}
+
+fn verify_file_object(
+    object: &FileObject,
+    path: &Path,
+    location: &str,
+    json_path: &str,
+    report: &mut ValidationReport,
+) {
+    let content_url = &object.content_url.0;
+    if path.is_dir() {
+        return;
+    }
+    let Ok(file_info) = std::fs::metadata(path) else {
+        report.push(
+            Severity::Error,
+            codes::FILE_MISSING,
+            format!("File \"{}\" does not exist.", path.display()),
+            location,
+            json_path,
+        );
+        return;
+    };
+
+    if let Some(content_size) = &object.content_size {
+        let actual = file_info.len();
+        match content_size_matches(&content_size.0, actual) {
+            Some(false) => {
+                report.push(
+                    Severity::Error,
+                    codes::SIZE_MISMATCH,
+                    format!(
+                        "File \"{content_url}\" is {actual} B, but its contentSize is \"{}\".",
+                        content_size.0
+                    ),
+                    location,
+                    format!("{json_path}.contentSize"),
+                );
+            }
+            Some(true) => {}
+            None => report.push(
+                Severity::Warning,
+                codes::CONSTRAINT,
+                format!("Cannot parse contentSize \"{}\".", content_size.0),
+                location,
+                format!("{json_path}.contentSize"),
+            ),
+        }
+    }
+
+    let checksums = [
+        (
+            "sha256",
+            object.sha256.as_deref(),
+            calculate_sha256 as fn(&Path) -> _,
+        ),
+        ("md5", object.md5.as_deref(), calculate_md5),
+    ];
+    for (property, expected, calculate) in checksums {
+        let Some(expected) = expected else {
+            continue;
+        };
+        match calculate(path) {
+            Ok(actual) if actual.eq_ignore_ascii_case(expected) => {}
+            Ok(actual) => report.push(
+                Severity::Error,
+                codes::CHECKSUM_MISMATCH,
+                format!(
+                    "File \"{content_url}\" was modified: its {property} is {actual}, expected {expected}."
+                ),
+                location,
+                format!("{json_path}.{property}"),
+            ),
+            Err(e) => report.push(
+                Severity::Error,
+                codes::FILE_MISSING,
+                e.to_string(),
+                location,
+                json_path,
+            ),
+        }
+    }
+}
+
+fn verify_file_set(
+    dataset: &Dataset,
+    set: &FileSet,
+    location: &str,
+    json_path: &str,
+    report: &mut ValidationReport,
+) {
+    let files = match dataset.list_files(set) {
+        Ok(files) => files,
+        Err(Error::Unsupported(reason)) => {
+            report.push(
+                Severity::Info,
+                codes::NOT_VERIFIED,
+                format!("File set was not verified: {reason}."),
+                location,
+                json_path,
+            );
+            return;
+        }
+        Err(e) => {
+            report.push(
+                Severity::Error,
+                codes::FILE_MISSING,
+                e.to_string(),
+                location,
+                json_path,
+            );
+            return;
+        }
+    };
+    if files.is_empty() {
+        report.push(
+            Severity::Error,
+            codes::FILE_MISSING,
+            "No file matches the includes of the file set.",
+            location,
+            format!("{json_path}.includes"),
+        );
+        return;
+    }
+
+    let Some(expected) = &set.sha256 else {
+        return;
+    };
+    let locations = files.iter().map(|(l, _)| l.clone()).collect::<Vec<_>>();
+    let digests = match sha256_all(&locations) {
+        Ok(digests) => digests,
+        Err(e) => {
+            report.push(
+                Severity::Error,
+                codes::FILE_MISSING,
+                e.to_string(),
+                location,
+                json_path,
+            );
+            return;
+        }
+    };
+    let actual = aggregate_sha256(
+        files
+            .iter()
+            .zip(&digests)
+            .map(|((_, relative), digest)| (relative.as_str(), digest.as_str())),
+    );
+    if !actual.eq_ignore_ascii_case(expected) {
+        report.push(
+            Severity::Error,
+            codes::CHECKSUM_MISMATCH,
+            format!(
+                "Files of the file set were added, removed or modified: their sha256 is {actual}, expected {expected}."
+            ),
+            location,
+            format!("{json_path}.sha256"),
+        );
+    }
+}
+
+/// Whether a `contentSize` describes `actual` bytes, `None` when it cannot be parsed
+///
+/// Sizes with a unit only have to match up to the rounding of their value.
+/// `KB`, `MB`, ... are accepted as powers of 1000 or of 1024, `KiB`, `MiB`,
+/// ... as powers of 1024.
+fn content_size_matches(text: &str, actual: u64) -> Option<bool> {
+    let text = text.trim();
+    let split = text
+        .find(|c: char| !(c.is_ascii_digit() || c == '.'))
+        .unwrap_or(text.len());
+    let (digits, unit) = text.split_at(split);
+    let number = digits.parse::<f64>().ok()?;
+    let (exponent, decimal, binary) = match unit.trim() {
+        "" | "B" | "bytes" => return Some(number == actual as f64),
+        "kB" => (1, true, false),
+        "KB" => (1, true, true),
+        "KiB" => (1, false, true),
+        "MB" => (2, true, true),
+        "MiB" => (2, false, true),
+        "GB" => (3, true, true),
+        "GiB" => (3, false, true),
+        "TB" => (4, true, true),
+        "TiB" => (4, false, true),
+        _ => return None,
+    };
+    let decimals = digits.split_once('.').map_or(0, |(_, d)| d.len() as i32);
+    let precision = 0.5 * 10f64.powi(-decimals);
+    let matches = |base: f64| {
+        let value = actual as f64 / base.powi(exponent);
+        (value - number).abs() <= precision
+    };
+    Some((decimal && matches(1000.0)) || (binary && matches(1024.0)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::croissant::jsonld;
+    use crate::croissant::utils::{md5_reader, sha256_reader};
+    use serde_json::json;
+
+    const ROWS: &str = "id,name\n1,Ada\n2,Grace\n";
+    const GENDERS: &str = "label\nfemale\nmale\n";
+
+    /// Metadata describing `data/rows.csv` and a remote file
+    fn metadata() -> Metadata {
+        jsonld::parse_metadata(json!({
+            "@context": {"@vocab": "https://schema.org/", "cr": "http://mlcommons.org/croissant/"},
+            "@type": "sc:Dataset",
+            "name": "test",
+            "description": "test",
+            "version": "1.0.0",
+            "distribution": [
+                {
+                    "@type": "cr:FileObject",
+                    "@id": "rows.csv",
+                    "name": "rows.csv",
+                    "contentUrl": "data/rows.csv",
+                    "contentSize": format!("{} B", ROWS.len()),
+                    "encodingFormat": "text/csv",
+                    "sha256": sha256_reader(ROWS.as_bytes()).unwrap(),
+                },
+                {
+                    "@type": "cr:FileObject",
+                    "@id": "genders.csv",
+                    "name": "genders.csv",
+                    "contentUrl": "https://example.org/files/genders.csv?download=1",
+                    "encodingFormat": "text/csv",
+                    "md5": md5_reader(GENDERS.as_bytes()).unwrap(),
+                },
+            ],
+            "recordSet": [],
+        }))
+        .unwrap()
+    }
+
+    fn codes(report: &ValidationReport) -> Vec<&str> {
+        report.issues.iter().map(|i| i.code.as_str()).collect()
+    }
+
+    #[test]
+    fn unchanged_files_pass() {
+        let directory = tempfile::tempdir().unwrap();
+        let root = directory.path();
+        std::fs::create_dir_all(root.join("data")).unwrap();
+        std::fs::write(root.join("data/rows.csv"), ROWS).unwrap();
+        std::fs::create_dir_all(root.join("example.org/files")).unwrap();
+        std::fs::write(root.join("example.org/files/genders.csv"), GENDERS).unwrap();
+
+        let report = verify_metadata(&metadata(), root);
+        assert!(report.issues.is_empty(), "{report}");
+    }
+
+    #[test]
+    fn remote_files_are_only_verified_with_a_data_dir() {
+        let directory = tempfile::tempdir().unwrap();
+        let root = directory.path();
+        std::fs::create_dir(root.join("data")).unwrap();
+        std::fs::write(root.join("data/rows.csv"), ROWS).unwrap();
+        let metadata_path = root.join("metadata.json");
+        std::fs::write(&metadata_path, serde_json::to_string(&metadata()).unwrap()).unwrap();
+
+        let report = verify_file(&metadata_path, None).unwrap();
+        assert_eq!(codes(&report), [codes::NOT_VERIFIED]);
+        assert_eq!(report.issues[0].path, "$.distribution[1]");
+
+        let report = verify_file(&metadata_path, Some(root)).unwrap();
+        assert_eq!(codes(&report), [codes::FILE_MISSING]);
+        assert_eq!(report.issues[0].path, "$.distribution[1]");
+
+        std::fs::write(root.join("genders.csv"), GENDERS).unwrap();
+        let report = verify_file(&metadata_path, Some(root)).unwrap();
+        assert!(report.issues.is_empty(), "{report}");
+    }
+
+    #[test]
+    fn modified_and_missing_files_are_errors() {
+        let directory = tempfile::tempdir().unwrap();
+        let root = directory.path();
+        let report = verify_metadata(&metadata(), root);
+        assert_eq!(codes(&report), [codes::FILE_MISSING, codes::FILE_MISSING]);
+
+        std::fs::create_dir(root.join("data")).unwrap();
+        std::fs::write(root.join("data/rows.csv"), "id,name\n1,Ada\n").unwrap();
+        std::fs::write(root.join("genders.csv"), "label\nfemale\n").unwrap();
+        let report = verify_metadata(&metadata(), root);
+        assert_eq!(
+            codes(&report),
+            [
+                codes::SIZE_MISMATCH,
+                codes::CHECKSUM_MISMATCH,
+                codes::CHECKSUM_MISMATCH
+            ]
+        );
+        assert_eq!(report.issues[0].path, "$.distribution[0].contentSize");
+        assert_eq!(report.issues[1].path, "$.distribution[0].sha256");
+        assert_eq!(report.issues[2].path, "$.distribution[1].md5");
+    }
+
+    #[test]
+    fn content_sizes_match_up_to_their_rounding() {
+        assert_eq!(content_size_matches("117743 B", 117743), Some(true));
+        assert_eq!(content_size_matches("117743", 117742), Some(false));
+        assert_eq!(content_size_matches("117.7 kB", 117743), Some(true));
+        assert_eq!(content_size_matches("115 KiB", 117743), Some(true));
+        assert_eq!(content_size_matches("115 KB", 117743), Some(true));
+        assert_eq!(content_size_matches("115 kB", 117743), Some(false));
+        assert_eq!(content_size_matches("1.5 MB", 1_500_000), Some(true));
+        assert_eq!(content_size_matches("large", 1), None);
+        assert_eq!(content_size_matches("3 parsecs", 1), None);
+    }
+}
@@ -1,2 +1,12 @@
+//! Tools for generating and validating [Croissant](https://github.com/mlcommons/croissant)
+//! metadata.
+//!
+//! The Croissant data model is exposed through [`croissant`]; the most common
+//! types are also available from [`prelude`].
 pub mod croissant;
 pub mod version;
+
+pub use croissant::prelude;
+pub use croissant::{
+    DataType, Distribution, Error, Field, FileObject, FileSet, Metadata, RecordSet, Result,
+};
@@ -17,10 +17,17 @@ fn main() {
         )
         .subcommand(
             Command::new("generate")
-                .about("Generate Croissant metadata from a CSV file")
+                .about("Generate Croissant metadata from a CSV file, a Parquet file, a directory or an archive of files")
+                .long_about("Generate Croissant metadata from a CSV file, a Parquet file, a directory or an archive of files\n\n\
+                    Zip, tar and tar.gz archives are described as they are distributed, by file sets contained in them. \
+                    Several CSV files are described as related tables: each gets its own record set, \
+                    whose key and references to the other tables are detected from the data.\n\n\
+                    The delimiter, quoting, encoding, comment lines and header row of CSV files are detected \
+                    and recorded as a csvw:dialect; the dialect options below take precedence over detection")
                 .arg(clap::Arg::new("input")
-                    .help("Input CSV file")
+                    .help("Input CSV file, Parquet file, directory or archive, or several related CSV files")
                     .required(true)
+                    .num_args(1..)
                     .index(1)
                 )
                 .arg(clap::Arg::new("output")
@@ -30,15 +37,272 @@ fn main() {
                     .required(false)
                     .value_name("FILE")
                 )
+                .arg(clap::Arg::new("max-rows")
+                    .long("max-rows")
+                    .help("Maximum number of rows read for type inference (default: whole file)")
+                    .required(false)
+                    .value_name("N")
+                    .value_parser(clap::value_parser!(usize))
+                )
+                .arg(clap::Arg::new("max-categories")
+                    .long("max-categories")
+                    .help("Most distinct values of a text column described as an enumeration, 0 to disable (default: 20)")
+                    .required(false)
+                    .value_name("N")
+                    .value_parser(clap::value_parser!(usize))
+                )
+                .arg(clap::Arg::new("max-category-ratio")
+                    .long("max-category-ratio")
+                    .help("Highest ratio of distinct to non-null values of an enumeration column (default: 0.5)")
+                    .required(false)
+                    .value_name("RATIO")
+                    .value_parser(clap::value_parser!(f64))
+                )
+                .arg(clap::Arg::new("checksums")
+                    .long("checksums")
+                    .help("How checksums of the files of a directory or archive are recorded")
+                    .required(false)
+                    .value_name("MODE")
+                    .value_parser(["aggregate", "per-file", "none"])
+                    .default_value("aggregate")
+                )
+                .arg(clap::Arg::new("config")
+                    .long("config")
+                    .help("TOML, YAML or JSON file of dataset properties and column overrides merged over the inferred metadata")
+                    .required(false)
+                    .value_name("FILE")
+                )
+                .arg(clap::Arg::new("license")
+                    .long("license")
+                    .help("License of the dataset, a URL or SPDX identifier; may be repeated (overrides --config)")
+                    .required(false)
+                    .value_name("LICENSE")
+                    .action(clap::ArgAction::Append)
+                )
+                .arg(clap::Arg::new("url")
+                    .long("url")
+                    .help("URL of the dataset (overrides --config)")
+                    .required(false)
+                    .value_name("URL")
+                )
+                .arg(clap::Arg::new("creator")
+                    .long("creator")
+                    .help("Name of a person who created the dataset; may be repeated (overrides --config)")
+                    .required(false)
+                    .value_name("NAME")
+                    .action(clap::ArgAction::Append)
+                )
+                .arg(clap::Arg::new("rai")
+                    .long("rai")
+                    .help("JSON file of Responsible AI properties (dataCollection, dataBiases, ...) added to the dataset")
+                    .required(false)
+                    .value_name("FILE")
+                )
+                .arg(clap::Arg::new("delimiter")
+                    .long("delimiter")
+                    .help("Delimiter of CSV files, a single character or \\t (tab)")
+                    .required(false)
+                    .value_name("CHAR")
+                )
+                .arg(clap::Arg::new("quote")
+                    .long("quote")
+                    .help("Quote character of CSV files")
+                    .required(false)
+                    .value_name("CHAR")
+                )
+                .arg(clap::Arg::new("escape")
+                    .long("escape")
+                    .help("How quotes are escaped in CSV files: doubled (\"\") or with a backslash (\\\")")
+                    .required(false)
+                    .value_name("STYLE")
+                    .value_parser(["double", "backslash"])
+                )
+                .arg(clap::Arg::new("header")
+                    .long("header")
+                    .help("Whether the first row of CSV files holds the column names")
+                    .required(false)
+                    .value_name("BOOL")
+                    .value_parser(clap::value_parser!(bool))
+                )
+                .arg(clap::Arg::new("encoding")
+                    .long("encoding")
+                    .help("Character encoding of CSV files (utf-8, latin1, windows-1252, utf-16le, ...)")
+                    .required(false)
+                    .value_name("LABEL")
+                )
+                .arg(clap::Arg::new("comment")
+                    .long("comment")
+                    .help("Prefix of comment lines in CSV files")
+                    .required(false)
+                    .value_name("CHAR")
+                )
         )
         .subcommand(
             Command::new("validate")
                 .about("Validate a Croissant JSON-LD metadata file")
+                .long_about("Validate a Croissant JSON-LD metadata file\n\n\
+                    With --data, the records of every record set are also read and checked \
+                    against their dataType, keys and references.\n\n\
+                    Exit codes: 0 when the document is valid, 1 when errors were found, \
+                    2 when only warnings were found and --warnings-as-errors is set, \
+                    3 when the input cannot be read or is not well-formed JSON")
                 .arg(clap::Arg::new("input")
                     .help("Input JSON-LD file to validate")
                     .required(true)
                     .index(1)
                 )
+                .arg(clap::Arg::new("format")
+                    .long("format")
+                    .help("Output format of the report")
+                    .required(false)
+                    .value_name("FORMAT")
+                    .value_parser(["text", "json", "sarif", "junit"])
+                    .default_value("text")
+                )
+                .arg(clap::Arg::new("warnings-as-errors")
+                    .long("warnings-as-errors")
+                    .help("Exit with status 2 when warnings are found")
+                    .action(clap::ArgAction::SetTrue)
+                )
+                .arg(clap::Arg::new("lenient")
+                    .long("lenient")
+                    .help("Report missing license, url and creator as warnings instead of errors")
+                    .action(clap::ArgAction::SetTrue)
+                )
+                .arg(clap::Arg::new("data")
+                    .long("data")
+                    .help("Also read the data files and check their values against the record sets")
+                    .action(clap::ArgAction::SetTrue)
+                )
+                .arg(clap::Arg::new("data-dir")
+                    .long("data-dir")
+                    .help("Directory content URLs are resolved against with --data (default: the metadata file's directory)")
+                    .required(false)
+                    .value_name("DIR")
+                    .requires("data")
+                )
+        )
+        .subcommand(
+            Command::new("verify")
+                .about("Check the files of a dataset against the checksums and sizes of its metadata")
+                .arg(clap::Arg::new("input")
+                    .help("Input JSON-LD metadata file")
+                    .required(true)
+                    .index(1)
+                )
+                .arg(clap::Arg::new("data-dir")
+                    .long("data-dir")
+                    .help("Directory content URLs are resolved against (default: the metadata file's directory); remote files are looked up as DIR/<host>/<path> or DIR/<file name>")
+                    .required(false)
+                    .value_name("DIR")
+                )
+        )
+        .subcommand(
+            Command::new("convert")
+                .about("Convert Croissant metadata to RDF")
+                .arg(clap::Arg::new("input")
+                    .help("Input JSON-LD metadata file")
+                    .required(true)
+                    .index(1)
+                )
+                .arg(clap::Arg::new("to")
+                    .long("to")
+                    .help("RDF serialization to produce")
+                    .required(false)
+                    .value_name("FORMAT")
+                    .value_parser(["turtle", "ntriples", "nquads"])
+                    .default_value("turtle")
+                )
+                .arg(clap::Arg::new("output")
+                    .short('o')
+                    .long("output")
+                    .help("Output file (default: standard output)")
+                    .required(false)
+                    .value_name("FILE")
+                )
+                .arg(clap::Arg::new("base")
+                    .long("base")
+                    .help("IRI relative @ids are resolved against (default: the dataset url)")
+                    .required(false)
+                    .value_name("IRI")
+                )
+                .arg(clap::Arg::new("graph")
+                    .long("graph")
+                    .help("Graph name of N-Quads statements (default: the dataset IRI)")
+                    .required(false)
+                    .value_name("IRI")
+                )
+        )
+        .subcommand(
+            Command::new("migrate")
+                .about("Migrate Croissant metadata to a newer version of the specification")
+                .long_about("Migrate Croissant metadata to a newer version of the specification\n\n\
+                    Deprecated constructs are rewritten and every change is listed on the standard error")
+                .arg(clap::Arg::new("input")
+                    .help("Input JSON-LD metadata file")
+                    .required(true)
+                    .index(1)
+                )
+                .arg(clap::Arg::new("to")
+                    .long("to")
+                    .help("Version of the specification to migrate to")
+                    .required(false)
+                    .value_name("VERSION")
+                    .value_parser(["1.0", "1.1"])
+                    .default_value("1.1")
+                )
+                .arg(clap::Arg::new("output")
+                    .short('o')
+                    .long("output")
+                    .help("Output file (default: standard output)")
+                    .required(false)
+                    .value_name("FILE")
+                )
+        )
+        .subcommand(
+            Command::new("fmt")
+                .about("Rewrite Croissant metadata files in a canonical form")
+                .long_about("Rewrite Croissant metadata files in a canonical form\n\n\
+                    Properties are ordered as in the specification examples, single values are \
+                    written without an array, the @context is normalized and unknown properties are kept")
+                .arg(clap::Arg::new("input")
+                    .help("Input JSON-LD metadata files")
+                    .required(true)
+                    .num_args(1..)
+                    .index(1)
+                )
+                .arg(clap::Arg::new("check")
+                    .long("check")
+                    .help("List the files that are not formatted instead of rewriting them, and exit with an error if any")
+                    .action(clap::ArgAction::SetTrue)
+                )
+        )
+        .subcommand(
+            Command::new("records")
+                .about("Print the records of a record set as JSON lines")
+                .arg(clap::Arg::new("input")
+                    .help("Input JSON-LD metadata file")
+                    .required(true)
+                    .index(1)
+                )
+                .arg(clap::Arg::new("record-set")
+                    .help("Id of the record set to read")
+                    .required(true)
+                    .index(2)
+                )
+                .arg(clap::Arg::new("data-dir")
+                    .long("data-dir")
+                    .help("Directory content URLs are resolved against (default: the metadata file's directory)")
+                    .required(false)
+                    .value_name("DIR")
+                )
+                .arg(clap::Arg::new("limit")
+                    .long("limit")
+                    .help("Maximum number of records to print")
+                    .required(false)
+                    .value_name("N")
+                    .value_parser(clap::value_parser!(usize))
+                )
         );
 
     // Parse arguments and handle commands
@@ -53,31 +317,125 @@ fn main() {
             println!("Built on: {}", v.build_time);
         }
         Some(("generate", sub_m)) => {
-            let input = sub_m
-                .get_one::<String>("input")
-                .expect("Input CSV required");
+            let inputs = sub_m
+                .get_many::<String>("input")
+                .expect("Input path required")
+                .map(std::path::Path::new)
+                .collect::<Vec<_>>();
             let output = sub_m.get_one::<String>("output");
-            let input_path = std::path::Path::new(input);
             let output_path = output.map(std::path::Path::new);
 
             // Validate output path if provided
-            if let Some(out_path) = output_path {
-                if let Err(e) = rustcroissant::croissant::utils::validate_output_path(out_path) {
-                    eprintln!("Invalid output path: {e}");
-                    std::process::exit(1);
+            if let Some(out_path) = output_path
+                && let Err(e) = rustcroissant::croissant::validate_output_path(out_path)
+            {
+                eprintln!("Invalid output path: {e}");
+                std::process::exit(1);
+            }
+
+            let mut options = rustcroissant::croissant::GenerateOptions::default();
+            options.inference.max_rows = sub_m.get_one::<usize>("max-rows").copied();
+            if let Some(max) = sub_m.get_one::<usize>("max-categories") {
+                options.inference.max_categories = *max;
+            }
+            if let Some(ratio) = sub_m.get_one::<f64>("max-category-ratio") {
+                options.inference.max_category_ratio = *ratio;
+            }
+            let dialect = &mut options.inference.dialect;
+            let character = |name: &str| {
+                sub_m
+                    .get_one::<String>(name)
+                    .map(|value| match value.as_str() {
+                        "\\t" | "tab" => "\t".to_string(),
+                        value => value.to_string(),
+                    })
+            };
+            dialect.delimiter = character("delimiter");
+            dialect.quote_char = character("quote");
+            dialect.comment_prefix = character("comment");
+            dialect.double_quote = sub_m
+                .get_one::<String>("escape")
+                .map(|escape| escape == "double");
+            dialect.header = sub_m.get_one::<bool>("header").copied();
+            dialect.encoding = sub_m.get_one::<String>("encoding").cloned();
+            if let Err(e) = dialect
+                .character_encoding()
+                .and_then(|_| dialect.reader_builder())
+            {
+                eprintln!("Invalid CSV dialect: {e}");
+                std::process::exit(1);
+            }
+            options.checksums = match sub_m.get_one::<String>("checksums").map(String::as_str) {
+                Some("per-file") => rustcroissant::croissant::ChecksumMode::PerFile,
+                Some("none") => rustcroissant::croissant::ChecksumMode::None,
+                _ => rustcroissant::croissant::ChecksumMode::Aggregate,
+            };
+            if let Some(config) = sub_m.get_one::<String>("config") {
+                match rustcroissant::croissant::GenerateConfig::from_file(std::path::Path::new(
+                    config,
+                )) {
+                    Ok(config) => options.config = config,
+                    Err(e) => {
+                        eprintln!("Invalid configuration file: {e}");
+                        std::process::exit(1);
+                    }
+                }
+            }
+            if let Some(licenses) = sub_m.get_many::<String>("license") {
+                options.config.license = licenses.cloned().collect();
+            }
+            if let Some(url) = sub_m.get_one::<String>("url") {
+                options.config.url = Some(url.clone());
+            }
+            if let Some(creators) = sub_m.get_many::<String>("creator") {
+                options.config.creators = creators
+                    .map(|name| rustcroissant::croissant::CreatorConfig {
+                        name: name.clone(),
+                        email: None,
+                        url: None,
+                        kind: rustcroissant::croissant::CreatorKind::Person,
+                    })
+                    .collect();
+            }
+            if let Some(rai) = sub_m.get_one::<String>("rai") {
+                match rustcroissant::croissant::read_rai_file(std::path::Path::new(rai)) {
+                    Ok(rai) => options.rai = rai,
+                    Err(e) => {
+                        eprintln!("Invalid Responsible AI file: {e}");
+                        std::process::exit(1);
+                    }
                 }
             }
 
-            match rustcroissant::croissant::generate::generate_metadata_from_csv(
-                input_path,
-                output_path,
-            ) {
-                Ok(_) => {
+            let result = match inputs.as_slice() {
+                [input_path] => {
+                    rustcroissant::croissant::generate_metadata(input_path, output_path, &options)
+                }
+                _ => rustcroissant::croissant::generate_metadata_from_csv_files(
+                    &inputs,
+                    output_path,
+                    &options,
+                ),
+            };
+            match result {
+                Ok(metadata) => {
                     if let Some(o) = output {
                         println!("Croissant metadata generated and saved to: {o}");
                     } else {
                         println!("Croissant metadata generated.");
                     }
+                    // `validate` rejects documents without these properties
+                    let missing = [
+                        ("license", "--license", metadata.license.is_empty()),
+                        ("url", "--url", metadata.url.is_none()),
+                        ("creator", "--creator", metadata.creator.is_empty()),
+                    ];
+                    for (property, flag, _) in missing.iter().filter(|(.., missing)| *missing) {
+                        eprintln!(
+                            "Warning: no {property} given, which validate reports as an error; \
+                             set it with {flag} or in a --config file"
+                        );
+                    }
                 }
                 Err(e) => {
                     eprintln!("Error generating metadata: {e}");
@@ -90,12 +448,200 @@ fn main() {
                 .get_one::<String>("input")
                 .expect("Input JSON-LD file required");
             let input_path = std::path::Path::new(input);
+            let format = sub_m
+                .get_one::<String>("format")
+                .map(|f| f.parse().expect("Format checked by clap"))
+                .unwrap_or_default();
+
+            let options = rustcroissant::croissant::ValidateOptions {
+                lenient: sub_m.get_flag("lenient"),
+            };
+            let unreadable = |e: rustcroissant::croissant::Error| {
+                let mut report = rustcroissant::croissant::ValidationReport::new();
+                report.push(
+                    rustcroissant::croissant::Severity::Error,
+                    rustcroissant::croissant::codes::INPUT_READ,
+                    e.to_string(),
+                    "Metadata",
+                    "$",
+                );
+                report
+            };
+            let mut report =
+                rustcroissant::croissant::validate_file_with_options(input_path, &options)
+                    .unwrap_or_else(unreadable);
+            // Data can only be read through a valid document
+            if sub_m.get_flag("data") && !report.has_errors() {
+                let data_dir = sub_m
+                    .get_one::<String>("data-dir")
+                    .map(std::path::Path::new);
+                match rustcroissant::croissant::validate_data_file(input_path, data_dir) {
+                    Ok(data_report) => report.extend(data_report),
+                    Err(e) => report.extend(unreadable(e)),
+                }
+            }
+            match report.render(format, input) {
+                Ok(output) => print!("{output}"),
+                Err(e) => {
+                    eprintln!("Error rendering report: {e}");
+                    std::process::exit(3);
+                }
+            }
+            if report.is_unreadable() {
+                std::process::exit(3);
+            }
+            if report.has_errors() {
+                std::process::exit(1);
+            }
+            if sub_m.get_flag("warnings-as-errors") && report.has_warnings() {
+                std::process::exit(2);
+            }
+        }
+        Some(("verify", sub_m)) => {
+            let input = sub_m
+                .get_one::<String>("input")
+                .expect("Input JSON-LD file required");
+            let data_dir = sub_m
+                .get_one::<String>("data-dir")
+                .map(std::path::Path::new);
 
-            match rustcroissant::croissant::validate::validate_file(input_path) {
-                Ok(validate) => println!("{:?}", validate),
+            match rustcroissant::croissant::verify_file(std::path::Path::new(input), data_dir) {
+                Ok(report) => {
+                    print!("{report}");
+                    if report.has_errors() {
+                        std::process::exit(1);
+                    }
+                }
                 Err(e) => {
-                    eprintln!("Error validating metadata: {e}");
+                    eprintln!("Error verifying dataset: {e}");
+                    std::process::exit(1);
+                }
+            }
+        }
+        Some(("convert", sub_m)) => {
+            let input = sub_m
+                .get_one::<String>("input")
+                .expect("Input JSON-LD file required");
+            let format = sub_m
+                .get_one::<String>("to")
+                .map(|f| f.parse().expect("Format checked by clap"))
+                .unwrap_or_default();
+            let options = rustcroissant::croissant::RdfOptions {
+                base: sub_m.get_one::<String>("base").cloned(),
+                graph: sub_m.get_one::<String>("graph").cloned(),
+            };
+
+            let result = rustcroissant::croissant::convert_file(
+                std::path::Path::new(input),
+                format,
+                &options,
+            )
+            .and_then(|rdf| match sub_m.get_one::<String>("output") {
+                Some(output) => Ok(std::fs::write(output, rdf)?),
+                None => {
+                    print!("{rdf}");
+                    Ok(())
+                }
+            });
+            if let Err(e) = result {
+                eprintln!("Error converting metadata: {e}");
+                std::process::exit(1);
+            }
+        }
+        Some(("migrate", sub_m)) => {
+            let input = sub_m
+                .get_one::<String>("input")
+                .expect("Input JSON-LD file required");
+            let to = sub_m
+                .get_one::<String>("to")
+                .map(|v| v.parse().expect("Version checked by clap"))
+                .unwrap_or(rustcroissant::croissant::SpecVersion::LATEST);
+
+            let result = rustcroissant::croissant::migrate_file(std::path::Path::new(input), to)
+                .and_then(|migration| {
+                    let json = serde_json::to_string_pretty(&migration.document)?;
+                    match sub_m.get_one::<String>("output") {
+                        Some(output) => std::fs::write(output, format!("{json}\n"))?,
+                        None => println!("{json}"),
+                    }
+                    Ok(migration)
+                });
+            match result {
+                Ok(migration) => {
+                    eprintln!(
+                        "Migrated from Croissant {} to {} with {} change(s):",
+                        migration.from,
+                        migration.to,
+                        migration.changes.len()
+                    );
+                    for change in &migration.changes {
+                        eprintln!("  -  [{}] {}", change.path, change.message);
+                    }
+                }
+                Err(e) => {
+                    eprintln!("Error migrating metadata: {e}");
+                    std::process::exit(1);
+                }
+            }
+        }
+        Some(("fmt", sub_m)) => {
+            let check = sub_m.get_flag("check");
+            let mut failed = false;
+            for input in sub_m
+                .get_many::<String>("input")
+                .expect("Input JSON-LD files required")
+            {
+                let path = std::path::Path::new(input);
+                let result = if check {
+                    rustcroissant::croissant::is_formatted(path).map(|formatted| !formatted)
+                } else {
+                    rustcroissant::croissant::format_file(path)
+                };
+                match result {
+                    Ok(true) if check => {
+                        println!("Would reformat: {input}");
+                        failed = true;
+                    }
+                    Ok(true) => println!("Reformatted: {input}"),
+                    Ok(false) => {}
+                    Err(e) => {
+                        eprintln!("Error formatting {input}: {e}");
+                        failed = true;
+                    }
+                }
+            }
+            if failed {
+                std::process::exit(1);
+            }
+        }
+        Some(("records", sub_m)) => {
+            let input = sub_m
+                .get_one::<String>("input")
+                .expect("Input JSON-LD file required");
+            let record_set = sub_m
+                .get_one::<String>("record-set")
+                .expect("Record set id required");
+            let limit = sub_m.get_one::<usize>("limit").copied();
+
+            let dataset = rustcroissant::croissant::Dataset::from_file(std::path::Path::new(input))
+                .map(|dataset| match sub_m.get_one::<String>("data-dir") {
+                    Some(dir) => {
+                        rustcroissant::croissant::Dataset::new(dataset.metadata().clone(), dir)
+                    }
+                    None => dataset,
+                });
+            let result = dataset.and_then(|dataset| {
+                for record in dataset
+                    .records(record_set)?
+                    .take(limit.unwrap_or(usize::MAX))
+                {
+                    println!("{}", serde_json::to_string(&record?)?);
                 }
+                Ok(())
+            });
+            if let Err(e) = result {
+                eprintln!("Error reading records: {e}");
+                std::process::exit(1);
             }
         }
         _ => {